@@ -0,0 +1,74 @@
+// Translate hot Intcode programs into Rust ahead of time (see
+// src/translate.rs). The virtual machine picks up the native version when it's
+// given one of these programs.
+
+use std::env;
+use std::fs;
+use std::path::Path;
+
+#[path = "src/translate.rs"]
+mod translate;
+
+// Small programs that exercise the translator, so the tests can check the
+// native code against the interpreter. They're only compiled into the tests.
+const SAMPLES: &[&[i128]] = &[
+    // Quine
+    &[
+        109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99,
+    ],
+    // Compare the input to 8
+    &[
+        3, 21, 1008, 21, 8, 20, 1005, 20, 22, 107, 8, 21, 20, 1006, 20, 31, 1106, 0, 36, 98, 0, 0,
+        1002, 21, 125, 20, 4, 20, 1105, 1, 46, 104, 999, 1105, 1, 46, 1101, 1000, 1, 20, 4, 20,
+        1105, 1, 46, 98, 99,
+    ],
+    // Echo forever
+    &[3, 100, 4, 100, 1105, 1, 0],
+    // Call a subroutine, returning through a computed jump
+    &[
+        109, 100, 21101, 9, 0, 0, 1105, 1, 20, 4, 50, 99, 0, 0, 0, 0, 0, 0, 0, 0, 1101, 40, 2, 50,
+        2106, 0, 0,
+    ],
+    // Computed jump to an address that isn't a leader
+    &[1101, 4, 5, 50, 106, 0, 50, 104, 1, 104, 2, 99],
+    // Relative write into code, turning the second output into a halt
+    &[109, 0, 21101, 0, 99, 8, 104, 7, 104, 5, 99],
+];
+
+// Puzzle inputs that are run many times over, translated when present
+const INPUTS: &[&str] = &["day19.txt"];
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=src/translate.rs");
+
+    let samples: Vec<Vec<i128>> = SAMPLES.iter().map(|p| p.to_vec()).collect();
+    let mut programs: Vec<Vec<i128>> = Vec::new();
+
+    let data = Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join("data");
+    if data.is_dir() {
+        println!("cargo:rerun-if-changed={}", data.display());
+    }
+    for input in INPUTS {
+        let path = data.join(input);
+        if let Ok(source) = fs::read_to_string(&path) {
+            let program: Result<Vec<i128>, _> = source
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|s| !s.is_empty())
+                .map(|s| s.parse::<i128>())
+                .collect();
+            match program {
+                Ok(program) => programs.push(program),
+                Err(_) => println!("cargo:warning=Can't parse {}", path.display()),
+            }
+        }
+    }
+
+    let (code, errors) = translate::module(&programs, &samples);
+    for (name, error) in errors {
+        println!("cargo:warning=Not translating {}: {}", name, error);
+    }
+
+    let out = Path::new(&env::var("OUT_DIR").unwrap()).join("native.rs");
+    fs::write(out, code).expect("Can't write native.rs");
+}
@@ -0,0 +1,225 @@
+use std::io::{self, Write};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// Where a simulation sends its frames, each as a function that draws it.
+// Frames are only drawn when something is going to show or record them, so
+// solving normally (with no_frames) doesn't pay for it.
+pub type Frames<'a> = &'a mut dyn FnMut(&dyn Fn() -> String);
+
+// For running a simulation without watching it
+pub fn no_frames(_: &dyn Fn() -> String) {}
+
+// ANSI escape codes to hide and show the cursor, move it to the top left,
+// and clear to the end of the line or the screen
+const HIDE_CURSOR: &str = "\x1b[?25l";
+const SHOW_CURSOR: &str = "\x1b[?25h";
+const CLEAR_SCREEN: &str = "\x1b[2J";
+const HOME: &str = "\x1b[H";
+const CLEAR_LINE: &str = "\x1b[K";
+const CLEAR_BELOW: &str = "\x1b[J";
+
+// Plays frames on a terminal at a steady rate, or as fast as they come when
+// headless, and writes each one to a recording too if there is one
+pub struct Animation<'a> {
+    screen: Option<Box<dyn Write + 'a>>,
+    record: Option<Box<dyn Write + 'a>>,
+    interval: Duration,
+    next: Instant,
+    frames: usize,
+    error: Option<io::Error>,
+    // Whether the cursor needs putting back
+    hidden: bool,
+}
+
+impl<'a> Animation<'a> {
+    // Show frames on the screen at up to fps a second, unless it's None
+    pub fn new(screen: Option<Box<dyn Write + 'a>>, fps: u32) -> Animation<'a> {
+        Animation {
+            screen,
+            record: None,
+            interval: Duration::from_secs(1) / fps.max(1),
+            next: Instant::now(),
+            frames: 0,
+            error: None,
+            hidden: false,
+        }
+    }
+
+    // Also write every frame to record, one after another
+    pub fn record(mut self, record: Box<dyn Write + 'a>) -> Animation<'a> {
+        self.record = Some(record);
+        self
+    }
+
+    pub fn frame(&mut self, draw: &dyn Fn() -> String) {
+        // Once something can't be written there's no point drawing more
+        if self.error.is_some() || (self.screen.is_none() && self.record.is_none()) {
+            self.frames += 1;
+            return;
+        }
+
+        let text = draw();
+        self.frames += 1;
+        if let Err(error) = self.show(&text) {
+            self.error = Some(error);
+        }
+    }
+
+    fn show(&mut self, text: &str) -> io::Result<()> {
+        if let Some(record) = &mut self.record {
+            writeln!(record, "--- frame {} ---", self.frames)?;
+            write!(record, "{}", text)?;
+            if !text.ends_with('\n') {
+                writeln!(record)?;
+            }
+        }
+
+        if let Some(screen) = &mut self.screen {
+            // Wait until it's time for this frame, so fast simulations can
+            // still be followed
+            let now = Instant::now();
+            if self.next > now {
+                thread::sleep(self.next - now);
+            }
+            self.next = Instant::now() + self.interval;
+
+            if self.frames == 1 {
+                self.hidden = true;
+                write!(screen, "{}{}", HIDE_CURSOR, CLEAR_SCREEN)?;
+            }
+            write!(screen, "{}", HOME)?;
+            for line in text.lines() {
+                writeln!(screen, "{}{}", line, CLEAR_LINE)?;
+            }
+            write!(screen, "{}", CLEAR_BELOW)?;
+            screen.flush()?;
+        }
+        Ok(())
+    }
+
+    fn show_cursor(&mut self) -> io::Result<()> {
+        if let (true, Some(screen)) = (self.hidden, &mut self.screen) {
+            self.hidden = false;
+            write!(screen, "{}", SHOW_CURSOR)?;
+            screen.flush()?;
+        }
+        Ok(())
+    }
+
+    // Put the cursor back, returning how many frames there were
+    pub fn finish(mut self) -> io::Result<usize> {
+        let shown = self.show_cursor();
+        if let Some(error) = self.error.take() {
+            return Err(error);
+        }
+        shown?;
+        if let Some(screen) = &mut self.screen {
+            screen.flush()?;
+        }
+        if let Some(record) = &mut self.record {
+            record.flush()?;
+        }
+        Ok(self.frames)
+    }
+}
+
+// A simulation that panics, or an animation that's never finished, still
+// leaves the terminal with its cursor
+impl Drop for Animation<'_> {
+    fn drop(&mut self) {
+        let _ = self.show_cursor();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::panic::{self, AssertUnwindSafe};
+
+    // Count to three, a frame at a time
+    fn count(frames: Frames) {
+        for i in 1..=3 {
+            frames(&|| format!("{}\n", i));
+        }
+    }
+
+    #[test]
+    fn records_frames() {
+        let mut recording: Vec<u8> = Vec::new();
+        let mut animation = Animation::new(None, 1000).record(Box::new(&mut recording));
+        count(&mut |draw| animation.frame(draw));
+        assert_eq!(animation.finish().unwrap(), 3);
+        assert_eq!(
+            String::from_utf8(recording).unwrap(),
+            "--- frame 1 ---\n1\n--- frame 2 ---\n2\n--- frame 3 ---\n3\n"
+        );
+    }
+
+    #[test]
+    fn draws_over_the_last_frame() {
+        let mut screen: Vec<u8> = Vec::new();
+        let mut animation = Animation::new(Some(Box::new(&mut screen)), 1000);
+        count(&mut |draw| animation.frame(draw));
+        animation.finish().unwrap();
+        assert_eq!(
+            String::from_utf8(screen).unwrap(),
+            "\x1b[?25l\x1b[2J\
+             \x1b[H1\x1b[K\n\x1b[J\
+             \x1b[H2\x1b[K\n\x1b[J\
+             \x1b[H3\x1b[K\n\x1b[J\
+             \x1b[?25h"
+        );
+    }
+
+    // Takes a number of bytes, then fails
+    struct Full(usize);
+
+    impl Write for Full {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if buf.len() > self.0 {
+                return Err(io::Error::other("disk full"));
+            }
+            self.0 -= buf.len();
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn puts_the_cursor_back() {
+        // After the recording fails
+        let mut screen: Vec<u8> = Vec::new();
+        let mut animation =
+            Animation::new(Some(Box::new(&mut screen)), 1000).record(Box::new(Full(20)));
+        count(&mut |draw| animation.frame(draw));
+        assert_eq!(animation.finish().unwrap_err().to_string(), "disk full");
+        assert!(String::from_utf8(screen).unwrap().ends_with(SHOW_CURSOR));
+
+        // After a panic part way through
+        let mut screen: Vec<u8> = Vec::new();
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            let mut animation = Animation::new(Some(Box::new(&mut screen)), 1000);
+            animation.frame(&|| "1\n".to_string());
+            panic!("the simulation fell over");
+        }));
+        assert!(result.is_err());
+        let screen = String::from_utf8(screen).unwrap();
+        assert!(screen.starts_with(HIDE_CURSOR));
+        assert!(screen.ends_with(SHOW_CURSOR));
+        assert_eq!(screen.matches(SHOW_CURSOR).count(), 1);
+    }
+
+    #[test]
+    fn headless_frames_are_only_counted() {
+        let mut animation = Animation::new(None, 1);
+        let start = Instant::now();
+        count(&mut |_| animation.frame(&|| panic!("drew a frame")));
+        assert_eq!(animation.finish().unwrap(), 3);
+        assert!(start.elapsed() < Duration::from_millis(500));
+        count(&mut no_frames);
+    }
+}
@@ -0,0 +1,210 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::solution::Answer;
+
+// The expected answers for each day and part, read from a small subset of
+// TOML:
+//
+//   [day08]
+//   part1 = 1360
+//   part2 = """
+//   x xx ...
+//   """
+//
+// Values are integers, "strings" or """multi-line strings""".
+#[derive(Default, Debug)]
+pub struct Answers {
+    expected: HashMap<(usize, u8), String>,
+}
+
+// How a day's answer compares to the recorded one
+#[derive(PartialEq, Eq, Debug)]
+pub enum Verdict {
+    Pass,
+    Fail { expected: String, actual: String },
+    Missing { actual: String },
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = |s: &String| -> String {
+            if s.contains('\n') {
+                format!("\n{}\n", s)
+            } else {
+                s.clone()
+            }
+        };
+        match self {
+            Verdict::Pass => write!(f, "pass"),
+            Verdict::Fail { expected, actual } => write!(
+                f,
+                "FAIL expected {}, got {}",
+                value(expected),
+                value(actual)
+            ),
+            Verdict::Missing { actual } => write!(f, "missing, got {}", value(actual)),
+        }
+    }
+}
+
+// Answers are compared without trailing whitespace on each line, or blank
+// lines around them, so rendered letters don't depend on how they're padded
+fn normalize(s: &str) -> String {
+    let lines: Vec<&str> = s.lines().map(str::trim_end).collect();
+    lines.join("\n").trim_matches('\n').to_string()
+}
+
+impl Answers {
+    pub fn parse(source: &str) -> Result<Answers, String> {
+        let mut answers = Answers::default();
+        let mut day: Option<usize> = None;
+
+        let mut lines = source.lines().enumerate();
+        while let Some((l, line)) = lines.next() {
+            let error = |message: &str| format!("line {}: {}", l + 1, message);
+
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if let Some(section) = line.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+                let d = section
+                    .trim()
+                    .strip_prefix("day")
+                    .and_then(|d| d.parse::<usize>().ok())
+                    .filter(|d| (1..=25).contains(d))
+                    .ok_or_else(|| error(&format!("invalid section '{}'", section)))?;
+                day = Some(d);
+                continue;
+            }
+
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| error("expected 'part1 = ...' or '[dayNN]'"))?;
+            let day = day.ok_or_else(|| error("answer outside of a [dayNN] section"))?;
+            let part = match key.trim() {
+                "part1" => 1,
+                "part2" => 2,
+                k => return Err(error(&format!("unknown key '{}'", k))),
+            };
+
+            let value = value.trim();
+            let value = if let Some(rest) = value.strip_prefix("\"\"\"") {
+                let mut text = String::new();
+                if let Some(end) = rest.strip_suffix("\"\"\"") {
+                    text.push_str(end);
+                } else {
+                    text.push_str(rest);
+                    loop {
+                        let (_, next) = lines.next().ok_or_else(|| error("unclosed \"\"\""))?;
+                        text.push('\n');
+                        if let Some(end) = next.trim_end().strip_suffix("\"\"\"") {
+                            text.push_str(end);
+                            break;
+                        }
+                        text.push_str(next);
+                    }
+                }
+                text
+            } else if let Some(rest) = value.strip_prefix('"') {
+                rest.strip_suffix('"')
+                    .ok_or_else(|| error("unclosed string"))?
+                    .to_string()
+            } else if value.parse::<i128>().is_ok() {
+                value.to_string()
+            } else {
+                return Err(error(&format!("invalid value '{}'", value)));
+            };
+
+            if answers
+                .expected
+                .insert((day, part), normalize(&value))
+                .is_some()
+            {
+                return Err(error(&format!("day {} part {} is listed twice", day, part)));
+            }
+        }
+
+        Ok(answers)
+    }
+
+    pub fn check(&self, day: usize, part: u8, answer: &Answer) -> Verdict {
+        let actual = normalize(&answer.to_string());
+        match self.expected.get(&(day, part)) {
+            Some(expected) if *expected == actual => Verdict::Pass,
+            Some(expected) => Verdict::Fail {
+                expected: expected.clone(),
+                actual,
+            },
+            // A part without a puzzle has nothing to record
+            None if *answer == Answer::None => Verdict::Pass,
+            None => Verdict::Missing { actual },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ANSWERS: &str = r#"
+# Recorded answers
+[day01]
+part1 = 3266516
+part2 = "4896902"
+
+[day08]
+part1 = 1360
+part2 = """
+ xx  x
+x  x x
+"""
+"#;
+
+    #[test]
+    fn checks_recorded_answers() {
+        let answers = Answers::parse(ANSWERS).unwrap();
+        assert_eq!(answers.check(1, 1, &Answer::Number(3266516)), Verdict::Pass);
+        assert_eq!(answers.check(1, 2, &Answer::Number(4896902)), Verdict::Pass);
+        assert_eq!(
+            answers.check(1, 1, &Answer::Number(3266517)),
+            Verdict::Fail {
+                expected: "3266516".to_string(),
+                actual: "3266517".to_string()
+            }
+        );
+        assert_eq!(
+            answers.check(8, 2, &Answer::Rendered("\n xx  x   \nx  x x\n".to_string())),
+            Verdict::Pass
+        );
+        assert_eq!(
+            answers.check(2, 1, &Answer::Number(1)),
+            Verdict::Missing {
+                actual: "1".to_string()
+            }
+        );
+        assert_eq!(answers.check(25, 2, &Answer::None), Verdict::Pass);
+    }
+
+    #[test]
+    fn reports_bad_files() {
+        let error = |source: &str| Answers::parse(source).unwrap_err();
+        assert_eq!(
+            error("part1 = 1"),
+            "line 1: answer outside of a [dayNN] section"
+        );
+        assert_eq!(error("[day26]"), "line 1: invalid section 'day26'");
+        assert_eq!(error("[day01]\npart3 = 1"), "line 2: unknown key 'part3'");
+        assert_eq!(error("[day01]\npart1 = one"), "line 2: invalid value 'one'");
+        assert_eq!(
+            error("[day01]\npart1 = 1\npart1 = 2"),
+            "line 3: day 1 part 1 is listed twice"
+        );
+        assert_eq!(
+            error("[day08]\npart2 = \"\"\"\nx"),
+            "line 2: unclosed \"\"\""
+        );
+    }
+}
@@ -0,0 +1,276 @@
+use std::collections::BTreeMap;
+use std::fmt::{self, Write};
+use std::time::Duration;
+
+use crate::solution::Solved;
+
+// How a benchmark is run, and what counts as a slowdown
+pub struct Settings {
+    pub runs: usize,
+    pub warmup: usize,
+    pub baseline: String,
+    // Percent slower than the baseline median before a step is flagged
+    pub threshold: f64,
+    // Record these results as the new baseline
+    pub save: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Settings {
+        Settings {
+            runs: 10,
+            warmup: 2,
+            baseline: "bench-baseline.txt".to_string(),
+            threshold: 10.0,
+            save: false,
+        }
+    }
+}
+
+// Timings for one step (parsing, or a part) over all the runs
+#[derive(PartialEq, Eq, Debug)]
+pub struct Stats {
+    pub min: Duration,
+    pub median: Duration,
+    pub p95: Duration,
+}
+
+impl Stats {
+    pub fn from_samples(samples: &[Duration]) -> Stats {
+        let mut sorted = samples.to_vec();
+        sorted.sort();
+
+        // Nearest rank, so the p95 of a few runs is the slowest one
+        let rank = |p: usize| -> Duration {
+            let i = (p * sorted.len()).div_ceil(100);
+            sorted[i.max(1) - 1]
+        };
+        Stats {
+            min: sorted[0],
+            median: sorted[(sorted.len() - 1) / 2],
+            p95: rank(95),
+        }
+    }
+}
+
+// Solve a day again and again, throwing away the warm-up runs, and work out
+// the statistics for parsing and each part
+pub fn measure<F>(settings: &Settings, mut solve: F) -> Result<Vec<(String, Stats)>, String>
+where
+    F: FnMut() -> Result<Solved, String>,
+{
+    for _ in 0..settings.warmup {
+        solve()?;
+    }
+
+    let mut samples: Vec<(String, Vec<Duration>)> = Vec::new();
+    for run in 0..settings.runs {
+        let solved = solve()?;
+        let steps = std::iter::once(("parse".to_string(), solved.parse)).chain(
+            solved
+                .parts
+                .iter()
+                .map(|(part, _, elapsed)| (format!("part{}", part), *elapsed)),
+        );
+        for (i, (step, elapsed)) in steps.enumerate() {
+            if run == 0 {
+                samples.push((step, Vec::new()));
+            }
+            samples[i].1.push(elapsed);
+        }
+    }
+
+    Ok(samples
+        .into_iter()
+        .map(|(step, samples)| (step, Stats::from_samples(&samples)))
+        .collect())
+}
+
+// The median time of each day's steps from an earlier run, one per line:
+//
+//   18 part1 812345678
+//
+// with the time in nanoseconds
+#[derive(Default, Debug)]
+pub struct Baseline {
+    medians: BTreeMap<(usize, String), Duration>,
+}
+
+impl Baseline {
+    pub fn parse(source: &str) -> Result<Baseline, String> {
+        let mut baseline = Baseline::default();
+        for (l, line) in source.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let entry = match fields[..] {
+                [day, step, nanos] => day
+                    .parse::<usize>()
+                    .ok()
+                    .zip(nanos.parse::<u64>().ok())
+                    .map(|(day, nanos)| ((day, step.to_string()), Duration::from_nanos(nanos))),
+                _ => None,
+            };
+            let (key, median) = entry.ok_or(format!(
+                "line {}: expected '<day> <step> <nanoseconds>'",
+                l + 1
+            ))?;
+            baseline.medians.insert(key, median);
+        }
+        Ok(baseline)
+    }
+
+    pub fn get(&self, day: usize, step: &str) -> Option<Duration> {
+        self.medians.get(&(day, step.to_string())).copied()
+    }
+
+    pub fn set(&mut self, day: usize, step: &str, median: Duration) {
+        self.medians.insert((day, step.to_string()), median);
+    }
+
+    pub fn contains_day(&self, day: usize) -> bool {
+        self.medians.keys().any(|(d, _)| *d == day)
+    }
+}
+
+impl fmt::Display for Baseline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "# day step median-nanoseconds")?;
+        for ((day, step), median) in &self.medians {
+            writeln!(f, "{} {} {}", day, step, median.as_nanos())?;
+        }
+        Ok(())
+    }
+}
+
+fn format_duration(d: Duration) -> String {
+    format!("{:.3}ms", d.as_secs_f64() * 1000.0)
+}
+
+// A table of the statistics for each step, compared to the baseline. Returns
+// the table and whether any step got slower than the threshold allows.
+pub fn report(
+    day: usize,
+    results: &[(String, Stats)],
+    baseline: &Baseline,
+    threshold: f64,
+) -> (String, bool) {
+    let mut s = String::new();
+    let mut slower = false;
+
+    writeln!(
+        s,
+        "{:<6} {:>12} {:>12} {:>12} {:>12}",
+        "Step", "Min", "Median", "P95", "Baseline"
+    )
+    .unwrap();
+    for (step, stats) in results {
+        let comparison = match baseline.get(day, step) {
+            Some(median) => {
+                let change = if median.is_zero() {
+                    0.0
+                } else {
+                    (stats.median.as_secs_f64() / median.as_secs_f64() - 1.0) * 100.0
+                };
+                let flag = if change > threshold {
+                    slower = true;
+                    " SLOWER"
+                } else {
+                    ""
+                };
+                format!("{} ({:+.1}%){}", format_duration(median), change, flag)
+            }
+            None => "-".to_string(),
+        };
+        writeln!(
+            s,
+            "{:<6} {:>12} {:>12} {:>12} {:>12}",
+            step,
+            format_duration(stats.min),
+            format_duration(stats.median),
+            format_duration(stats.p95),
+            comparison
+        )
+        .unwrap();
+    }
+    (s, slower)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solution::Answer;
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn works_out_statistics() {
+        let samples: Vec<Duration> = [7, 1, 5, 3, 9, 2, 8, 4, 6, 10]
+            .iter()
+            .map(|&m| ms(m))
+            .collect();
+        assert_eq!(
+            Stats::from_samples(&samples),
+            Stats {
+                min: ms(1),
+                median: ms(5),
+                p95: ms(10)
+            }
+        );
+        assert_eq!(Stats::from_samples(&[ms(3)]).p95, ms(3));
+    }
+
+    #[test]
+    fn measures_every_step() {
+        let settings = Settings {
+            runs: 3,
+            warmup: 1,
+            ..Settings::default()
+        };
+        let mut calls = 0;
+        let results = measure(&settings, || {
+            calls += 1;
+            Ok(Solved {
+                parse: ms(calls),
+                parts: vec![(1, Answer::None, ms(10 * calls))],
+            })
+        })
+        .unwrap();
+
+        assert_eq!(calls, 4);
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].0, "parse");
+        assert_eq!(results[0].1.min, ms(2));
+        assert_eq!(results[1].0, "part1");
+        assert_eq!(results[1].1.median, ms(30));
+    }
+
+    #[test]
+    fn flags_slowdowns_against_the_baseline() {
+        let mut baseline = Baseline::parse("# comment\n18 part1 100000000\n").unwrap();
+        assert_eq!(baseline.get(18, "part1"), Some(ms(100)));
+        assert!(Baseline::parse("18 part1").is_err());
+
+        let stats = |median: u64| Stats {
+            min: ms(median),
+            median: ms(median),
+            p95: ms(median),
+        };
+        let (_, slower) = report(18, &[("part1".to_string(), stats(105))], &baseline, 10.0);
+        assert!(!slower);
+        let (table, slower) = report(18, &[("part1".to_string(), stats(150))], &baseline, 10.0);
+        assert!(slower);
+        assert!(table.contains("+50.0%"));
+
+        baseline.set(18, "part2", ms(1));
+        let saved = Baseline::parse(&baseline.to_string()).unwrap();
+        assert_eq!(saved.get(18, "part2"), Some(ms(1)));
+        assert!(saved.contains_day(18));
+        assert!(!saved.contains_day(17));
+    }
+}
@@ -0,0 +1,270 @@
+// Iterators over the ways to order or choose from a list of items. Each one
+// works out the next arrangement as it's asked for, rather than building
+// them all up front, so even a long list can be started on.
+
+// Every ordering of the items, by Heap's algorithm: each one is the last with
+// a single pair of items swapped. The first is the items as they're given.
+// https://en.wikipedia.org/wiki/Heap%27s_algorithm
+pub struct Permutations<T> {
+    items: Vec<T>,
+    counters: Vec<usize>,
+    i: usize,
+    started: bool,
+}
+
+pub fn permutations<T: Clone>(items: &[T]) -> Permutations<T> {
+    Permutations {
+        items: items.to_vec(),
+        counters: vec![0; items.len()],
+        i: 0,
+        started: false,
+    }
+}
+
+impl<T: Clone> Iterator for Permutations<T> {
+    type Item = Vec<T>;
+
+    fn next(&mut self) -> Option<Vec<T>> {
+        if !self.started {
+            self.started = true;
+            return Some(self.items.clone());
+        }
+
+        while self.i < self.items.len() {
+            let i = self.i;
+            if self.counters[i] < i {
+                if i.is_multiple_of(2) {
+                    self.items.swap(0, i);
+                } else {
+                    self.items.swap(self.counters[i], i);
+                }
+                self.counters[i] += 1;
+                self.i = 0;
+                return Some(self.items.clone());
+            }
+            self.counters[i] = 0;
+            self.i += 1;
+        }
+        None
+    }
+}
+
+// Every way to choose k of the items, keeping them in the order they're
+// given. The choices come in lexicographic order of the items' positions.
+pub struct Combinations<T> {
+    items: Vec<T>,
+    // The positions of the chosen items, or None once they've all been seen
+    chosen: Option<Vec<usize>>,
+}
+
+pub fn combinations<T: Clone>(items: &[T], k: usize) -> Combinations<T> {
+    Combinations {
+        items: items.to_vec(),
+        chosen: if k <= items.len() {
+            Some((0..k).collect())
+        } else {
+            None
+        },
+    }
+}
+
+impl<T: Clone> Iterator for Combinations<T> {
+    type Item = Vec<T>;
+
+    fn next(&mut self) -> Option<Vec<T>> {
+        let items = &self.items;
+        let chosen = self.chosen.as_mut()?;
+        let combination = chosen.iter().map(|&i| items[i].clone()).collect();
+
+        // Move the last position that can go any further along by one, and
+        // the ones after it to just after that
+        let (n, k) = (items.len(), chosen.len());
+        match (0..k).rev().find(|&i| chosen[i] != i + n - k) {
+            Some(i) => {
+                chosen[i] += 1;
+                for j in i + 1..k {
+                    chosen[j] = chosen[j - 1] + 1;
+                }
+            }
+            None => self.chosen = None,
+        }
+        Some(combination)
+    }
+}
+
+// Every subset of the items, smallest first, starting with the empty set
+pub struct Powerset<T> {
+    items: Vec<T>,
+    size: usize,
+    current: Combinations<T>,
+}
+
+pub fn powerset<T: Clone>(items: &[T]) -> Powerset<T> {
+    Powerset {
+        items: items.to_vec(),
+        size: 0,
+        current: combinations(items, 0),
+    }
+}
+
+impl<T: Clone> Iterator for Powerset<T> {
+    type Item = Vec<T>;
+
+    fn next(&mut self) -> Option<Vec<T>> {
+        loop {
+            if let Some(subset) = self.current.next() {
+                return Some(subset);
+            }
+            if self.size == self.items.len() {
+                return None;
+            }
+            self.size += 1;
+            self.current = combinations(&self.items, self.size);
+        }
+    }
+}
+
+// Every subset of the items in Gray code order, where each differs from the
+// last by one item being added or taken away. Starting from the empty set,
+// this yields which item changes each time and whether it's now in the
+// subset, so trying every subset only takes one change per step.
+#[allow(dead_code)]
+pub struct GraySubsets<'a, T> {
+    items: &'a [T],
+    included: Vec<bool>,
+    step: u128,
+}
+
+#[allow(dead_code)]
+pub fn gray_subsets<T>(items: &[T]) -> GraySubsets<'_, T> {
+    GraySubsets {
+        items,
+        included: vec![false; items.len()],
+        step: 0,
+    }
+}
+
+impl<'a, T> Iterator for GraySubsets<'a, T> {
+    type Item = (&'a T, bool);
+
+    fn next(&mut self) -> Option<(&'a T, bool)> {
+        // The item to change at step i is the lowest set bit of i, until
+        // that's past the last item. A u128 can count further than anyone
+        // could wait for.
+        self.step += 1;
+        let i = self.step.trailing_zeros() as usize;
+        if i >= self.items.len() {
+            self.step -= 1;
+            return None;
+        }
+        self.included[i] = !self.included[i];
+        Some((&self.items[i], self.included[i]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn every_ordering() {
+        let all: Vec<Vec<char>> = permutations(&['a', 'b', 'c']).collect();
+        assert_eq!(
+            all,
+            vec![
+                vec!['a', 'b', 'c'],
+                vec!['b', 'a', 'c'],
+                vec!['c', 'a', 'b'],
+                vec!['a', 'c', 'b'],
+                vec!['b', 'c', 'a'],
+                vec!['c', 'b', 'a'],
+            ]
+        );
+
+        let unique: HashSet<Vec<u32>> = permutations(&[0, 1, 2, 3, 4, 5]).collect();
+        assert_eq!(unique.len(), 720);
+        assert_eq!(permutations::<u32>(&[]).count(), 1);
+        assert_eq!(permutations(&[7]).collect::<Vec<_>>(), vec![vec![7]]);
+    }
+
+    #[test]
+    fn every_choice() {
+        let all: Vec<Vec<u32>> = combinations(&[1, 2, 3, 4], 2).collect();
+        assert_eq!(
+            all,
+            vec![
+                vec![1, 2],
+                vec![1, 3],
+                vec![1, 4],
+                vec![2, 3],
+                vec![2, 4],
+                vec![3, 4],
+            ]
+        );
+        assert_eq!(
+            combinations(&[1, 2, 3], 0).collect::<Vec<_>>(),
+            vec![vec![]]
+        );
+        assert_eq!(combinations(&[1, 2, 3], 3).count(), 1);
+        assert_eq!(combinations(&[1, 2, 3], 4).count(), 0);
+        assert_eq!(combinations(&(0..10).collect::<Vec<_>>(), 4).count(), 210);
+    }
+
+    #[test]
+    fn every_subset() {
+        let all: Vec<Vec<char>> = powerset(&['a', 'b', 'c']).collect();
+        assert_eq!(
+            all,
+            vec![
+                vec![],
+                vec!['a'],
+                vec!['b'],
+                vec!['c'],
+                vec!['a', 'b'],
+                vec!['a', 'c'],
+                vec!['b', 'c'],
+                vec!['a', 'b', 'c'],
+            ]
+        );
+        assert_eq!(powerset::<u8>(&[]).count(), 1);
+        assert_eq!(powerset(&[0; 12]).count(), 4096);
+    }
+
+    #[test]
+    fn every_subset_one_change_at_a_time() {
+        let items = ['a', 'b', 'c', 'd'];
+        let mut subset: Vec<char> = Vec::new();
+        let mut seen: HashSet<Vec<char>> = HashSet::new();
+        seen.insert(subset.clone());
+        for (&item, added) in gray_subsets(&items) {
+            if added {
+                subset.push(item);
+            } else {
+                subset.retain(|&i| i != item);
+            }
+            subset.sort();
+            assert!(seen.insert(subset.clone()), "{:?} came up twice", subset);
+        }
+        assert_eq!(seen.len(), 16);
+
+        let changes: Vec<(&char, bool)> = gray_subsets(&items[..2]).collect();
+        assert_eq!(changes, vec![(&'a', true), (&'b', true), (&'a', false)]);
+        assert_eq!(gray_subsets::<char>(&[]).count(), 0);
+    }
+
+    #[test]
+    fn long_lists_are_lazy() {
+        // Far more arrangements than could ever be built up front, or counted
+        // in a u32 or even a u64
+        let items: Vec<u32> = (0..100).collect();
+        assert_eq!(permutations(&items).nth(1).unwrap()[..3], [1, 0, 2]);
+        assert_eq!(combinations(&items, 50).next().unwrap().len(), 50);
+        assert_eq!(powerset(&items).nth(101), Some(vec![0, 1]));
+        let changes: Vec<(&u32, bool)> = gray_subsets(&items).take(4).collect();
+        assert_eq!(
+            changes,
+            vec![(&0, true), (&1, true), (&0, false), (&2, true)]
+        );
+    }
+}
@@ -0,0 +1,157 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+// Where a simulation that keeps taking the same step from state to state
+// starts repeating itself: after start steps it reaches a state that comes
+// round again every length steps
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Cycle {
+    pub start: usize,
+    pub length: usize,
+}
+
+impl Cycle {
+    // The earliest step with the same state as step n
+    pub fn equivalent(&self, n: usize) -> usize {
+        if n < self.start {
+            n
+        } else {
+            self.start + (n - self.start) % self.length
+        }
+    }
+
+    // The state after n steps, however large n is, by only running as far as
+    // the equivalent step
+    pub fn extrapolate<S, F>(&self, start: S, step: F, n: usize) -> S
+    where
+        F: FnMut(&S) -> S,
+    {
+        iterate(start, step, self.equivalent(n))
+    }
+}
+
+fn iterate<S, F>(start: S, mut step: F, n: usize) -> S
+where
+    F: FnMut(&S) -> S,
+{
+    let mut state = start;
+    for _ in 0..n {
+        state = step(&state);
+    }
+    state
+}
+
+// Brent's algorithm, which only needs to compare states and keeps two of them
+// at a time, for states too big to remember them all
+// https://en.wikipedia.org/wiki/Cycle_detection#Brent's_algorithm
+pub fn brent<S, F>(start: S, mut step: F) -> Cycle
+where
+    S: Clone + PartialEq,
+    F: FnMut(&S) -> S,
+{
+    // The hare runs ahead of the tortoise, which catches up with it whenever
+    // it's a power of two steps ahead, until the hare laps it
+    let (mut power, mut length) = (1, 1);
+    let mut tortoise = start.clone();
+    let mut hare = step(&start);
+    while tortoise != hare {
+        if power == length {
+            tortoise = hare.clone();
+            power *= 2;
+            length = 0;
+        }
+        hare = step(&hare);
+        length += 1;
+    }
+
+    // Then with the hare a cycle ahead, they meet where the cycle starts
+    let mut tortoise = start.clone();
+    let mut hare = iterate(start, &mut step, length);
+    let mut first = 0;
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        first += 1;
+    }
+
+    Cycle {
+        start: first,
+        length,
+    }
+}
+
+// Remember a key for every state, such as a hash or a compact form of it, and
+// stop at the first one seen twice. Each step is only taken once, which is
+// quicker when the states are small enough to keep.
+pub fn hashed<S, K, F, G>(start: S, mut step: F, mut key: G) -> Cycle
+where
+    K: Eq + Hash,
+    F: FnMut(&S) -> S,
+    G: FnMut(&S) -> K,
+{
+    let mut seen: HashMap<K, usize> = HashMap::new();
+    let mut state = start;
+    let mut n = 0;
+    loop {
+        if let Some(first) = seen.insert(key(&state), n) {
+            return Cycle {
+                start: first,
+                length: n - first,
+            };
+        }
+        state = step(&state);
+        n += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 0, 1, 2, 3, then 4 to 9 over and over
+    fn rho(&n: &u64) -> u64 {
+        if n < 9 {
+            n + 1
+        } else {
+            4
+        }
+    }
+
+    #[test]
+    fn finds_the_cycle() {
+        let expected = Cycle {
+            start: 4,
+            length: 6,
+        };
+        assert_eq!(brent(0, rho), expected);
+        assert_eq!(hashed(0, rho, |&n| n), expected);
+
+        // Starting inside the cycle
+        let expected = Cycle {
+            start: 0,
+            length: 6,
+        };
+        assert_eq!(brent(7, rho), expected);
+        assert_eq!(hashed(7, rho, |&n| n), expected);
+
+        // A state that never changes
+        assert_eq!(brent('x', |&c| c).length, 1);
+    }
+
+    #[test]
+    fn extrapolates() {
+        let cycle = brent(0, rho);
+        assert_eq!(cycle.equivalent(3), 3);
+        assert_eq!(cycle.equivalent(10), 4);
+        assert_eq!(cycle.extrapolate(0, rho, 10), 4);
+        assert_eq!(cycle.extrapolate(0, rho, 1_000_000_000_004), 8);
+
+        let mut steps = 0;
+        let counted = |&n: &u64| {
+            steps += 1;
+            rho(&n)
+        };
+        cycle.extrapolate(0, counted, usize::MAX);
+        assert!(steps < 10);
+    }
+}
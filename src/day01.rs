@@ -1,34 +1,50 @@
-use std::io;
-use std::io::BufRead;
-
-use std::fs;
-
-fn read_inputs(filename: String) -> io::Result<Vec<i32>> {
-    let file_in = fs::File::open(filename)?;
-    let file_reader = io::BufReader::new(file_in);
-    Ok(file_reader
-        .lines()
-        .filter_map(io::Result::ok)
-        .map(|line| line.parse::<i32>().unwrap())
-        .collect())
+use std::error::Error;
+
+use crate::parse;
+use crate::solution::{Answer, Solution};
+
+pub struct Day01 {
+    masses: Vec<i32>,
+}
+
+impl Solution for Day01 {
+    fn parse(input: &str) -> Result<Self, Box<dyn Error>> {
+        let masses = parse::lines(input, parse::number)?;
+        Ok(Day01 { masses })
+    }
+
+    fn part1(&self) -> Answer {
+        let sum = self.masses.iter().fold(0, |acc, x| acc + (x / 3) - 2);
+        sum.into()
+    }
+
+    fn part2(&self) -> Answer {
+        let sum = self.masses.iter().fold(0, |acc, x| {
+            let mut local_sum = 0;
+            let mut fuel = (x / 3) - 2;
+            while fuel > 0 {
+                local_sum += fuel;
+                fuel = (fuel / 3) - 2;
+            }
+            acc + local_sum
+        });
+        sum.into()
+    }
 }
 
-pub fn run() {
-    let inputs = read_inputs("data/day01.txt".to_string()).expect("Can't read file");
-
-    // Part 1
-    let sum = inputs.iter().fold(0, |acc, x| acc + (x / 3) - 2);
-    println!("{} fuel required", sum);
-
-    // Part 2
-    let sum = inputs.iter().fold(0, |acc, x| {
-        let mut local_sum = 0;
-        let mut fuel = (x / 3) - 2;
-        while fuel > 0 {
-            local_sum += fuel;
-            fuel = (fuel / 3) - 2;
-        }
-        acc + local_sum
-    });
-    println!("{} fuel required", sum);
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fuel_for_modules() {
+        let day = Day01::parse("12\n14\n1969\n100756\n").unwrap();
+        assert_eq!(day.part1(), Answer::Number(2 + 2 + 654 + 33583));
+    }
+
+    #[test]
+    fn fuel_for_fuel() {
+        let day = Day01::parse("14\n1969\n100756\n").unwrap();
+        assert_eq!(day.part2(), Answer::Number(2 + 966 + 50346));
+    }
 }
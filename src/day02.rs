@@ -1,103 +1,80 @@
-use std::fs;
-use std::io;
-use std::io::BufRead;
+use std::error::Error;
 
-pub fn load_program(filename: String) -> Vec<usize> {
-    let file_in = fs::File::open(filename).expect("Can't read file");
-    let file_reader = io::BufReader::new(file_in);
-    let line: Vec<String> = file_reader.lines().filter_map(io::Result::ok).collect();
+use crate::goalseek::{self, Variable};
+use crate::intcode::{parse_program, VirtualMachine, VirtualMachineState};
+use crate::solution::{Answer, Solution};
 
-    line[0]
-        .split(',')
-        .map(|line| line.parse::<usize>().unwrap())
-        .collect()
-}
-
-fn run_program(program: &Vec<usize>, noun: usize, verb: usize) -> usize {
-    let mut output: Vec<usize> = program.clone();
-
-    output[1] = noun;
-    output[2] = verb;
-
-    let mut ip = 0;
-    loop {
-        let instruction_size;
-        match output[ip] {
-            1 => {
-                // Opcode 1 adds together numbers read from two positions
-                // and stores the result in a third position.
-
-                // The three integers immediately after the opcode tell
-                // you these three positions - the first two indicate the
-                // positions from which you should read the input values,
-                // and the third indicates the position at which the output
-                // should be stored.
+fn run_program(program: &[i128], noun: i128, verb: i128) -> i128 {
+    let mut vm = VirtualMachine::new(program);
 
-                let load1 = output[ip + 1];
-                let load2 = output[ip + 2];
-                let store = output[ip + 3];
+    vm.set_memory(1, noun);
+    vm.set_memory(2, verb);
 
-                output[store] = output[load1] + output[load2];
+    match vm.run() {
+        VirtualMachineState::Terminated => {
+            // Once the program has halted, its output is available at address 0.
+            vm.get_memory(0)
+        }
+        _ => {
+            panic!("Unexpected program state");
+        }
+    }
+}
 
-                instruction_size = 4;
-            }
-            2 => {
-                // Opcode 2 works exactly like opcode 1, except it multiplies
-                // the two inputs instead of adding them.
+pub struct Day02 {
+    program: Vec<i128>,
+}
 
-                let load1 = output[ip + 1];
-                let load2 = output[ip + 2];
-                let store = output[ip + 3];
+impl Solution for Day02 {
+    fn parse(input: &str) -> Result<Self, Box<dyn Error>> {
+        Ok(Day02 {
+            program: parse_program(input)?,
+        })
+    }
 
-                output[store] = output[load1] * output[load2];
+    fn part1(&self) -> Answer {
+        // Before running the program, replace position 1 with the value 12 and
+        // replace position 2 with the value 2
+        run_program(&self.program, 12, 2).into()
+    }
 
-                instruction_size = 4;
+    fn part2(&self) -> Answer {
+        // Find the input noun and verb that cause the program to produce the
+        // output 19690720. What is 100 * noun + verb?
+
+        // Each of the two input values will be between 0 and 99, inclusive.
+        let variables = vec![
+            Variable {
+                address: 1,
+                values: 0..100,
+            },
+            Variable {
+                address: 2,
+                values: 0..100,
+            },
+        ];
+        match goalseek::solve(&self.program, &variables, 0, 19690720) {
+            Some(values) => {
+                let (noun, verb) = (values[0], values[1]);
+                (100 * noun + verb).into()
             }
-            99 => {
-                // Opcode 99 means that the program is finished and should
-                // immediately halt.
-                break;
-            }
-            _ => {
-                // Encountering an unknown opcode means something went wrong.
-                panic!("Unknown opcode")
+            None => {
+                panic!("No noun and verb produce the output");
             }
         }
-        // After an instruction finishes, the instruction pointer increases by
-        // the number of values in the instruction.
-        ip += instruction_size;
     }
-
-    // Once the program has halted, its output is available at address 0.
-    output[0]
 }
 
-pub fn run() {
-    let program = load_program("data/day02.txt".to_string());
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    // Part 1
+    #[test]
+    fn example_program() {
+        let program = parse_program("1,9,10,3,2,3,11,0,99,30,40,50").unwrap();
+        assert_eq!(run_program(&program, 9, 10), 3500);
 
-    // Before running the program, replace position 1 with the value 12 and
-    // replace position 2 with the value 2
-    let part1 = run_program(&program, 12, 2);
-    println!("{}", part1);
-
-    // Part 2
-
-    // Find the input noun and verb that cause the program to produce the
-    // output 19690720. What is 100 * noun + verb?
-    let mut found = false;
-    for noun in 1..99 {
-        for verb in 1..99 {
-            let part2 = run_program(&program, noun, verb);
-            if part2 == 19690720 {
-                println!("100 * {} + {} = {}", noun, verb, 100 * noun + verb);
-                found = true;
-                break;
-            }
-        }
-        if found {
-            break;
-        }
+        let program = parse_program("1,1,1,4,99,5,6,0,99").unwrap();
+        assert_eq!(run_program(&program, 1, 1), 30);
     }
 }
@@ -1,170 +1,133 @@
-use std::io;
-use std::io::BufRead;
-
-use std::cmp;
-use std::fmt;
-use std::fs;
-use std::hash;
-use std::ops;
+use std::error::Error;
 
 use std::collections::HashMap;
 
-fn read_inputs(filename: String) -> io::Result<Vec<Vec<Direction>>> {
-    let file_in = fs::File::open(filename)?;
-    let file_reader = io::BufReader::new(file_in);
-    Ok(file_reader
-        .lines()
-        .filter_map(io::Result::ok)
-        .map(|line| {
-            line.split(',')
-                .map(|s| {
-                    let (direction, distance) = s.split_at(1);
-                    let magnitude = distance.parse::<i32>().unwrap();
-                    match direction {
-                        "U" => Direction::Up(magnitude),
-                        "D" => Direction::Down(magnitude),
-                        "L" => Direction::Left(magnitude),
-                        "R" => Direction::Right(magnitude),
-                        _ => panic!("Unknown direction"),
-                    }
-                })
-                .collect()
+use crate::geom::{Direction, Point};
+use crate::parse::{self, Reason};
+use crate::solution::{Answer, Solution};
+
+// A wire's steps, like "R8,U5,L5,D3"
+fn parse_wire(line: &str) -> Result<Vec<(Direction, i32)>, Reason> {
+    line.split(',')
+        .map(|step| {
+            let direction = match step.chars().next() {
+                Some('U') => Direction::North,
+                Some('D') => Direction::South,
+                Some('L') => Direction::West,
+                Some('R') => Direction::East,
+                _ => {
+                    return Err(Reason::Unexpected {
+                        expected: "a step like 'R8'".to_string(),
+                        found: step.to_string(),
+                    })
+                }
+            };
+            Ok((direction, parse::number(&step[1..])?))
         })
-        .collect())
-}
-
-enum Direction {
-    Up(i32),
-    Down(i32),
-    Left(i32),
-    Right(i32),
+        .collect()
 }
 
-#[derive(Copy, Clone)]
-struct Point {
-    x: i32,
-    y: i32,
-}
+fn path(directions: &[(Direction, i32)]) -> HashMap<Point, i32> {
+    let mut visited = HashMap::new();
+    let mut p = Point::ORIGIN;
+    let mut steps = 0;
 
-impl fmt::Display for Point {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{},{}", self.x, self.y)
+    for &(direction, len) in directions {
+        for _ in 1..=len {
+            p = p.step(direction);
+            steps += 1;
+            visited.entry(p).or_insert(steps);
+        }
     }
+    visited
 }
 
-impl hash::Hash for Point {
-    fn hash<H: hash::Hasher>(&self, state: &mut H) {
-        self.x.hash(state);
-        self.y.hash(state);
-    }
+pub struct Day03 {
+    path_a: HashMap<Point, i32>,
+    path_b: HashMap<Point, i32>,
 }
 
-impl cmp::PartialEq for Point {
-    fn eq(&self, other: &Self) -> bool {
-        self.x == other.x && self.y == other.y
+impl Day03 {
+    fn intersections(&self) -> impl Iterator<Item = &Point> {
+        self.path_a
+            .keys()
+            .filter(move |p| self.path_b.contains_key(p))
     }
 }
 
-impl cmp::Eq for Point {}
+impl Solution for Day03 {
+    fn parse(input: &str) -> Result<Self, Box<dyn Error>> {
+        let wires = parse::lines(input, parse_wire)?;
 
-impl ops::Add for Point {
-    type Output = Self;
-
-    fn add(self, other: Self) -> Self {
-        Self {
-            x: self.x + other.x,
-            y: self.y + other.y,
+        if wires.len() != 2 {
+            return Err(format!("Expected 2 wires, found {}", wires.len()).into());
         }
-    }
-}
 
-fn manhatten_distance(a: Point, b: Point) -> i32 {
-    (a.x - b.x).abs() + (a.y - b.y).abs()
-}
-
-fn path(directions: &Vec<Direction>) -> HashMap<Point, i32> {
-    let mut visited = HashMap::new();
-    let mut p = Point { x: 0, y: 0 };
-    let mut steps = 0;
-
-    for d in directions {
-        let dp;
-        let len;
-
-        match d {
-            Direction::Up(distance) => {
-                dp = Point { x: 0, y: -1 };
-                len = *distance;
-            }
-            Direction::Down(distance) => {
-                dp = Point { x: 0, y: 1 };
-                len = *distance;
-            }
-            Direction::Left(distance) => {
-                dp = Point { x: -1, y: 0 };
-                len = *distance;
-            }
-            Direction::Right(distance) => {
-                dp = Point { x: 1, y: 0 };
-                len = *distance;
-            }
-        }
-        for _ in 1..=len {
-            p = p + dp;
-            steps += 1;
-            visited.entry(p).or_insert(steps);
-        }
+        Ok(Day03 {
+            path_a: path(&wires[0]),
+            path_b: path(&wires[1]),
+        })
     }
-    visited
-}
 
-pub fn run() {
-    let inputs = read_inputs("data/day03.txt".to_string()).expect("Can't read file");
+    fn part1(&self) -> Answer {
+        self.intersections()
+            .map(|intersection| intersection.manhattan(Point::ORIGIN))
+            .min()
+            .expect("The wires never cross")
+            .into()
+    }
 
-    if inputs.len() != 2 {
-        panic!("Bad file length");
+    fn part2(&self) -> Answer {
+        self.intersections()
+            .map(|intersection| self.path_a[intersection] + self.path_b[intersection])
+            .min()
+            .expect("The wires never cross")
+            .into()
     }
+}
 
-    let path_a = path(&inputs[0]);
-    let path_b = path(&inputs[1]);
-
-    let intersections: Vec<(&Point, &i32)> =
-        path_a.iter().filter(|p| path_b.contains_key(p.0)).collect();
-
-    let start = Point { x: 0, y: 0 };
-    let mut min_intersection = None;
-    let mut min_distance: i32 = 0;
-
-    for intersection in &intersections {
-        if min_intersection == None {
-            min_intersection = Some(*intersection.0);
-            min_distance = manhatten_distance(start, min_intersection.unwrap());
-        } else {
-            let distance = manhatten_distance(start, *intersection.0);
-            if distance < min_distance {
-                min_intersection = Some(*intersection.0);
-                min_distance = distance;
-            }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLES: [(&str, i128, i128); 3] = [
+        ("R8,U5,L5,D3\nU7,R6,D4,L4\n", 6, 30),
+        (
+            "R75,D30,R83,U83,L12,D49,R71,U7,L72\nU62,R66,U55,R34,D71,R55,D58,R83\n",
+            159,
+            610,
+        ),
+        (
+            "R98,U47,R26,D63,R33,U87,L62,D20,R33,U53,R51\nU98,R91,D20,R16,D67,R40,U7,R15,U6,R7\n",
+            135,
+            410,
+        ),
+    ];
+
+    #[test]
+    fn closest_intersection() {
+        for (wires, distance, _) in EXAMPLES.iter() {
+            let day = Day03::parse(wires).unwrap();
+            assert_eq!(day.part1(), Answer::Number(*distance));
         }
     }
 
-    println!("{}", min_distance);
-
-    min_intersection = None;
-    min_distance = 0;
-
-    for intersection in &intersections {
-        if min_intersection == None {
-            min_intersection = Some(*intersection.0);
-            min_distance = path_a[intersection.0] + path_b[intersection.0];
-        } else {
-            let distance = path_a[intersection.0] + path_b[intersection.0];
-            if distance < min_distance {
-                min_intersection = Some(*intersection.0);
-                min_distance = distance;
-            }
+    #[test]
+    fn fewest_combined_steps() {
+        for (wires, _, steps) in EXAMPLES.iter() {
+            let day = Day03::parse(wires).unwrap();
+            assert_eq!(day.part2(), Answer::Number(*steps));
         }
     }
 
-    println!("{}", min_distance);
+    #[test]
+    fn rejects_bad_steps() {
+        let error = Day03::parse("R8,U5\nU7,X6\n").err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "line 2: expected a step like 'R8', found 'X6'"
+        );
+        let error = Day03::parse("R8,U5\nU7,R\n").err().unwrap();
+        assert_eq!(error.to_string(), "line 2: '' isn't a number");
+    }
 }
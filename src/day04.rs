@@ -1,65 +1,95 @@
-use std::fs;
+use std::error::Error;
 
-fn read_inputs(filename: String) -> (u32, u32) {
-    let values: Vec<u32> = fs::read_to_string(filename)
-        .expect("Can't read file")
-        .split('-')
-        .map(|s| s.parse::<u32>().unwrap())
+use crate::solution::{Answer, Solution};
+
+// Check a password against the rules for each part
+fn check_password(n: u32) -> (bool, bool) {
+    // A few key facts about the password:
+    //   - Two adjacent digits are the same (like 22 in 122345).
+    //   - Going from left to right, the digits never decrease;
+    //     they only ever increase or stay the same (like 111123 or 135679).
+
+    // one more important detail:
+    //   - The two adjacent matching digits are not part of a larger group of matching digits.
+
+    let digits: Vec<u32> = n
+        .to_string()
+        .chars()
+        .map(|d| d.to_digit(10).unwrap())
         .collect();
+    let mut increasing = true;
+    let mut adjacent = false;
+    let mut contains_double = false;
+    for i in 1..digits.len() {
+        if digits[i] < digits[i - 1] {
+            increasing = false;
+            break;
+        }
+        if digits[i] == digits[i - 1] {
+            adjacent = true;
 
-    if values.len() != 2 {
-        panic!("Should find to numbers");
+            if ((i == 1) || (digits[i] != digits[i - 2]))
+                && ((i == digits.len() - 1) || (digits[i] != digits[i + 1]))
+            {
+                contains_double = true;
+            }
+        }
     }
 
-    (values[0], values[1])
+    let part1 = increasing && adjacent;
+    (part1, part1 && contains_double)
 }
 
-pub fn run() {
-    let (start, end) = read_inputs("data/day04.txt".to_string());
+pub struct Day04 {
+    start: u32,
+    end: u32,
+}
 
-    let mut found_passwords_part1 = 0;
-    let mut found_passwords_part2 = 0;
+impl Solution for Day04 {
+    fn parse(input: &str) -> Result<Self, Box<dyn Error>> {
+        let values: Vec<u32> = input
+            .trim()
+            .split('-')
+            .map(|s| s.parse::<u32>())
+            .collect::<Result<_, _>>()?;
 
-    for n in start..end {
-        // A few key facts about the password:
-        //   - Two adjacent digits are the same (like 22 in 122345).
-        //   - Going from left to right, the digits never decrease;
-        //     they only ever increase or stay the same (like 111123 or 135679).
+        if values.len() != 2 {
+            return Err("Should find two numbers".into());
+        }
 
-        // one more important detail:
-        //   - The two adjacent matching digits are not part of a larger group of matching digits.
+        Ok(Day04 {
+            start: values[0],
+            end: values[1],
+        })
+    }
 
-        let digits: Vec<u32> = n
-            .to_string()
-            .chars()
-            .map(|d| d.to_digit(10).unwrap())
-            .collect();
-        let mut increasing = true;
-        let mut adjacent = false;
-        let mut contains_double = false;
-        for i in 1..digits.len() {
-            if digits[i] < digits[i - 1] {
-                increasing = false;
-                break;
-            }
-            if digits[i] == digits[i - 1] {
-                adjacent = true;
+    fn part1(&self) -> Answer {
+        (self.start..self.end)
+            .filter(|&n| check_password(n).0)
+            .count()
+            .into()
+    }
 
-                if ((i == 1) || (digits[i] != digits[i - 2]))
-                    && ((i == digits.len() - 1) || (digits[i] != digits[i + 1]))
-                {
-                    contains_double = true;
-                }
-            }
-        }
-        if increasing && adjacent {
-            found_passwords_part1 += 1;
+    fn part2(&self) -> Answer {
+        (self.start..self.end)
+            .filter(|&n| check_password(n).1)
+            .count()
+            .into()
+    }
+}
 
-            if contains_double {
-                found_passwords_part2 += 1;
-            }
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn password_rules() {
+        assert_eq!(check_password(111111), (true, false));
+        assert_eq!(check_password(223450), (false, false));
+        assert_eq!(check_password(123789), (false, false));
+
+        assert_eq!(check_password(112233), (true, true));
+        assert_eq!(check_password(123444), (true, false));
+        assert_eq!(check_password(111122), (true, true));
     }
-    println!("{}", found_passwords_part1);
-    println!("{}", found_passwords_part2);
 }
@@ -1,274 +1,81 @@
-use std::fs;
-use std::io;
-use std::io::BufRead;
+use std::error::Error;
 
-pub fn load_program(filename: String) -> Vec<i32> {
-    let file_in = fs::File::open(filename).expect("Can't read file");
-    let file_reader = io::BufReader::new(file_in);
-    let line: Vec<String> = file_reader.lines().filter_map(io::Result::ok).collect();
+use crate::intcode::{parse_program, VirtualMachine, VirtualMachineState};
+use crate::solution::{Answer, Solution};
 
-    line[0]
-        .split(',')
-        .map(|line| line.parse::<i32>().unwrap())
-        .collect()
-}
-
-fn decode_instruction(i: u32) -> (u32, Vec<u32>) {
-    // Parameter modes are stored in the same value as the instruction's opcode.
-    // The opcode is a two-digit number based only on the ones and tens digit of
-    // the value, that is, the opcode is the rightmost two digits of the first
-    // value in an instruction. Parameter modes are single digits, one per
-    // parameter, read right-to-left from the opcode: the first parameter's mode
-    // is in the hundreds digit, the second parameter's mode is in the thousands
-    // digit, the third parameter's mode is in the ten-thousands digit, and so on.
-    // Any missing modes are 0.
-
-    let mut digits: Vec<u32> = i
-        .to_string()
-        .chars()
-        .map(|d| d.to_digit(10).unwrap())
-        .collect();
-    digits.reverse();
+// Run the diagnostic program for a system, returning the diagnostic code
+fn run_program(program: &[i128], system_id: i128) -> i128 {
+    let mut vm = VirtualMachine::new(program);
+    vm.add_input(system_id);
 
-    let mut i: u32 = 0;
-    let mut modes: Vec<u32> = Vec::new();
-
-    let mut count = 0;
-    for digit in digits {
-        if count == 0 {
-            i = digit;
-        } else if count == 1 {
-            i += digit * 10;
-        } else {
-            if (digit != 0) && (digit != 1) {
-                panic!("Invalid mode");
+    let mut outputs: Vec<i128> = Vec::new();
+    loop {
+        match vm.run() {
+            VirtualMachineState::Output(v) => {
+                outputs.push(v);
+            }
+            VirtualMachineState::Terminated => {
+                break;
+            }
+            VirtualMachineState::WaitForInput => {
+                panic!("Unexpected request for input");
             }
-            modes.push(digit);
         }
-        count += 1;
     }
 
-    (i, modes)
-}
-
-fn get_parameter(p: i32, m: u32, program: &Vec<i32>) -> i32 {
-    match m {
-        0 => {
-            // mode 0, position mode, causes the parameter to be interpreted as a position
-            let pos = p as usize;
-            program[pos]
-        }
-        1 => {
-            // mode 1, immediate mode, causes a parameter is interpreted as a value
-            p
-        }
-        _ => {
-            panic!("Invalid mode");
-        }
+    // Every output before the diagnostic code is the result of a test, and
+    // should be 0 if the test passed.
+    let (code, tests) = outputs.split_last().expect("No diagnostic code");
+    if let Some(failed) = tests.iter().position(|&t| t != 0) {
+        panic!("Diagnostic test {} failed", failed);
     }
+    *code
 }
 
-fn get_mode(mode: &Vec<u32>, i: usize) -> u32 {
-    if i >= mode.len() {
-        return 0;
-    }
-    mode[i]
+pub struct Day05 {
+    program: Vec<i128>,
 }
 
-fn run_program(program: &Vec<i32>, inputs: &Vec<i32>) {
-    let mut output: Vec<i32> = program.clone();
-    let mut input: Vec<i32> = inputs.clone();
-
-    let mut ip = 0;
-    loop {
-        let mut instruction_size = 0;
-
-        print!("[{}] ", ip);
-
-        let raw_i = output[ip] as u32;
-        let (i, mode) = decode_instruction(raw_i);
-        match i {
-            1 => {
-                // Opcode 1 adds together numbers read from two positions
-                // and stores the result in a third position.
-
-                // The three integers immediately after the opcode tell
-                // you these three positions - the first two indicate the
-                // positions from which you should read the input values,
-                // and the third indicates the position at which the output
-                // should be stored.
-
-                let load1 = output[ip + 1];
-                let load2 = output[ip + 2];
-                let store = output[ip + 3] as usize;
-
-                println!("ADD ({}) {} {} {}", raw_i, load1, load2, store);
-
-                let p1 = get_parameter(load1, get_mode(&mode, 0), &output);
-                let p2 = get_parameter(load2, get_mode(&mode, 1), &output);
-
-                output[store] = p1 + p2;
-
-                instruction_size = 4;
-            }
-            2 => {
-                // Opcode 2 works exactly like opcode 1, except it multiplies
-                // the two inputs instead of adding them.
-
-                let load1 = output[ip + 1];
-                let load2 = output[ip + 2];
-                let store = output[ip + 3] as usize;
-
-                println!("MULT ({}) {} {} {}", raw_i, load1, load2, store);
-
-                let p1 = get_parameter(load1, get_mode(&mode, 0), &output);
-                let p2 = get_parameter(load2, get_mode(&mode, 1), &output);
-
-                output[store] = p1 * p2;
-
-                instruction_size = 4;
-            }
-            3 => {
-                // Opcode 3 takes a single integer as input and saves it to the
-                // position given by its only parameter.
-
-                let store = output[ip + 1] as usize;
-
-                println!("IN ({}) {}", raw_i, store);
-
-                output[store] = input.remove(0);
-
-                instruction_size = 2;
-            }
-            4 => {
-                // Opcode 4 outputs the value of its only parameter.
-
-                let get = output[ip + 1] as usize;
-
-                println!("OUT ({}) {}", raw_i, get);
-
-                println!("{}", output[get]);
-
-                instruction_size = 2;
-            }
-            5 => {
-                // Opcode 5 is jump-if-true: if the first parameter is non-zero,
-                // it sets the instruction pointer to the value from the second
-                // parameter. Otherwise, it does nothing.
-
-                let load1 = output[ip + 1];
-                let load2 = output[ip + 2];
-
-                println!("JUMPIF ({}) {} {}", raw_i, load1, load2);
-
-                let p1 = get_parameter(load1, get_mode(&mode, 0), &output);
-                let p2 = get_parameter(load2, get_mode(&mode, 1), &output);
-
-                if p1 != 0 {
-                    ip = p2 as usize;
-                } else {
-                    instruction_size = 3;
-                }
-            }
-            6 => {
-                // Opcode 6 is jump-if-false: if the first parameter is zero, it
-                // sets the instruction pointer to the value from the second
-                // parameter. Otherwise, it does nothing.
-
-                let load1 = output[ip + 1];
-                let load2 = output[ip + 2];
-
-                println!("JUMPIF! ({}) {} {}", raw_i, load1, load2);
-
-                let p1 = get_parameter(load1, get_mode(&mode, 0), &output);
-                let p2 = get_parameter(load2, get_mode(&mode, 1), &output);
-
-                if p1 == 0 {
-                    ip = p2 as usize;
-                } else {
-                    instruction_size = 3;
-                }
-            }
-            7 => {
-                // Opcode 7 is less than: if the first parameter is less than the
-                // second parameter, it stores 1 in the position given by the
-                // third parameter. Otherwise, it stores 0.
-
-                let load1 = output[ip + 1];
-                let load2 = output[ip + 2];
-                let store = output[ip + 3] as usize;
-
-                println!("LT ({}) {} {}", raw_i, load1, load2);
-
-                let p1 = get_parameter(load1, get_mode(&mode, 0), &output);
-                let p2 = get_parameter(load2, get_mode(&mode, 1), &output);
-
-                if p1 < p2 {
-                    output[store] = 1;
-                } else {
-                    output[store] = 0;
-                }
-
-                instruction_size = 4;
-            }
-            8 => {
-                // Opcode 8 is equals: if the first parameter is equal to the second
-                // parameter, it stores 1 in the position given by the third
-                // parameter. Otherwise, it stores 0.
-
-                let load1 = output[ip + 1];
-                let load2 = output[ip + 2];
-                let store = output[ip + 3] as usize;
-
-                println!("EQ ({}) {} {}", raw_i, load1, load2);
-
-                let p1 = get_parameter(load1, get_mode(&mode, 0), &output);
-                let p2 = get_parameter(load2, get_mode(&mode, 1), &output);
-
-                if p1 == p2 {
-                    output[store] = 1;
-                } else {
-                    output[store] = 0;
-                }
-
-                instruction_size = 4;
-            }
-            99 => {
-                // Opcode 99 means that the program is finished and should
-                // immediately halt.
-
-                println!("HALT");
-
-                break;
-            }
-            _ => {
-                // Encountering an unknown opcode means something went wrong.
-
-                println!("UNKNOWN ({}) ({})", raw_i, i);
+impl Solution for Day05 {
+    fn parse(input: &str) -> Result<Self, Box<dyn Error>> {
+        Ok(Day05 {
+            program: parse_program(input)?,
+        })
+    }
 
-                panic!("Unknown opcode")
-            }
-        }
-        // After an instruction finishes, the instruction pointer increases by
-        // the number of values in the instruction.
-        ip += instruction_size;
+    fn part1(&self) -> Answer {
+        // The ship's air conditioner unit is system 1
+        run_program(&self.program, 1).into()
     }
-    /*
-    // Debugging
-    for i in 0..output.len() {
-        println!("{}: {}", i, output[i]);
+
+    fn part2(&self) -> Answer {
+        // The ship's thermal radiator controller is system 5
+        run_program(&self.program, 5).into()
     }
-    */
 }
 
-pub fn run() {
-    let program = load_program("data/day05.txt".to_string());
-
-    // Part 1
-    let part1 = vec![1];
-    run_program(&program, &part1);
-
-    // Part 1
-    let part2 = vec![5];
-    run_program(&program, &part2);
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Compares the input to 8, and outputs 1 if they're equal
+    const EQUAL_TO_8: &str = "3,9,8,9,10,9,4,9,99,-1,8";
+
+    // Outputs 999 below 8, 1000 for 8 and 1001 above 8
+    const COMPARE_TO_8: &str = "3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,\
+        1106,0,36,98,0,0,1002,21,125,20,4,20,1105,1,46,104,\
+        999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99";
+
+    #[test]
+    fn comparison_programs() {
+        let program = parse_program(EQUAL_TO_8).unwrap();
+        assert_eq!(run_program(&program, 8), 1);
+        assert_eq!(run_program(&program, 5), 0);
+
+        let program = parse_program(COMPARE_TO_8).unwrap();
+        assert_eq!(run_program(&program, 1), 999);
+        assert_eq!(run_program(&program, 5), 999);
+        assert_eq!(run_program(&program, 8), 1000);
+        assert_eq!(run_program(&program, 9), 1001);
+    }
 }
@@ -1,65 +1,79 @@
-use std::io;
-use std::io::BufRead;
-
-use std::fs;
+use std::error::Error;
 
 use std::collections::HashMap;
 
-fn read_inputs(filename: String) -> HashMap<String, String> {
-    let file_in = fs::File::open(filename).expect("Couldn't open file");
-    let file_reader = io::BufReader::new(file_in);
-    let inputs: Vec<Vec<String>> = file_reader
-        .lines()
-        .filter_map(io::Result::ok)
-        .map(|line| line.split(')').map(|s| String::from(s)).collect())
-        .collect();
-
-    let mut orbits = HashMap::new();
-    for input in inputs {
-        let key: String = input[1].to_string();
-        let value: String = input[0].to_string();
-        orbits.entry(key).or_insert(value);
-    }
-    orbits
-}
+use crate::parse;
+use crate::solution::{Answer, Solution};
 
-fn path_to_root(p: &String, planets: &HashMap<String, String>) -> Vec<String> {
-    let mut current_planet: &String = p;
+fn path_to_root(p: &str, planets: &HashMap<String, String>) -> Vec<String> {
+    let mut current_planet: &str = p;
     let mut path: Vec<String> = Vec::new();
     while planets.contains_key(current_planet) {
         let next_planet = &planets[current_planet];
         path.push(next_planet.to_string());
-        current_planet = &next_planet;
+        current_planet = next_planet;
     }
     path
 }
 
-pub fn run() {
-    let inputs = read_inputs("data/day06.txt".to_string());
+pub struct Day06 {
+    orbits: HashMap<String, String>,
+}
+
+impl Solution for Day06 {
+    fn parse(input: &str) -> Result<Self, Box<dyn Error>> {
+        let mut orbits = HashMap::new();
+        for (value, key) in parse::lines(input, |line| parse::split_once(line, ")"))? {
+            orbits
+                .entry(key.to_string())
+                .or_insert_with(|| value.to_string());
+        }
+        Ok(Day06 { orbits })
+    }
 
-    // Part 1
-    let mut orbit_total = 0;
-    let planets = inputs.keys();
-    for p in planets {
-        let path = path_to_root(p, &inputs);
-        orbit_total += path.len();
+    fn part1(&self) -> Answer {
+        let mut orbit_total = 0;
+        for p in self.orbits.keys() {
+            let path = path_to_root(p, &self.orbits);
+            orbit_total += path.len();
+        }
+        orbit_total.into()
     }
-    println!("{}", orbit_total);
 
-    // Part 2
-    let mut you = path_to_root(&"YOU".to_string(), &inputs);
-    let mut santa = path_to_root(&"SAN".to_string(), &inputs);
+    fn part2(&self) -> Answer {
+        let mut you = path_to_root("YOU", &self.orbits);
+        let mut santa = path_to_root("SAN", &self.orbits);
 
-    you.reverse();
-    santa.reverse();
+        you.reverse();
+        santa.reverse();
 
-    while you.first() == santa.first() {
-        if (you.len() == 0) || (santa.len() == 0) {
-            panic!("Something has gone wrong");
+        while you.first() == santa.first() {
+            if you.is_empty() || santa.is_empty() {
+                panic!("Something has gone wrong");
+            }
+            you.remove(0);
+            santa.remove(0);
         }
-        you.remove(0);
-        santa.remove(0);
+        let total_path = you.len() + santa.len();
+        total_path.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ORBITS: &str = "COM)B\nB)C\nC)D\nD)E\nE)F\nB)G\nG)H\nD)I\nE)J\nJ)K\nK)L\n";
+
+    #[test]
+    fn counts_orbits() {
+        let day = Day06::parse(ORBITS).unwrap();
+        assert_eq!(day.part1(), Answer::Number(42));
+    }
+
+    #[test]
+    fn transfers_to_santa() {
+        let day = Day06::parse(&format!("{}K)YOU\nI)SAN\n", ORBITS)).unwrap();
+        assert_eq!(day.part2(), Answer::Number(4));
     }
-    let total_path = you.len() + santa.len();
-    println!("{}", total_path);
 }
@@ -1,17 +1,7 @@
-use std::fs;
-use std::io;
-use std::io::BufRead;
-
-pub fn load_program(filename: String) -> Vec<i32> {
-    let file_in = fs::File::open(filename).expect("Can't read file");
-    let file_reader = io::BufReader::new(file_in);
-    let line: Vec<String> = file_reader.lines().filter_map(io::Result::ok).collect();
-
-    line[0]
-        .split(',')
-        .map(|line| line.parse::<i32>().unwrap())
-        .collect()
-}
+use std::error::Error;
+
+use crate::combinatorics::permutations;
+use crate::solution::{Answer, Solution};
 
 fn decode_instruction(i: u32) -> (u32, Vec<u32>) {
     // Parameter modes are stored in the same value as the instruction's opcode.
@@ -33,8 +23,7 @@ fn decode_instruction(i: u32) -> (u32, Vec<u32>) {
     let mut i: u32 = 0;
     let mut modes: Vec<u32> = Vec::new();
 
-    let mut count = 0;
-    for digit in digits {
+    for (count, digit) in digits.into_iter().enumerate() {
         if count == 0 {
             i = digit;
         } else if count == 1 {
@@ -45,13 +34,12 @@ fn decode_instruction(i: u32) -> (u32, Vec<u32>) {
             }
             modes.push(digit);
         }
-        count += 1;
     }
 
     (i, modes)
 }
 
-fn get_parameter(p: i32, m: u32, program: &Vec<i32>) -> i32 {
+fn get_parameter(p: i32, m: u32, program: &[i32]) -> i32 {
     match m {
         0 => {
             // mode 0, position mode, causes the parameter to be interpreted as a position
@@ -68,7 +56,7 @@ fn get_parameter(p: i32, m: u32, program: &Vec<i32>) -> i32 {
     }
 }
 
-fn get_mode(mode: &Vec<u32>, i: usize) -> u32 {
+fn get_mode(mode: &[u32], i: usize) -> u32 {
     if i >= mode.len() {
         return 0;
     }
@@ -243,98 +231,129 @@ impl VM {
     }
 }
 
-// https://en.wikipedia.org/wiki/Heap%27s_algorithm
-fn generate_all_permutations<T: Clone>(n: usize, arr: Vec<T>) -> Vec<Vec<T>> {
-    let mut a = arr.clone();
-    let mut c: Vec<usize> = Vec::new();
-    for _ in 0..n {
-        c.push(0);
-    }
+pub struct Day07 {
+    program: Vec<i32>,
+}
 
-    let mut output: Vec<Vec<T>> = Vec::new();
-    output.push(a.clone());
+impl Solution for Day07 {
+    fn parse(input: &str) -> Result<Self, Box<dyn Error>> {
+        let program = input
+            .trim()
+            .split(',')
+            .map(|s| s.trim().parse::<i32>())
+            .collect::<Result<_, _>>()?;
+        Ok(Day07 { program })
+    }
 
-    let mut i = 0;
-    while i < n {
-        if c[i] < i {
-            if i % 2 == 0 {
-                a.swap(0, i);
-            } else {
-                a.swap(c[i], i);
+    fn part1(&self) -> Answer {
+        let program = &self.program;
+        let phase_settings = permutations(&[0, 1, 2, 3, 4]);
+        let mut largest_output = 0;
+        for setting in phase_settings {
+            let mut output = 0;
+            for amp in setting {
+                let mut vm = VM {
+                    ip: 0,
+                    memory: program.clone(),
+                    input: vec![amp, output],
+                };
+                match vm.run() {
+                    VmState::Output(v) => {
+                        output = v;
+                        if output > largest_output {
+                            largest_output = output;
+                        }
+                    }
+                    VmState::Terminated => {
+                        panic!("Unexpected end of program");
+                    }
+                }
             }
-            output.push(a.clone());
-            c[i] += 1;
-            i = 0;
-        } else {
-            c[i] = 0;
-            i += 1;
         }
+        largest_output.into()
     }
 
-    return output;
-}
+    fn part2(&self) -> Answer {
+        let program = &self.program;
+        let phase_settings = permutations(&[5, 6, 7, 8, 9]);
+        let mut largest_output = 0;
+        for setting in phase_settings {
+            let mut vms: Vec<VM> = Vec::new();
+            for amp in setting {
+                vms.push(VM {
+                    ip: 0,
+                    memory: program.clone(),
+                    input: vec![amp],
+                })
+            }
+
+            let mut curr = 0;
+            let mut output = 0;
+            loop {
+                let vm = &mut vms[curr];
+                vm.input.push(output);
 
-pub fn run() {
-    let program = load_program("data/day07.txt".to_string());
-
-    // Part 1
-    let phase_settings = generate_all_permutations(5, vec![0, 1, 2, 3, 4]);
-    let mut largest_output = 0;
-    for setting in phase_settings {
-        let mut output = 0;
-        for amp in setting {
-            let mut vm = VM {
-                ip: 0,
-                memory: program.clone(),
-                input: vec![amp, output],
-            };
-            match vm.run() {
-                VmState::Output(v) => {
-                    output = v;
-                    if output > largest_output {
-                        largest_output = output;
+                match vm.run() {
+                    VmState::Output(v) => {
+                        output = v;
+                        curr = (curr + 1) % vms.len();
+                    }
+                    VmState::Terminated => {
+                        break;
                     }
                 }
-                VmState::Terminated => {
-                    panic!("Unexpected end of program");
-                }
+            }
+            if output > largest_output {
+                largest_output = output;
             }
         }
+        largest_output.into()
     }
-    println!("{}", largest_output);
-
-    // Part 2
-    let phase_settings = generate_all_permutations(5, vec![5, 6, 7, 8, 9]);
-    let mut largest_output = 0;
-    for setting in phase_settings {
-        let mut vms: Vec<VM> = Vec::new();
-        for amp in setting {
-            vms.push(VM {
-                ip: 0,
-                memory: program.clone(),
-                input: vec![amp],
-            })
-        }
-
-        let mut curr = 0;
-        let mut output = 0;
-        loop {
-            let vm = &mut vms[curr];
-            vm.input.push(output);
+}
 
-            match vm.run() {
-                VmState::Output(v) => {
-                    output = v;
-                    curr = (curr + 1) % vms.len();
-                }
-                VmState::Terminated => {
-                    break;
-                }
-            }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn amplifier_chain() {
+        let examples = [
+            ("3,15,3,16,1002,16,10,16,1,16,15,15,4,15,99,0,0", 43210),
+            (
+                "3,23,3,24,1002,24,10,24,1002,23,-1,23,\
+                 101,5,23,23,1,24,23,23,4,23,99,0,0",
+                54321,
+            ),
+            (
+                "3,31,3,32,1002,32,10,32,1001,31,-2,31,1007,31,0,33,\
+                 1002,33,7,33,1,33,31,31,1,32,31,31,4,31,99,0,0,0",
+                65210,
+            ),
+        ];
+        for (program, signal) in examples.iter() {
+            let day = Day07::parse(program).unwrap();
+            assert_eq!(day.part1(), Answer::Number(*signal));
         }
-        if output > largest_output {
-            largest_output = output;
+    }
+
+    #[test]
+    fn feedback_loop() {
+        let examples = [
+            (
+                "3,26,1001,26,-4,26,3,27,1002,27,2,27,1,27,26,\
+                 27,4,27,1001,28,-1,28,1005,28,6,99,0,0,5",
+                139629729,
+            ),
+            (
+                "3,52,1001,52,-5,52,3,53,1,52,56,54,1007,54,5,55,1005,55,26,1001,54,\
+                 -5,54,1105,1,12,1,53,54,53,1008,54,0,55,1001,55,1,55,2,53,55,53,4,\
+                 53,1001,56,-1,56,1005,56,6,99,0,0,0,0,10",
+                18216,
+            ),
+        ];
+        for (program, signal) in examples.iter() {
+            let day = Day07::parse(program).unwrap();
+            assert_eq!(day.part2(), Answer::Number(*signal));
         }
     }
-    println!("{}", largest_output);
 }
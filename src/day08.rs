@@ -1,73 +1,130 @@
-use std::fs;
-
-fn load_image(filename: String) -> Vec<u32> {
-    fs::read_to_string(filename)
-        .expect("Can't read file")
-        .chars()
-        .map(|d| d.to_digit(10).unwrap())
-        .collect()
-}
+use std::error::Error;
+
+use crate::grid::Grid;
+use crate::image::{Image, BLACK, WHITE};
+use crate::ocr;
+use crate::solution::{Answer, Solution};
+
+const WIDTH: usize = 25;
+const HEIGHT: usize = 6;
 
 fn count_digits(image: &[u32], digit: u32) -> usize {
     let digits: Vec<u32> = image.iter().cloned().filter(|&n| n == digit).collect();
     digits.len()
 }
 
-pub fn run() {
-    let image = load_image("data/day08.txt".to_string());
-    let (width, height) = (25, 6);
-
-    let layer_size = width * height;
-    let layer_count = image.len() / layer_size;
+pub struct Day08 {
+    image: Vec<u32>,
+    width: usize,
+    height: usize,
+}
 
-    // Part 1
-    let mut counts = (layer_size, 0, 0);
-    for i in 0..layer_count {
-        let layer: &[u32] = &image[i * layer_size..(i + 1) * layer_size];
+impl Day08 {
+    fn parse_image(input: &str, width: usize, height: usize) -> Result<Self, Box<dyn Error>> {
+        let image: Vec<u32> = input
+            .trim()
+            .chars()
+            .map(|d| d.to_digit(10).ok_or(format!("Invalid pixel '{}'", d)))
+            .collect::<Result<_, _>>()?;
 
-        let zero_count = count_digits(layer, 0);
-        if zero_count < counts.0 {
-            counts = (zero_count, count_digits(layer, 1), count_digits(layer, 2));
+        if image.is_empty() || !image.len().is_multiple_of(width * height) {
+            return Err(format!("{} pixels isn't a whole number of layers", image.len()).into());
         }
+
+        Ok(Day08 {
+            image,
+            width,
+            height,
+        })
     }
-    println!(
-        "0:{} 1:{} 2:{} {}",
-        counts.0,
-        counts.1,
-        counts.2,
-        counts.1 * counts.2
-    );
-
-    // Part 2
-    let mut final_image: Vec<u32> = Vec::new();
-    for _ in 0..layer_size {
-        final_image.push(2);
-    }
 
-    for p in 0..layer_size {
-        for l in 0..layer_count {
-            let px = image[l * layer_size + p];
-            if px != 2 {
-                final_image[p] = px;
-                break;
+    // Stack the layers: each pixel is the colour of the first layer that
+    // isn't transparent there
+    fn decode(&self) -> Grid<bool> {
+        let layer_size = self.width * self.height;
+        let layer_count = self.image.len() / layer_size;
+
+        let mut final_image: Vec<u32> = vec![2; layer_size];
+
+        for (p, pixel) in final_image.iter_mut().enumerate() {
+            for l in 0..layer_count {
+                let px = self.image[l * layer_size + p];
+                if px != 2 {
+                    *pixel = px;
+                    break;
+                }
             }
         }
+
+        Grid::from_fn(self.width, self.height, |p| {
+            match final_image[p.y as usize * self.width + p.x as usize] {
+                0 => false,
+                1 => true,
+                _ => panic!("Transparent pixel"),
+            }
+        })
     }
+}
 
-    for y in 0..height {
-        for x in 0..width {
-            match final_image[y * width + x] {
-                0 => {
-                    print!(" ");
-                }
-                1 => {
-                    print!("x");
-                }
-                _ => {
-                    panic!("Transparent pixel");
-                }
+impl Solution for Day08 {
+    fn parse(input: &str) -> Result<Self, Box<dyn Error>> {
+        Day08::parse_image(input, WIDTH, HEIGHT)
+    }
+
+    fn part1(&self) -> Answer {
+        let layer_size = self.width * self.height;
+
+        let mut counts = (layer_size, 0, 0);
+        for layer in self.image.chunks(layer_size) {
+            let zero_count = count_digits(layer, 0);
+            if zero_count < counts.0 {
+                counts = (zero_count, count_digits(layer, 1), count_digits(layer, 2));
             }
         }
-        println!("");
+        (counts.1 * counts.2).into()
+    }
+
+    fn part2(&self) -> Answer {
+        ocr::read_or_render(&self.decode(), 'x')
+    }
+
+    fn pictures(&self) -> Vec<(String, Image)> {
+        let message = Image::from_grid(&self.decode(), |&on| if on { WHITE } else { BLACK });
+        vec![("message".to_string(), message)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layer_with_fewest_zeros() {
+        let day = Day08::parse_image("123456789012", 3, 2).unwrap();
+        assert_eq!(day.part1(), Answer::Number(1));
+        assert!(Day08::parse_image("12345678901", 3, 2).is_err());
+    }
+
+    #[test]
+    fn stacks_layers() {
+        let day = Day08::parse_image("0222112222120000", 2, 2).unwrap();
+        assert_eq!(day.part2(), Answer::Rendered(" x\nx \n".to_string()));
+    }
+
+    #[test]
+    fn reads_the_letters() {
+        // "HI" on the second layer, under a first layer that's all transparent
+        let letters = [
+            "1001001110",
+            "1001000100",
+            "1111000100",
+            "1001000100",
+            "1001000100",
+            "1001001110",
+        ]
+        .concat();
+        let image = "2".repeat(60) + &letters;
+        let day = Day08::parse_image(&image, 10, 6).unwrap();
+        assert_eq!(day.part2(), Answer::Text("HI".to_string()));
     }
 }
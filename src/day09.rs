@@ -1,17 +1,17 @@
-use std::fs;
+use std::error::Error;
 
-use crate::intcode::{VirtualMachine, VirtualMachineState, load_program};
+use crate::intcode::{parse_program, VirtualMachine, VirtualMachineState};
+use crate::solution::{Answer, Solution};
 
-pub fn run() {
-    let program = load_program("data/day09.txt".to_string());
+fn run_boost(program: &[i128], mode: i128) -> Answer {
+    let mut vm = VirtualMachine::new(program);
+    vm.add_input(mode);
 
-    // Part 1
-    let mut vm = VirtualMachine::new(&program);
-    vm.add_input(1);
+    let mut outputs: Vec<i128> = Vec::new();
     loop {
         match vm.run() {
             VirtualMachineState::Output(v) => {
-                println!("{}", v);
+                outputs.push(v);
             }
             VirtualMachineState::Terminated => {
                 break;
@@ -21,21 +21,54 @@ pub fn run() {
             }
         }
     }
-    
-    // Part 2
-    vm = VirtualMachine::new(&program);
-    vm.add_input(2);
-    loop {
-        match vm.run() {
-            VirtualMachineState::Output(v) => {
-                println!("{}", v);
-            }
-            VirtualMachineState::Terminated => {
-                break;
-            }
-            _ => {
-                panic!("Unexpected request for input");
-            }
-        }
+
+    // A working VM gets a single keycode back, otherwise the outputs are the
+    // opcodes that are broken
+    match outputs.as_slice() {
+        [keycode] => (*keycode).into(),
+        _ => outputs
+            .iter()
+            .map(|v| v.to_string())
+            .collect::<Vec<String>>()
+            .join(",")
+            .into(),
+    }
+}
+
+pub struct Day09 {
+    program: Vec<i128>,
+}
+
+impl Solution for Day09 {
+    fn parse(input: &str) -> Result<Self, Box<dyn Error>> {
+        Ok(Day09 {
+            program: parse_program(input)?,
+        })
+    }
+
+    fn part1(&self) -> Answer {
+        run_boost(&self.program, 1)
+    }
+
+    fn part2(&self) -> Answer {
+        run_boost(&self.program, 2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relative_mode_and_large_numbers() {
+        let quine = "109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99";
+        let day = Day09::parse(quine).unwrap();
+        assert_eq!(day.part1(), Answer::Text(quine.to_string()));
+
+        let day = Day09::parse("1102,34915192,34915192,7,4,7,99,0").unwrap();
+        assert_eq!(day.part1(), Answer::Number(1219070632396864));
+
+        let day = Day09::parse("104,1125899906842624,99").unwrap();
+        assert_eq!(day.part2(), Answer::Number(1125899906842624));
     }
 }
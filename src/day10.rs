@@ -1,70 +1,56 @@
-use std::io;
-use std::io::BufRead;
-
-use std::fmt;
-use std::fs;
+use std::error::Error;
 
 use std::collections::HashSet;
 use std::f64::consts;
 
-#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
-struct Point {
-    x: i32,
-    y: i32,
-}
-
-impl fmt::Display for Point {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{},{}", self.x, self.y)
-    }
-}
-
-fn read_inputs(filename: String) -> Vec<Point> {
-    let file_in = fs::File::open(filename).expect("Can't read file!");
-    let file_reader = io::BufReader::new(file_in);
-    let inputs: Vec<Vec<char>> = file_reader
-        .lines()
-        .filter_map(io::Result::ok)
-        .map(|line| {
-            let chars: Vec<char> = line.chars().collect();
-            chars
-        })
-        .collect();
+use crate::geom::Point;
+use crate::parse::{ParseError, Reason};
+use crate::solution::{Answer, Solution};
 
+fn parse_asteroids(input: &str) -> Result<Vec<Point>, ParseError> {
     let mut points: Vec<Point> = Vec::new();
-    for y in 0..inputs.len() {
-        for x in 0..inputs[y].len() {
-            if inputs[y][x] == '#' {
-                points.push(Point {
-                    x: x as i32,
-                    y: y as i32,
-                });
+    for (y, line) in input.lines().enumerate() {
+        // Positions are counted from the first tile, but errors point at the
+        // column in the line as it was given
+        let indent = line.chars().count() - line.trim_start().chars().count();
+        for (x, c) in line.trim().chars().enumerate() {
+            match c {
+                '#' => points.push(Point::new(x as i32, y as i32)),
+                '.' => {}
+                _ => {
+                    let reason = Reason::Unexpected {
+                        expected: "'#' or '.'".to_string(),
+                        found: c.to_string(),
+                    };
+                    return Err(ParseError::at(y + 1, indent + x + 1, reason));
+                }
             }
         }
     }
-    points
+    Ok(points)
 }
 
 fn is_point_between(a: Point, b: Point, c: Point) -> bool {
-    let crossproduct = (c.y - a.y) * (b.x - a.x) - (c.x - a.x) * (b.y - a.y);
+    let (ab, ac) = (b - a, c - a);
+    let crossproduct = ac.y * ab.x - ac.x * ab.y;
     if crossproduct != 0 {
         return false;
     }
 
-    let dotproduct = (c.x - a.x) * (b.x - a.x) + (c.y - a.y) * (b.y - a.y);
+    let dotproduct = ac.x * ab.x + ac.y * ab.y;
     if dotproduct < 0 {
         return false;
     }
 
-    let squaredlengthba = (b.x - a.x) * (b.x - a.x) + (b.y - a.y) * (b.y - a.y);
+    let squaredlengthba = ab.x * ab.x + ab.y * ab.y;
     if dotproduct > squaredlengthba {
         return false;
     }
 
-    return true;
+    true
 }
 
-fn get_closest(start: Point, inputs: &Vec<Point>) -> Vec<usize> {
+fn get_closest(start: Point, inputs: &[Point]) -> Vec<usize> {
     let mut closest: Vec<usize> = Vec::new();
     for j in 0..inputs.len() {
         let end = inputs[j];
@@ -72,8 +58,7 @@ fn get_closest(start: Point, inputs: &Vec<Point>) -> Vec<usize> {
             continue;
         }
         let mut is_closest = true;
-        for k in 0..inputs.len() {
-            let p = inputs[k];
+        for &p in inputs {
             if (p == start) || (p == end) {
                 continue;
             }
@@ -91,12 +76,12 @@ fn get_closest(start: Point, inputs: &Vec<Point>) -> Vec<usize> {
 }
 
 fn angle_to(start: Point, end: Point) -> f64 {
-    let dx = (end.x as f64) - (start.x as f64);
-    let dy = (end.y as f64) - (start.y as f64);
+    let delta = end - start;
+    let (dx, dy) = (delta.x as f64, delta.y as f64);
 
     let mut deg = -dy.atan2(dx) * 180.0 / consts::PI;
 
-    if (deg <= 90.0) && (deg >= 0.0) {
+    if (0.0..=90.0).contains(&deg) {
         deg = (deg - 90.0).abs();
     } else if deg < 0.0 {
         deg = deg.abs() + 90.0;
@@ -104,48 +89,125 @@ fn angle_to(start: Point, end: Point) -> f64 {
         deg = 450.0 - deg;
     }
 
-    return deg;
+    deg
 }
 
-pub fn run() {
-    let mut inputs = read_inputs("data/day10.txt".to_string());
-
-    // Part 1
-    let mut max_count = 0;
-    let mut max_asteroid = 0;
-    for i in 0..inputs.len() {
-        let closest = get_closest(inputs[i], &inputs);
-        if closest.len() > max_count {
-            max_count = closest.len();
-            max_asteroid = i;
+pub struct Day10 {
+    asteroids: Vec<Point>,
+}
+
+impl Day10 {
+    // The asteroid that can see the most other asteroids, and how many it sees
+    fn station(&self) -> (Point, usize) {
+        let mut max_count = 0;
+        let mut max_asteroid = 0;
+        for (i, &asteroid) in self.asteroids.iter().enumerate() {
+            let closest = get_closest(asteroid, &self.asteroids);
+            if closest.len() > max_count {
+                max_count = closest.len();
+                max_asteroid = i;
+            }
         }
+        (self.asteroids[max_asteroid], max_count)
+    }
+}
+
+impl Solution for Day10 {
+    fn parse(input: &str) -> Result<Self, Box<dyn Error>> {
+        Ok(Day10 {
+            asteroids: parse_asteroids(input)?,
+        })
+    }
+
+    fn part1(&self) -> Answer {
+        self.station().1.into()
     }
 
-    let station = inputs[max_asteroid];
-    println!("{} @ {}", max_count, station);
-
-    // Part 2
-    let mut remaining_asteroids = 200;
-    loop {
-        let closest = get_closest(station, &inputs);
-        if closest.len() < remaining_asteroids {
-            remaining_asteroids -= closest.len();
-            let remove: HashSet<Point> = closest.iter().map(|&i| inputs[i]).collect();
-            inputs.retain(|p| !remove.contains(p));
-        } else {
-            let mut angles: Vec<(usize, Point, f64)> = closest
-                .iter()
-                .map(|&i| {
-                    let p = inputs[i];
-                    let angle = angle_to(station, p);
-                    (i, p, angle)
-                })
-                .collect();
-            angles.sort_by(|a, b| a.2.partial_cmp(&b.2).unwrap());
-
-            let two_hundredth = angles[remaining_asteroids - 1].0;
-            println!("{}", inputs[two_hundredth]);
-            break;
+    fn part2(&self) -> Answer {
+        let (station, _) = self.station();
+        let mut inputs = self.asteroids.clone();
+
+        let mut remaining_asteroids = 200;
+        loop {
+            let closest = get_closest(station, &inputs);
+            if closest.is_empty() {
+                panic!("Fewer than 200 asteroids");
+            }
+            if closest.len() < remaining_asteroids {
+                remaining_asteroids -= closest.len();
+                let remove: HashSet<Point> = closest.iter().map(|&i| inputs[i]).collect();
+                inputs.retain(|p| !remove.contains(p));
+            } else {
+                let mut angles: Vec<(usize, Point, f64)> = closest
+                    .iter()
+                    .map(|&i| {
+                        let p = inputs[i];
+                        let angle = angle_to(station, p);
+                        (i, p, angle)
+                    })
+                    .collect();
+                angles.sort_by(|a, b| a.2.partial_cmp(&b.2).unwrap());
+
+                let two_hundredth = inputs[angles[remaining_asteroids - 1].0];
+                return (two_hundredth.x * 100 + two_hundredth.y).into();
+            }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LARGE: &str = "
+        .#..##.###...#######
+        ##.############..##.
+        .#.######.########.#
+        .###.#######.####.#.
+        #####.##.#.##.###.##
+        ..#####..#.#########
+        ####################
+        #.####....###.#.#.##
+        ##.#################
+        #####.##.###..####..
+        ..######..##.#######
+        ####.##.####...##..#
+        .#####..#.######.###
+        ##...#.##########...
+        #.##########.#######
+        .####.#.###.###.#.##
+        ....##.##.###..#####
+        .#.#.###########.###
+        #.#.#.#####.####.###
+        ###.##.####.##.#..##";
+
+    #[test]
+    fn best_station() {
+        let day = Day10::parse(".#..#\n.....\n#####\n....#\n...##\n").unwrap();
+        assert_eq!(day.station(), (Point { x: 3, y: 4 }, 8));
+
+        let day = Day10::parse(LARGE.trim_start()).unwrap();
+        assert_eq!(day.station(), (Point { x: 11, y: 13 }, 210));
+    }
+
+    #[test]
+    fn two_hundredth_vaporized() {
+        let day = Day10::parse(LARGE.trim_start()).unwrap();
+        assert_eq!(day.part2(), Answer::Number(802));
+    }
+
+    #[test]
+    fn points_at_bad_tiles() {
+        let error = Day10::parse(".#..#\n..@..\n").err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "line 2, column 3: expected '#' or '.', found '@'"
+        );
+
+        let error = Day10::parse("  .#..#\n  ..@..\n").err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "line 2, column 5: expected '#' or '.', found '@'"
+        );
+    }
+}
@@ -1,77 +1,23 @@
-use std::cmp;
-use std::fmt;
+use std::error::Error;
 
-use crate::intcode::{VirtualMachine, VirtualMachineState, load_program};
+use crate::geom::{Direction, Point, Turn};
+use crate::grid::Grid;
+use crate::image::{self, Image, BLACK, WHITE};
+use crate::intcode::{parse_program, VirtualMachine, VirtualMachineState};
+use crate::ocr;
+use crate::solution::{Answer, Solution};
 
 use std::collections::HashMap;
 
-#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
-struct Point {
-    x: i32,
-    y: i32,
-}
-
-impl fmt::Display for Point {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{},{}", self.x, self.y)
-    }
-}
-
-impl Ord for Point {
-    fn cmp(&self, other: &Self) -> cmp::Ordering {
-        self.y.cmp(&other.y).then_with(|| self.x.cmp(&other.x))
-    }
-}
-
-impl PartialOrd for Point {
-    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
-        Some(self.cmp(other))
-    }
-}
-
-enum Direction {
-    Up,
-    Down,
-    Left,
-    Right,
-}
-
-fn rotate_left(direction: &Direction) -> Direction {
-    match direction {
-        Direction::Up => Direction::Left,
-        Direction::Down => Direction::Right,
-        Direction::Left => Direction::Down,
-        Direction::Right => Direction::Up,
-    }
-}
-
-fn rotate_right(direction: &Direction) -> Direction {
-    match direction {
-        Direction::Up => Direction::Right,
-        Direction::Down => Direction::Left,
-        Direction::Left => Direction::Up,
-        Direction::Right => Direction::Down,
-    }
-}
-
-fn move_forward(p: &Point, direction: &Direction) -> Point {
-    match direction {
-        Direction::Up => Point { x: p.x, y: p.y - 1 },
-        Direction::Down => Point { x: p.x, y: p.y + 1 },
-        Direction::Left => Point { x: p.x - 1, y: p.y },
-        Direction::Right => Point { x: p.x + 1, y: p.y },
-    }
-}
-
 enum State {
     OutputColor,
     OutputOrientationChange,
 }
 
-fn run_robot(program: &Vec<i128>, starting: i128) -> HashMap<Point, i128> {
+fn run_robot(program: &[i128], starting: i128) -> HashMap<Point, i128> {
     let mut map: HashMap<Point, i128> = HashMap::new();
     let mut robot: (Point, Direction, State) =
-        (Point { x: 0, y: 0 }, Direction::Up, State::OutputColor);
+        (Point::ORIGIN, Direction::North, State::OutputColor);
     map.insert(robot.0, starting);
 
     let mut vm = VirtualMachine::new(program);
@@ -107,20 +53,17 @@ fn run_robot(program: &Vec<i128>, starting: i128) -> HashMap<Point, i128> {
                         //     1 means it should turn right 90 degrees
                         // After the robot turns, it should always move forward exactly one panel.
 
-                        match v {
-                            0 => {
-                                robot.1 = rotate_left(&robot.1);
-                            }
-                            1 => {
-                                robot.1 = rotate_right(&robot.1);
-                            }
+                        let turn = match v {
+                            0 => Turn::Left,
+                            1 => Turn::Right,
                             _ => {
                                 panic!("Invalid Orientation Change");
                             }
-                        }
+                        };
+                        robot.1 = robot.1.turn(turn);
 
                         // After the robot turns, it should always move forward exactly one panel.
-                        robot.0 = move_forward(&robot.0, &robot.1);
+                        robot.0 = robot.0.step(robot.1);
 
                         robot.2 = State::OutputColor;
                     }
@@ -134,26 +77,66 @@ fn run_robot(program: &Vec<i128>, starting: i128) -> HashMap<Point, i128> {
     map
 }
 
-pub fn run() {
-    let program = load_program("data/day11.txt".to_string());
-
-    // Part 1
-    let part1 = run_robot(&program, 0);
-    println!("{}", part1.keys().count());
-
-    // Part 2
-    let part2 = run_robot(&program, 1);
-    let min: &Point = part2.keys().min_by_key(|&x| x).unwrap();
-    let max: &Point = part2.keys().max_by_key(|&x| x).unwrap();
-    for y in min.y..=max.y {
-        for x in min.x..=max.x {
-            let p = Point { x: x, y: y };
-            if part2.contains_key(&p) && (part2[&p] == 1) {
-                print!("X");
-            } else {
-                print!(" ");
-            }
-        }
-        println!("");
+pub struct Day11 {
+    program: Vec<i128>,
+}
+
+impl Solution for Day11 {
+    fn parse(input: &str) -> Result<Self, Box<dyn Error>> {
+        Ok(Day11 {
+            program: parse_program(input)?,
+        })
+    }
+
+    fn part1(&self) -> Answer {
+        let painted = run_robot(&self.program, 0);
+        painted.keys().count().into()
+    }
+
+    fn part2(&self) -> Answer {
+        let painted = run_robot(&self.program, 1);
+        let (hull, _) = Grid::from_sparse(&painted, 0);
+        ocr::read_or_render(&hull.map(|&colour| colour == 1), 'X')
+    }
+
+    fn pictures(&self) -> Vec<(String, Image)> {
+        let painted = run_robot(&self.program, 1);
+        let hull = image::from_sparse(
+            &painted,
+            BLACK,
+            |&colour| {
+                if colour == 1 {
+                    WHITE
+                } else {
+                    BLACK
+                }
+            },
+        );
+        vec![("hull".to_string(), hull)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A stand-in for the robot's brain that ignores the camera and gives the
+    // instructions from the example
+    const EXAMPLE: &str = "104,1,104,0,104,0,104,0,104,1,104,0,104,1,104,0,\
+        104,0,104,1,104,1,104,0,104,1,104,0,99";
+
+    #[test]
+    fn paints_panels() {
+        let day = Day11::parse(EXAMPLE).unwrap();
+        assert_eq!(day.part1(), Answer::Number(6));
+
+        let painted = run_robot(&day.program, 0);
+        let white: Vec<Point> = painted
+            .iter()
+            .filter(|(_, &colour)| colour == 1)
+            .map(|(&p, _)| p)
+            .collect();
+        assert_eq!(white.len(), 4);
+        assert_eq!(day.part2(), Answer::Rendered("  X\n  X\nXX \n".to_string()));
     }
 }
@@ -1,18 +1,14 @@
-
-use std::io::BufRead;
-use std::io;
-
-use std::hash;
+use std::error::Error;
 use std::fmt;
-use std::fs;
+use std::hash;
 use std::ops;
 
 use regex::Regex;
 
-use lazy_static;
-
-use std::collections::HashMap;
-use std::collections::HashSet;
+use crate::cycle;
+use crate::numtheory::lcm;
+use crate::parse::{self, Reason};
+use crate::solution::{Answer, Solution};
 
 #[derive(Copy, Clone, PartialEq, Eq)]
 struct Point3D {
@@ -62,22 +58,18 @@ impl Moon {
     fn new(p: Point3D) -> Moon {
         Moon {
             position: p,
-            velocity: Point3D{x: 0, y: 0, z: 0}
+            velocity: Point3D { x: 0, y: 0, z: 0 },
         }
     }
     fn potential_energy(&self) -> i128 {
-        // A moon's potential energy is the sum of the absolute 
+        // A moon's potential energy is the sum of the absolute
         // values of its position coordinates.
-        return self.position.x.abs() +
-               self.position.y.abs() +
-               self.position.z.abs();
+        self.position.x.abs() + self.position.y.abs() + self.position.z.abs()
     }
     fn kinetic_energy(&self) -> i128 {
-        // A moon's kinetic energy is the sum of the absolute 
+        // A moon's kinetic energy is the sum of the absolute
         // values of its velocity coordinates.
-        return self.velocity.x.abs() +
-               self.velocity.y.abs() +
-               self.velocity.z.abs();
+        self.velocity.x.abs() + self.velocity.y.abs() + self.velocity.z.abs()
     }
 }
 
@@ -87,35 +79,27 @@ impl fmt::Display for Moon {
     }
 }
 
-fn read_inputs(filename: String) -> Vec<Moon> {
-    let file_in = fs::File::open(filename).expect("Can't open file");
-    let file_reader = io::BufReader::new(file_in);
-    file_reader.lines().filter_map(io::Result::ok).map(|line| {
-        lazy_static! {
-            // <x=#, y=#, z=#>
-            static ref LINE_RE: Regex = Regex::new(r"<x=(-?[\d]+), y=(-?[\d]+), z=(-?[\d]+)>").unwrap();
-        }
-        if LINE_RE.is_match(&line) {
-            for line_cap in LINE_RE.captures_iter(&line) {
-                let x: i128 = line_cap[1].parse().unwrap();
-                let y: i128 = line_cap[2].parse().unwrap();
-                let z: i128 = line_cap[3].parse().unwrap();
-                return Moon::new(Point3D { x: x, y: y, z: z });
-            }
-        }
-        panic!("Invalid input");
-    }).collect()
+fn parse_moon(line: &str) -> Result<Moon, Reason> {
+    lazy_static! {
+        // <x=#, y=#, z=#>
+        static ref LINE_RE: Regex = Regex::new(r"<x=(-?[\d]+), y=(-?[\d]+), z=(-?[\d]+)>").unwrap();
+    }
+    let line_cap = parse::captures(&LINE_RE, line, "a moon like '<x=1, y=2, z=3>'")?;
+    let x: i128 = parse::number(&line_cap[1])?;
+    let y: i128 = parse::number(&line_cap[2])?;
+    let z: i128 = parse::number(&line_cap[3])?;
+    Ok(Moon::new(Point3D { x, y, z }))
 }
 
-fn simulate_step(moons: &mut Vec<Moon>, axis: Vec<usize>) {
-    // Simulate the motion of the moons in time steps. 
-    // Within each time step, first update the velocity of every moon 
+fn simulate_step(moons: &mut [Moon], axis: Vec<usize>) {
+    // Simulate the motion of the moons in time steps.
+    // Within each time step, first update the velocity of every moon
     // by applying gravity.
 
-    // To apply gravity, consider every pair of moons. On each axis 
-    // (x, y, and z), the velocity of each moon changes by exactly 
-    // +1 or -1 to pull the moons together. If the positions on a 
-    // given axis are the same, the velocity on that axis does not 
+    // To apply gravity, consider every pair of moons. On each axis
+    // (x, y, and z), the velocity of each moon changes by exactly
+    // +1 or -1 to pull the moons together. If the positions on a
+    // given axis are the same, the velocity on that axis does not
     // change for that pair of moons.
 
     let count = moons.len();
@@ -156,92 +140,91 @@ fn simulate_step(moons: &mut Vec<Moon>, axis: Vec<usize>) {
         }
     }
 
-    // Then, once all moons' velocities have been updated, update the 
+    // Then, once all moons' velocities have been updated, update the
     // position of every moon by applying velocity.
 
-    for i in 0..count {
-        let v = moons[i].velocity;
-        moons[i].position += v;
+    for moon in moons.iter_mut() {
+        let v = moon.velocity;
+        moon.position += v;
     }
 }
 
-fn prime_factors(num: i128) -> HashMap<i128, i128> {
-    let mut n = num;
-    let mut factors: HashMap<i128, i128> = HashMap::new();
+pub struct Day12 {
+    moons: Vec<Moon>,
+}
 
-    for i in 2..=n {
-        let mut count = 0;
-        while n % i == 0 {
-            n /= i;
-            count += 1;
+impl Day12 {
+    // The total energy in the system after some time steps
+    fn energy_after(&self, steps: usize) -> i128 {
+        let mut moons = self.moons.clone();
+        for _ in 0..steps {
+            simulate_step(&mut moons, vec![0, 1, 2]);
         }
-        if count > 0 {
-            factors.insert(i, count);
+
+        // The total energy for a single moon is its potential energy
+        // multiplied by its kinetic energy.
+        let mut total_energy = 0;
+        for moon in &moons {
+            let pe = moon.potential_energy();
+            let ke = moon.kinetic_energy();
+            total_energy += pe * ke;
         }
+        total_energy
     }
-
-    factors
 }
 
-fn lowest_common_multiple(nums: Vec<i128>) -> i128 {
-    let mut factors: HashMap<i128, i128> = HashMap::new();
-    for n in nums {
-        let n_factors = prime_factors(n);
-        for f in n_factors {
-            if factors.contains_key(&f.0) {
-                if f.1 > factors[&f.0] {
-                    factors.insert(f.0, f.1);
-                }
-            } else {
-                factors.insert(f.0, f.1);
-            }
-        }
+impl Solution for Day12 {
+    fn parse(input: &str) -> Result<Self, Box<dyn Error>> {
+        let moons = parse::lines(input, parse_moon)?;
+        Ok(Day12 { moons })
     }
 
-    let mut min_multiple = 1;
-    for f in &factors {
-        for _ in 0..*f.1 {
-            min_multiple *= f.0;
-        }
+    fn part1(&self) -> Answer {
+        self.energy_after(1000).into()
     }
 
-    min_multiple
+    fn part2(&self) -> Answer {
+        // Each step can be undone, so every axis comes back round to where
+        // it started rather than settling into a cycle part way through
+        let periods = (0..=2).map(|axis| {
+            let step = |moons: &Vec<Moon>| {
+                let mut moons = moons.clone();
+                simulate_step(&mut moons, vec![axis]);
+                moons
+            };
+            cycle::brent(self.moons.clone(), step).length as i64
+        });
+
+        // Each axis moves independently, so everything lines up again once
+        // every axis has
+        periods.fold(1, lcm).into()
+    }
 }
 
-pub fn run() {
-    let moons: Vec<Moon> = read_inputs("data/day12.txt".to_string());
-    
-    // Part 1
-    let mut part1 = moons.clone();
-    for _ in 0..1000 {
-        simulate_step(&mut part1, vec![0, 1, 2]);
-    }
-
-    // The total energy for a single moon is its potential energy 
-    // multiplied by its kinetic energy.
-    let mut total_energy = 0;
-    for i in 0..part1.len() {
-        let pe = part1[i].potential_energy();
-        let ke = part1[i].kinetic_energy();
-        total_energy += pe * ke;
-    }
-    println!("{}", total_energy);
-
-    // Part 2
-    let mut periods: Vec<i128> = Vec::new();
-    for i in 0..=2 {
-        let mut time = 0;
-        let mut part2 = moons.clone();
-        loop {
-            time += 1;
-            simulate_step(&mut part2, vec![i]);
-            if part2 == moons {
-                periods.push(time);
-                break;
-            }
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIRST: &str =
+        "<x=-1, y=0, z=2>\n<x=2, y=-10, z=-7>\n<x=4, y=-8, z=8>\n<x=3, y=5, z=-1>\n";
+    const SECOND: &str =
+        "<x=-8, y=-10, z=0>\n<x=5, y=5, z=10>\n<x=2, y=-7, z=3>\n<x=9, y=-8, z=-3>\n";
+
+    #[test]
+    fn total_energy() {
+        let day = Day12::parse(FIRST).unwrap();
+        assert_eq!(day.energy_after(10), 179);
+
+        let day = Day12::parse(SECOND).unwrap();
+        assert_eq!(day.energy_after(100), 1940);
     }
 
-    let min = lowest_common_multiple(periods);
-    println!("{}", min);
-}
\ No newline at end of file
+    #[test]
+    fn steps_until_repeated() {
+        let day = Day12::parse(FIRST).unwrap();
+        assert_eq!(day.part2(), Answer::Number(2772));
+
+        let day = Day12::parse(SECOND).unwrap();
+        assert_eq!(day.part2(), Answer::Number(4686774924));
+    }
+}
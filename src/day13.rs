@@ -1,49 +1,44 @@
-use std::cmp;
-use std::fmt;
+use std::error::Error;
 
-use crate::intcode::{VirtualMachine, VirtualMachineState, load_program};
+use crate::animation::{no_frames, Frames};
+use crate::geom::Point;
+use crate::grid::Grid;
+use crate::image::{self, Image, Rgb, BLACK};
+use crate::intcode::{parse_program, VirtualMachine, VirtualMachineState};
+use crate::solution::{Answer, Solution};
 
 use std::collections::HashMap;
 
-#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
-struct Point {
-    x: i128,
-    y: i128,
-}
-
-impl fmt::Display for Point {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{},{}", self.x, self.y)
-    }
-}
-
-impl Ord for Point {
-    fn cmp(&self, other: &Self) -> cmp::Ordering {
-        self.y.cmp(&other.y).then_with(|| self.x.cmp(&other.x))
-    }
-}
-
-impl PartialOrd for Point {
-    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
-        Some(self.cmp(other))
-    }
-}
-
 enum State {
     OutputX,
     OutputY,
     OutputTile,
 }
 
-fn run_game(program: &Vec<i128>, play: bool) -> (HashMap<Point, i128>, i128) {
+// The screen as text, with the score underneath
+fn render(screen: &HashMap<Point, i128>, score: i128) -> String {
+    let (grid, _) = Grid::from_sparse(screen, 0);
+    let tiles = grid.render(|&tile| match tile {
+        1 => '#',
+        2 => '=',
+        3 => '_',
+        4 => 'o',
+        _ => ' ',
+    });
+    format!("{}Score: {}\n", tiles, score)
+}
+
+// Play the game (or just draw the first screen), sending a frame each time
+// the joystick is read
+fn run_game(program: &[i128], play: bool, frames: Frames) -> (HashMap<Point, i128>, i128) {
     let mut screen: HashMap<Point, i128> = HashMap::new();
 
     let mut score = 0;
-    let mut ball = Point { x: 0, y: 0 };
-    let mut paddle = Point { x: 0, y: 0 };
+    let mut ball = Point::ORIGIN;
+    let mut paddle = Point::ORIGIN;
 
     let mut state = State::OutputX;
-    let mut p = Point { x: 0, y: 0 };
+    let mut p = Point::ORIGIN;
 
     let mut vm = VirtualMachine::new(program);
 
@@ -59,6 +54,7 @@ fn run_game(program: &Vec<i128>, play: bool) -> (HashMap<Point, i128>, i128) {
                 // If the joystick is in the neutral position, provide 0.
                 // If the joystick is tilted to the left, provide -1.
                 // If the joystick is tilted to the right, provide 1.
+                frames(&|| render(&screen, score));
 
                 if paddle.x < ball.x {
                     vm.add_input(1);
@@ -71,11 +67,11 @@ fn run_game(program: &Vec<i128>, play: bool) -> (HashMap<Point, i128>, i128) {
             VirtualMachineState::Output(v) => {
                 match state {
                     State::OutputX => {
-                        p.x = v;
+                        p.x = v as i32;
                         state = State::OutputY;
                     }
                     State::OutputY => {
-                        p.y = v;
+                        p.y = v as i32;
                         state = State::OutputTile;
                     }
                     State::OutputTile => {
@@ -110,20 +106,75 @@ fn run_game(program: &Vec<i128>, play: bool) -> (HashMap<Point, i128>, i128) {
     (screen, score)
 }
 
-pub fn run() {
-    let program = load_program("data/day13.txt".to_string());
+fn tile_colour(tile: i128) -> Rgb {
+    match tile {
+        1 => [128, 128, 128], // wall
+        2 => [200, 80, 40],   // block
+        3 => [60, 120, 220],  // paddle
+        4 => [240, 240, 240], // ball
+        _ => BLACK,
+    }
+}
+
+pub struct Day13 {
+    program: Vec<i128>,
+}
 
-    // Part 1
-    let part1 = run_game(&program, false).0;
-    let mut block_count = 0;
-    for tile in part1.values() {
-        if tile == &2 {
-            block_count += 1;
-        }
+impl Solution for Day13 {
+    fn parse(input: &str) -> Result<Self, Box<dyn Error>> {
+        Ok(Day13 {
+            program: parse_program(input)?,
+        })
+    }
+
+    fn part1(&self) -> Answer {
+        let screen = run_game(&self.program, false, &mut no_frames).0;
+        screen.values().filter(|&&tile| tile == 2).count().into()
+    }
+
+    fn part2(&self) -> Answer {
+        run_game(&self.program, true, &mut no_frames).1.into()
+    }
+
+    // The screen before the game starts, with every block still there
+    fn pictures(&self) -> Vec<(String, Image)> {
+        let screen = run_game(&self.program, false, &mut no_frames).0;
+        let picture = image::from_sparse(&screen, BLACK, |&tile| tile_colour(tile));
+        vec![("screen".to_string(), picture)]
     }
-    println!("{} blocks", block_count);
 
-    // Part 2
-    let part2 = run_game(&program, true).1;
-    println!("{}", part2);
+    // The game being played, a frame per move of the joystick
+    fn animate(&self, frames: Frames) {
+        let (screen, score) = run_game(&self.program, true, frames);
+        frames(&|| render(&screen, score));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A stand-in for the game that draws the example's paddle and ball, two
+    // blocks and a score. It starts with an add, so putting in quarters only
+    // turns that into a multiply.
+    const EXAMPLE: &str = "1101,0,0,100,\
+        104,1,104,2,104,3,104,6,104,5,104,4,\
+        104,0,104,0,104,2,104,1,104,0,104,2,\
+        104,-1,104,0,104,12345,99";
+
+    #[test]
+    fn draws_tiles_and_score() {
+        let day = Day13::parse(EXAMPLE).unwrap();
+
+        let (screen, score) = run_game(&day.program, false, &mut no_frames);
+        assert_eq!(screen[&Point::new(1, 2)], 3);
+        assert_eq!(screen[&Point::new(6, 5)], 4);
+        assert_eq!(
+            render(&screen, score),
+            "==     \n       \n _     \n       \n       \n      o\nScore: 12345\n"
+        );
+
+        assert_eq!(day.part1(), Answer::Number(2));
+        assert_eq!(day.part2(), Answer::Number(12345));
+    }
 }
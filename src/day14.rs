@@ -1,15 +1,13 @@
-use std::io;
-use std::io::BufRead;
-
 use std::cmp;
+use std::error::Error;
 use std::fmt;
-use std::fs;
 
 use regex::Regex;
 
-use lazy_static;
+use std::collections::{HashMap, HashSet};
 
-use std::collections::HashMap;
+use crate::parse::{self, ParseError, Reason};
+use crate::solution::{Answer, Solution};
 
 #[derive(Clone)]
 struct Ingredient {
@@ -18,22 +16,16 @@ struct Ingredient {
 }
 
 impl Ingredient {
-    fn from_string(s: String) -> Ingredient {
+    fn parse(s: &str) -> Result<Ingredient, Reason> {
         lazy_static! {
             // # XXX
-            static ref LINE_RE: Regex = Regex::new(r"([\d]+) (.*)").unwrap();
-        }
-        if LINE_RE.is_match(&s) {
-            for line_cap in LINE_RE.captures_iter(&s) {
-                let quantity: i128 = line_cap[1].parse().unwrap();
-                let chemical = &line_cap[2];
-                return Ingredient {
-                    chemical: chemical.to_string(),
-                    quantity: quantity,
-                };
-            }
+            static ref LINE_RE: Regex = Regex::new(r"^([\d]+) (\w+)$").unwrap();
         }
-        panic!("Invalid input");
+        let line_cap = parse::captures(&LINE_RE, s.trim(), "an ingredient like '7 ORE'")?;
+        Ok(Ingredient {
+            chemical: line_cap[2].to_string(),
+            quantity: parse::number(&line_cap[1])?,
+        })
     }
 }
 
@@ -58,37 +50,16 @@ impl fmt::Display for Recipe {
     }
 }
 
-fn read_inputs(filename: String) -> HashMap<String, Recipe> {
-    let file_in = fs::File::open(filename).expect("Can't open file");
-    let file_reader = io::BufReader::new(file_in);
-
-    let recipe_list: Vec<Recipe> = file_reader
-        .lines()
-        .filter_map(io::Result::ok)
-        .map(|line| {
-            let split_by_produce: Vec<&str> = line.split(" => ").collect();
-            if split_by_produce.len() != 2 {
-                panic!("Invalid line");
-            }
-
-            let inputs: Vec<&str> = split_by_produce[0].split(", ").collect();
-            let output: &str = split_by_produce[1];
+fn parse_recipe(line: &str) -> Result<Recipe, Reason> {
+    let (inputs, output) = parse::split_once(line, " => ")?;
 
-            Recipe {
-                inputs: inputs
-                    .iter()
-                    .map(|s| Ingredient::from_string(s.to_string()))
-                    .collect(),
-                output: Ingredient::from_string(output.to_string()),
-            }
-        })
-        .collect();
-    let mut recipies: HashMap<String, Recipe> = HashMap::new();
-    for r in recipe_list {
-        let k = r.output.chemical.clone();
-        recipies.insert(k, r);
-    }
-    recipies
+    Ok(Recipe {
+        inputs: inputs
+            .split(", ")
+            .map(Ingredient::parse)
+            .collect::<Result<_, _>>()?,
+        output: Ingredient::parse(output)?,
+    })
 }
 
 fn get_number_of_steps(
@@ -118,7 +89,7 @@ fn get_fuel_cost(fuel_quantity: i128, recipies: &HashMap<String, Recipe>) -> i12
     let ore = "ORE".to_string();
     let fuel = "FUEL".to_string();
     let mut steps: HashMap<String, i128> = HashMap::new();
-    let fuel_steps = get_number_of_steps(&fuel, &recipies, &mut steps);
+    let _fuel_steps = get_number_of_steps(&fuel, recipies, &mut steps);
 
     let mut have: HashMap<&String, i128> = HashMap::new();
     let mut extras: HashMap<&String, i128> = HashMap::new();
@@ -187,48 +158,153 @@ fn get_fuel_cost(fuel_quantity: i128, recipies: &HashMap<String, Recipe>) -> i12
     ore_quantity
 }
 
-pub fn run() {
-    let recipies = read_inputs("data/day14.txt".to_string());
-
-    // Part 1
-    let cost_of_one_fuel = get_fuel_cost(1, &recipies);
-    println!("{}", cost_of_one_fuel);
-
-    // Part 2
-    let ore: i128 = 1000000000000;
+pub struct Day14 {
+    recipies: HashMap<String, Recipe>,
+}
 
-    let search = |start, increment: &dyn Fn(i128) -> i128| -> (i128, i128) {
-        let mut f = start;
-        let mut prev_f = start;
-        let mut o = get_fuel_cost(f, &recipies);
+impl Solution for Day14 {
+    fn parse(input: &str) -> Result<Self, Box<dyn Error>> {
+        let recipe_list = parse::numbered_lines(input, parse_recipe)?;
+
+        // Everything that's used has to be made somewhere, apart from ORE,
+        // and there has to be a way to make FUEL at all
+        let made: HashSet<&str> = recipe_list
+            .iter()
+            .map(|(_, r)| r.output.chemical.as_str())
+            .collect();
+        let missing = |chemical: &str| Reason::Missing(format!("a recipe for {}", chemical));
+        for (line, r) in &recipe_list {
+            if let Some(i) = r
+                .inputs
+                .iter()
+                .find(|i| i.chemical != "ORE" && !made.contains(i.chemical.as_str()))
+            {
+                return Err(Box::new(ParseError {
+                    line: *line,
+                    column: None,
+                    reason: missing(&i.chemical),
+                }));
+            }
+        }
+        if !made.contains("FUEL") {
+            return Err(Box::new(ParseError {
+                line: recipe_list.last().map_or(1, |(line, _)| *line),
+                column: None,
+                reason: missing("FUEL"),
+            }));
+        }
 
-        while o < ore {
-            prev_f = f;
-            f = increment(f);
-            o = get_fuel_cost(f, &recipies);
+        let mut recipies: HashMap<String, Recipe> = HashMap::new();
+        for (_, r) in recipe_list {
+            let k = r.output.chemical.clone();
+            recipies.insert(k, r);
         }
+        Ok(Day14 { recipies })
+    }
+
+    fn part1(&self) -> Answer {
+        get_fuel_cost(1, &self.recipies).into()
+    }
 
-        (prev_f, f)
-    };
+    fn part2(&self) -> Answer {
+        let recipies = &self.recipies;
+        let ore: i128 = 1000000000000;
 
-    let mut start = 1;
-    let increment = |a| a * 10;
-    let (lower, upper) = search(start, &increment);
+        let search = |start, increment: &dyn Fn(i128) -> i128| -> (i128, i128) {
+            let mut f = start;
+            let mut prev_f = start;
+            let mut o = get_fuel_cost(f, recipies);
 
-    let window_count = 10;
+            while o < ore {
+                prev_f = f;
+                f = increment(f);
+                o = get_fuel_cost(f, recipies);
+            }
 
-    start = lower;
-    let mut window_size = (upper - lower) / window_count;
+            (prev_f, f)
+        };
 
-    while window_size > 1 {
-        let increment = |a| a + window_size;
+        let mut start = 1;
+        let increment = |a| a * 10;
         let (lower, upper) = search(start, &increment);
 
-        window_size = (upper - lower) / window_count;
+        let window_count = 10;
+
         start = lower;
+        let mut window_size = (upper - lower) / window_count;
+
+        while window_size > 1 {
+            let increment = |a| a + window_size;
+            let (lower, upper) = search(start, &increment);
+
+            window_size = (upper - lower) / window_count;
+            start = lower;
+        }
+
+        let increment = |a| a + 1;
+        let (lower, _) = search(start, &increment);
+        lower.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SMALL: &str = "
+        10 ORE => 10 A
+        1 ORE => 1 B
+        7 A, 1 B => 1 C
+        7 A, 1 C => 1 D
+        7 A, 1 D => 1 E
+        7 A, 1 E => 1 FUEL";
+
+    const LEFTOVERS: &str = "
+        9 ORE => 2 A
+        8 ORE => 3 B
+        7 ORE => 5 C
+        3 A, 4 B => 1 AB
+        5 B, 7 C => 1 BC
+        4 C, 1 A => 1 CA
+        2 AB, 3 BC, 4 CA => 1 FUEL";
+
+    const LARGE: &str = "
+        157 ORE => 5 NZVS
+        165 ORE => 6 DCFZ
+        44 XJWVT, 5 KHKGT, 1 QDVJ, 29 NZVS, 9 GPVTF, 48 HKGWZ => 1 FUEL
+        12 HKGWZ, 1 GPVTF, 8 PSHF => 9 QDVJ
+        179 ORE => 7 PSHF
+        177 ORE => 5 HKGWZ
+        7 DCFZ, 7 PSHF => 2 XJWVT
+        165 ORE => 2 GPVTF
+        3 DCFZ, 7 NZVS, 5 HKGWZ, 10 PSHF => 8 KHKGT";
+
+    #[test]
+    fn ore_for_one_fuel() {
+        assert_eq!(Day14::parse(SMALL).unwrap().part1(), Answer::Number(31));
+        assert_eq!(
+            Day14::parse(LEFTOVERS).unwrap().part1(),
+            Answer::Number(165)
+        );
+        assert_eq!(Day14::parse(LARGE).unwrap().part1(), Answer::Number(13312));
     }
 
-    let increment = |a| a + 1;
-    let (lower, _) = search(start, &increment);
-    println!("{}", lower);
+    #[test]
+    fn every_chemical_needs_a_recipe() {
+        let error = Day14::parse("10 ORE => 10 A\n\n7 A, 1 B => 1 FUEL\n")
+            .map(|_| ())
+            .unwrap_err();
+        assert_eq!(error.to_string(), "line 3: missing a recipe for B");
+
+        let error = Day14::parse("10 ORE => 10 A\n7 A => 1 B\n")
+            .map(|_| ())
+            .unwrap_err();
+        assert_eq!(error.to_string(), "line 2: missing a recipe for FUEL");
+    }
+
+    #[test]
+    fn fuel_from_a_trillion_ore() {
+        let day = Day14::parse(LARGE).unwrap();
+        assert_eq!(day.part2(), Answer::Number(82892753));
+    }
 }
@@ -1,89 +1,41 @@
-use std::cmp;
-use std::fmt;
-use std::ops;
+use std::error::Error;
 
-use crate::intcode::{VirtualMachine, VirtualMachineState, load_program};
+use crate::animation::{no_frames, Frames};
+use crate::geom::{Direction, Point};
+use crate::grid::Grid;
+use crate::image::{self, Image, Rgb, BLACK};
+use crate::intcode::{parse_program, VirtualMachine, VirtualMachineState};
+use crate::search::{astar, bfs};
+use crate::solution::{Answer, Solution};
 
+use std::cell::OnceCell;
 use std::collections::HashMap;
-use std::collections::HashSet;
 
-#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
-struct Point {
-    x: i128,
-    y: i128,
-}
-
-impl fmt::Display for Point {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{},{}", self.x, self.y)
-    }
-}
-
-impl Ord for Point {
-    fn cmp(&self, other: &Self) -> cmp::Ordering {
-        self.y.cmp(&other.y).then_with(|| self.x.cmp(&other.x))
-    }
-}
-
-impl PartialOrd for Point {
-    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
-        Some(self.cmp(other))
+// The movement command for each direction
+fn command(direction: Direction) -> i128 {
+    match direction {
+        Direction::North => 1,
+        Direction::South => 2,
+        Direction::West => 3,
+        Direction::East => 4,
     }
 }
 
-impl ops::Add for Point {
-    type Output = Self;
-
-    fn add(self, other: Self) -> Self {
-        Self {
-            x: self.x + other.x,
-            y: self.y + other.y,
-        }
-    }
-}
-
-impl ops::AddAssign for Point {
-    fn add_assign(&mut self, other: Self) {
-        *self = Self {
-            x: self.x + other.x,
-            y: self.y + other.y,
-        };
-    }
-}
-
-#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
-enum Direction {
-    North,
-    South,
-    East,
-    West,
-}
-
-impl Direction {
-    fn inverse(self) -> Direction {
-        match self {
-            Direction::North => Direction::South,
-            Direction::South => Direction::North,
-            Direction::East => Direction::West,
-            Direction::West => Direction::East,
-        }
-    }
-}
-
-fn generate_direction_table() -> HashMap<Direction, Point> {
-    let mut directions: HashMap<Direction, Point> = HashMap::new();
-    directions.insert(Direction::North, Point { x: 0, y: -1 });
-    directions.insert(Direction::South, Point { x: 0, y: 1 });
-    directions.insert(Direction::East, Point { x: 1, y: 0 });
-    directions.insert(Direction::West, Point { x: -1, y: 0 });
-    directions
-}
-
-lazy_static! {
-    static ref DIRECTIONS: HashMap<Direction, Point> = generate_direction_table();
+// The map explored so far, with the droid on it
+fn render(map: &HashMap<Point, i128>, droid: Point) -> String {
+    let (grid, corner) = Grid::from_sparse(map, -1);
+    let mut tiles = grid.map(|&tile| match tile {
+        0 => '#',
+        1 => '.',
+        2 => 'O',
+        _ => ' ',
+    });
+    tiles[droid - corner] = 'D';
+    tiles.render(|&c| c)
 }
 
-fn run_program(program: &Vec<i128>) -> HashMap<Point, i128> {
+// Explore the whole map, sending a frame each time the droid reports back
+fn run_program(program: &[i128], frames: Frames) -> HashMap<Point, i128> {
     let mut vm = VirtualMachine::new(program);
 
     // The remote control program executes the following steps in a loop forever:
@@ -92,7 +44,7 @@ fn run_program(program: &Vec<i128>) -> HashMap<Point, i128> {
     //   - Wait for the repair droid to finish the movement operation.
     //   - Report on the status of the repair droid via an output instruction.
 
-    let mut current = Point { x: 0, y: 0 };
+    let mut current = Point::ORIGIN;
     let mut path: Vec<(Direction, Point)> = Vec::new();
 
     let mut map: HashMap<Point, i128> = HashMap::new();
@@ -108,10 +60,10 @@ fn run_program(program: &Vec<i128>) -> HashMap<Point, i128> {
                 //   east (4).
 
                 let mut next: Option<(Direction, Point)> = None;
-                for d in DIRECTIONS.iter() {
-                    let p_next = current + *d.1;
+                for &d in Direction::ALL.iter() {
+                    let p_next = current.step(d);
                     if !map.contains_key(&p_next) {
-                        next = Some((*d.0, p_next));
+                        next = Some((d, p_next));
                         break;
                     }
                 }
@@ -134,20 +86,7 @@ fn run_program(program: &Vec<i128>) -> HashMap<Point, i128> {
                     }
                 }
 
-                match direction {
-                    Direction::North => {
-                        vm.add_input(1);
-                    }
-                    Direction::South => {
-                        vm.add_input(2);
-                    }
-                    Direction::East => {
-                        vm.add_input(3);
-                    }
-                    Direction::West => {
-                        vm.add_input(4);
-                    }
-                }
+                vm.add_input(command(direction));
             }
             VirtualMachineState::Output(v) => {
                 // The repair droid can reply with any of the following status codes:
@@ -155,14 +94,13 @@ fn run_program(program: &Vec<i128>) -> HashMap<Point, i128> {
                 //    1: The repair droid has moved one step in the requested direction.
                 //    2: The repair droid has moved one step in the requested direction;
                 //       its new position is the location of the oxygen system.
-                if !map.contains_key(&current) {
-                    map.insert(current, v);
-                }
+                map.entry(current).or_insert(v);
 
                 if v == 0 {
                     let backtrack = path.pop().unwrap();
                     current = backtrack.1;
                 }
+                frames(&|| render(&map, current));
             }
             VirtualMachineState::Terminated => {
                 break;
@@ -173,71 +111,104 @@ fn run_program(program: &Vec<i128>) -> HashMap<Point, i128> {
     map
 }
 
-fn bredth_first_search_map(
-    start: &Point,
-    map: &HashMap<Point, i128>,
-    stop: &mut dyn FnMut(Point, i128) -> bool,
-) {
-    let mut frontier: Vec<(Point, i128)> = Vec::new();
-    let mut visited: HashSet<Point> = HashSet::new();
+// The open locations next to p. Unexplored locations count as walls.
+fn open_neighbours(map: &HashMap<Point, i128>, p: Point) -> impl Iterator<Item = Point> + '_ {
+    IntoIterator::into_iter(p.neighbours4()).filter(move |n| map.get(n).is_some_and(|&t| t != 0))
+}
 
-    if !map.contains_key(start) {
-        panic!("Invalid start point");
-    }
+// The length of the shortest path from the start to the oxygen system
+fn distance_to_oxygen(map: &HashMap<Point, i128>, oxygen: Point) -> usize {
+    let search = astar(
+        Point::ORIGIN,
+        |&p| open_neighbours(map, p).map(|n| (n, 1)),
+        |p| p.manhattan(oxygen) as usize,
+        |&p| p == oxygen,
+    );
+    search.distance(&oxygen).unwrap()
+}
 
-    visited.insert(*start);
-    frontier.push((*start, 0));
+// How long it takes oxygen to spread from the oxygen system to every open
+// location, a minute per step
+fn time_to_fill(map: &HashMap<Point, i128>, oxygen: Point) -> usize {
+    let search = bfs(oxygen, |&p| open_neighbours(map, p), |_| false);
+    search.visited().map(|(_, minutes)| minutes).max().unwrap()
+}
 
-    while !frontier.is_empty() {
-        let p = frontier.remove(0);
+// Walls, open floor, and the oxygen system, with the unexplored parts black
+const PALETTE: [Rgb; 3] = [[100, 100, 100], [230, 230, 230], [40, 120, 240]];
 
-        for d in DIRECTIONS.iter() {
-            let p_next = p.0 + *d.1;
-            if !visited.contains(&p_next) && map.contains_key(&p_next) {
-                let distance = p.1 + 1;
+pub struct Day15 {
+    program: Vec<i128>,
+    // The map and where the oxygen system is, explored the first time
+    // either part needs them, so the droid only goes round once
+    explored: OnceCell<(HashMap<Point, i128>, Point)>,
+}
 
-                let next = map[&p_next];
-                if next == 0 {
-                    continue;
-                }
+impl Day15 {
+    fn explored(&self) -> &(HashMap<Point, i128>, Point) {
+        self.explored.get_or_init(|| {
+            let map = run_program(&self.program, &mut no_frames);
+            let oxygen = *map
+                .iter()
+                .find(|(_, &tile)| tile == 2)
+                .expect("The droid didn't find the oxygen system")
+                .0;
+            (map, oxygen)
+        })
+    }
+}
 
-                frontier.push((p_next, distance));
-                visited.insert(p_next);
+impl Solution for Day15 {
+    fn parse(input: &str) -> Result<Self, Box<dyn Error>> {
+        Ok(Day15 {
+            program: parse_program(input)?,
+            explored: OnceCell::new(),
+        })
+    }
 
-                if stop(p_next, distance) {
-                    break;
-                }
-            }
-        }
+    fn part1(&self) -> Answer {
+        let (map, oxygen) = self.explored();
+        distance_to_oxygen(map, *oxygen).into()
+    }
+
+    fn part2(&self) -> Answer {
+        let (map, oxygen) = self.explored();
+        time_to_fill(map, *oxygen).into()
+    }
+
+    fn pictures(&self) -> Vec<(String, Image)> {
+        let (map, _) = self.explored();
+        let picture = image::from_sparse(map, BLACK, |&tile| PALETTE[tile as usize]);
+        vec![("map".to_string(), picture)]
+    }
+
+    // The droid exploring, a frame per step
+    fn animate(&self, frames: Frames) {
+        run_program(&self.program, frames);
     }
 }
 
-pub fn run() {
-    let program = load_program("data/day15.txt".to_string());
-    let map = run_program(&program);
-
-    // Part 1
-    let start = Point { x: 0, y: 0 };
-    let mut oxygen = Point { x: 0, y: 0 };
-    let mut min_distance = 0;
-    bredth_first_search_map(&start, &map, &mut |p, distance| -> bool {
-        if map[&p] == 2 {
-            oxygen = p.clone();
-            min_distance = distance;
-            true
-        } else {
-            false
-        }
-    });
-    println!("Shortest path to oxygen @ {} = {}", oxygen, min_distance);
-    
-    // Part 2
-    let mut max_distance = 0;
-    bredth_first_search_map(&oxygen, &map, &mut |_, distance| -> bool {
-        if distance > max_distance {
-            max_distance = distance;
-        }
-        false
-    });
-    println!("Max distance from oxygen is {}", max_distance);
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn oxygen_spreads() {
+        let example = Grid::parse_padded(" ##\n#..##\n#.#..#\n#.O.#\n ###\n", ' ').unwrap();
+        let oxygen = example.find_all(&'O').next().unwrap();
+        let map = example
+            .map(|&tile| match tile {
+                '#' => 0,
+                '.' => 1,
+                'O' => 2,
+                _ => -1,
+            })
+            .to_sparse(Point::ORIGIN, &-1);
+
+        assert_eq!(time_to_fill(&map, oxygen), 4);
+        assert_eq!(
+            render(&map, Point::new(1, 1)),
+            " ##   \n#D.## \n#.#..#\n#.O.# \n ###  \n"
+        );
+    }
 }
@@ -1,17 +1,14 @@
-use std::fs;
+use std::error::Error;
 
-fn read_input(filename: String) -> Vec<u8> {
-    let digits: Vec<u8> = fs::read_to_string(filename)
-        .expect("Can't read file")
-        .chars()
-        .map(|d| d.to_digit(10).unwrap() as u8)
-        .collect();
+use crate::solution::{Answer, Solution};
 
-    digits
+// The digits of a signal, as text
+fn message(digits: &[u8]) -> Answer {
+    Answer::Text(digits.iter().map(|d| d.to_string()).collect())
 }
 
-fn run_fft(start: &Vec<u8>) -> Vec<u8> {
-    let mut input = start.clone();
+fn run_fft(start: &[u8]) -> Vec<u8> {
+    let mut input = start.to_vec();
     for _ in 0..100 {
         // FFT operates in repeated phases. In each phase, a new list is constructed
         // with the same length as the input list. This new list is also used as the
@@ -29,7 +26,7 @@ fn run_fft(start: &Vec<u8>) -> Vec<u8> {
 
             // The base pattern is 0, 1, 0, -1.
 
-            let base_pattern = vec![0, 1, 0, -1];
+            let base_pattern = [0, 1, 0, -1];
 
             // Then, repeat each value in the pattern a number of times equal to the
             // position in the output list being considered.
@@ -37,12 +34,12 @@ fn run_fft(start: &Vec<u8>) -> Vec<u8> {
             let mut j = 0;
 
             let mut acc: i64 = 0;
-            for k in 0..input.len() {
+            for (k, &v) in input.iter().enumerate() {
                 if (k + 1) % (i + 1) == 0 {
                     j = (j + 1) % base_pattern.len();
                 }
 
-                let v = input[k] as i64;
+                let v = v as i64;
                 let p = base_pattern[j];
                 acc += p * v;
             }
@@ -56,54 +53,103 @@ fn run_fft(start: &Vec<u8>) -> Vec<u8> {
     input
 }
 
-pub fn run() {
-    let input = read_input("data/day16.txt".to_string());
+pub struct Day16 {
+    input: Vec<u8>,
+}
+
+impl Solution for Day16 {
+    fn parse(input: &str) -> Result<Self, Box<dyn Error>> {
+        let input: Vec<u8> = input
+            .trim()
+            .chars()
+            .map(|d| {
+                d.to_digit(10)
+                    .map(|d| d as u8)
+                    .ok_or(format!("Invalid digit '{}'", d))
+            })
+            .collect::<Result<_, _>>()?;
+
+        if input.len() < 8 {
+            return Err("The signal is too short".into());
+        }
 
-    // Part 1
-    let part1 = run_fft(&input);
-    for i in 0..8 {
-        print!("{}", part1[i]);
+        Ok(Day16 { input })
     }
-    println!("");
-
-    // Part 2
-    // The first seven digits of your initial input signal also represent the message
-    // offset. The message offset is the location of the eight-digit message in the
-    // final output list. Specifically, the message offset indicates the number of
-    // digits to skip before reading the eight-digit message.
-    let mut offset: usize = 0;
-    let mut mult: usize = 1;
-    for i in 0..7 {
-        offset += (input[6 - i] as usize) * mult;
-        mult *= 10;
+
+    fn part1(&self) -> Answer {
+        let part1 = run_fft(&self.input);
+        message(&part1[..8])
     }
-    println!("Offset: {}", offset);
 
-    // The real signal is your puzzle input repeated 10000 times.
-    let mut real_signal: Vec<u8> = Vec::new();
-    for _ in 0..10000 {
-        for i in &input {
-            real_signal.push(*i);
+    fn part2(&self) -> Answer {
+        let input = &self.input;
+
+        // The first seven digits of your initial input signal also represent the message
+        // offset. The message offset is the location of the eight-digit message in the
+        // final output list. Specifically, the message offset indicates the number of
+        // digits to skip before reading the eight-digit message.
+        let mut offset: usize = 0;
+        let mut mult: usize = 1;
+        for i in 0..7 {
+            offset += (input[6 - i] as usize) * mult;
+            mult *= 10;
+        }
+
+        // The real signal is your puzzle input repeated 10000 times.
+        let mut real_signal: Vec<u8> = Vec::new();
+        for _ in 0..10000 {
+            for i in input {
+                real_signal.push(*i);
+            }
         }
-    }
 
-    //
-    // https://www.reddit.com/r/adventofcode/comments/ebf5cy/2019_day_16_part_2_understanding_how_to_come_up/
-    //
-    // The second half of the digits only require the remaining digits summed together to find the next digit.
-    //
+        //
+        // https://www.reddit.com/r/adventofcode/comments/ebf5cy/2019_day_16_part_2_understanding_how_to_come_up/
+        //
+        // The second half of the digits only require the remaining digits summed together to find the next digit.
+        //
 
-    if offset < real_signal.len() / 2 {
-        panic!("The trick doesn't work!");
+        if offset < real_signal.len() / 2 {
+            panic!("The trick doesn't work!");
+        }
+
+        for _ in 0..100 {
+            for i in (offset..real_signal.len() - 1).rev() {
+                real_signal[i] = (real_signal[i] + real_signal[i + 1]) % 10;
+            }
+        }
+
+        message(&real_signal[offset..offset + 8])
     }
+}
 
-    for _ in 0..100 {
-        for i in (offset..real_signal.len() - 1).rev() {
-            real_signal[i] = (real_signal[i] + real_signal[i + 1]) % 10;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn one_hundred_phases() {
+        let examples = [
+            ("80871224585914546619083218645595", "24176176"),
+            ("19617804207202209144916044189917", "73745418"),
+            ("69317163492948606335995924319873", "52432133"),
+        ];
+        for (signal, message) in examples.iter() {
+            let day = Day16::parse(signal).unwrap();
+            assert_eq!(day.part1(), Answer::Text(message.to_string()));
         }
     }
 
-    for i in 0..8 {
-        print!("{}", real_signal[offset + i]);
+    #[test]
+    fn real_signal_message() {
+        let examples = [
+            ("03036732577212944063491565474664", "84462026"),
+            ("02935109699940807407585447034323", "78725270"),
+            ("03081770884921959731165446850517", "53553731"),
+        ];
+        for (signal, message) in examples.iter() {
+            let day = Day16::parse(signal).unwrap();
+            assert_eq!(day.part2(), Answer::Text(message.to_string()));
+        }
     }
 }
@@ -1,352 +1,185 @@
-use std::fs;
+use std::error::Error;
 
 use std::collections::HashMap;
-use std::collections::HashSet;
 
-fn load_input(filename: String) -> Vec<Vec<char>> {
-    fs::read_to_string(filename)
-        .expect("Can't read file")
-        .split('\n')
-        .filter_map(|s: &str| {
-            if s.len() == 0 {
-                None
-            } else {
-                Some(s.chars().collect())
-            }
-        })
-        .collect()
-}
-
-fn bredth_first_search(
-    start: &(usize, usize),
-    map: &Vec<Vec<char>>,
-    stop: &mut dyn FnMut((usize, usize), u128) -> bool,
-) {
-    let (height, width) = (map.len(), map[0].len());
-    let mut frontier: Vec<((usize, usize), u128)> = Vec::new();
-    let mut visited: HashSet<(usize, usize)> = HashSet::new();
-
-    visited.insert(*start);
-    frontier.push((*start, 0));
-
-    if stop(*start, 0) {
-        return;
-    }
-
-    while !frontier.is_empty() {
-        let (current, distance) = frontier.remove(0);
-
-        let mut visit: Vec<(usize, usize)> = Vec::new();
-        if current.1 > 0 {
-            let up = (current.0, current.1 - 1);
-            visit.push(up);
-        }
-        if current.1 < (height - 1) {
-            let down = (current.0, current.1 + 1);
-            visit.push(down);
-        }
-        if current.0 > 0 {
-            let left = (current.0 - 1, current.1);
-            visit.push(left);
-        }
-        if current.0 < (width - 1) {
-            let rigth = (current.0 + 1, current.1);
-            visit.push(rigth);
-        }
-
-        let v_distance = distance + 1;
-        for v in visit {
-            if !visited.contains(&v) {
-                let next = map[v.1][v.0];
-                if next == '#' {
-                    continue;
-                }
-
-                frontier.push((v, v_distance));
-                visited.insert(v);
+use crate::geom::Point;
+use crate::grid::Grid;
+use crate::search::{bfs, dijkstra};
+use crate::solution::{Answer, Solution};
 
-                if stop(v, v_distance) {
-                    break;
-                }
-            }
-        }
-    }
+// Keys (and the doors they open) as bits, so a set of them fits in a u32
+fn key_bit(key: char) -> u32 {
+    1 << (key.to_ascii_lowercase() as u32 - 'a' as u32)
 }
 
-fn shortest_path(
-    start: &(usize, usize),
-    end: &(usize, usize),
-    map: &Vec<Vec<char>>,
-) -> Option<Vec<(usize, usize)>> {
-    let (height, width) = (map.len(), map[0].len());
-
-    let mut paths: HashMap<(usize, usize), u128> = HashMap::new();
-    bredth_first_search(start, map, &mut |p, d| -> bool {
-        paths.insert(p, d);
-        p == *end
-    });
-
-    if !paths.contains_key(&end) {
-        return None;
-    }
-
-    let mut path: Vec<(usize, usize)> = Vec::new();
-
-    let mut current: (usize, usize) = *end;
-    path.push(current);
-
-    while current != *start {
-        let mut visit: Vec<(usize, usize)> = Vec::new();
-        if current.1 > 0 {
-            let up = (current.0, current.1 - 1);
-            visit.push(up);
-        }
-        if current.1 < (height - 1) {
-            let down = (current.0, current.1 + 1);
-            visit.push(down);
-        }
-        if current.0 > 0 {
-            let left = (current.0 - 1, current.1);
-            visit.push(left);
-        }
-        if current.0 < (width - 1) {
-            let rigth = (current.0 + 1, current.1);
-            visit.push(rigth);
-        }
-
-        let closest = visit
-            .iter()
-            .filter(|p| paths.contains_key(&p))
-            .min_by_key(|p| paths[p])
-            .unwrap();
-
-        if *closest == current {
-            panic!("Stuck in local minima");
-        }
-
-        current = *closest;
-        path.push(current);
-    }
-
-    path.reverse();
-    Some(path)
+fn open_neighbours(map: &Grid<char>, p: Point) -> impl Iterator<Item = Point> + '_ {
+    map.neighbours(p)
+        .filter(|(_, &tile)| tile != '#')
+        .map(|(n, _)| n)
 }
 
-fn shortest_distance_to_all_keys(
-    current: &Vec<char>,
-    visited: &Vec<char>,
-    remaining: &Vec<char>,
-    paths: &HashMap<(char, char), (usize, Vec<char>)>,
-    cache: &mut HashMap<String, usize>,
-) -> Option<usize> {
-    let v_p: String = visited.iter().collect();
-    let c_p: String = current.iter().collect();
-    let r_p: String = remaining.iter().collect();
+fn solve(map: &Grid<char>) -> usize {
+    let mut keys: Vec<(char, Point)> = Vec::new();
+    let mut robots: Vec<Point> = Vec::new();
 
-    let hash = format!("{}-{}-{}", v_p, c_p, r_p).to_string();
-    if cache.contains_key(&hash) {
-        return Some(cache[&hash]);
+    for (p, &tile) in map.iter() {
+        if tile.is_ascii_lowercase() {
+            keys.push((tile, p));
+        } else if !tile.is_ascii_uppercase() && (tile != '#') && (tile != '.') {
+            robots.push(p);
+        }
     }
 
-    let has_key = |d: &char| -> bool {
-        let k: char = d.to_lowercase().collect::<Vec<_>>()[0];
-        for c in current {
-            if c == &k {
-                return true;
-            }
-        }
-        for v in visited {
-            if v == &k {
-                return true;
+    // The steps from each robot's starting point and each key to every key
+    // it can reach, and the doors on the way as the keys they need
+    let places: Vec<Point> = robots
+        .iter()
+        .copied()
+        .chain(keys.iter().map(|&(_, p)| p))
+        .collect();
+    let mut routes: HashMap<(usize, usize), (usize, u32)> = HashMap::new();
+    for (from, &start) in places.iter().enumerate() {
+        let search = bfs(start, |&p| open_neighbours(map, p), |_| false);
+        for (k, &(_, key)) in keys.iter().enumerate() {
+            if let Some(path) = search.path_to(&key) {
+                let doors = path
+                    .iter()
+                    .map(|&p| map[p])
+                    .filter(|tile| tile.is_ascii_uppercase())
+                    .fold(0, |needed, door| needed | key_bit(door));
+                routes.insert((from, robots.len() + k), (path.len() - 1, doors));
             }
         }
-        false
-    };
-
-    let mut min_distance: Option<usize> = None;
-    for &c in current {
-        let reachable_keys: Vec<char> = remaining
-            .iter()
-            .filter_map(|k| -> Option<char> {
-                let current_k: (char, char) = (c, *k);
-
-                if !paths.contains_key(&current_k) {
-                    return None;
-                }
-
-                let path = &paths[&current_k];
-                let mut door_in_way = false;
+    }
 
-                for d in &path.1 {
-                    if !has_key(d) {
-                        door_in_way = true;
-                        break;
+    // Search the states of where each robot is and which keys have been
+    // collected, each move taking one robot to a key it can get to
+    let all_keys = keys.iter().fold(0, |all, &(key, _)| all | key_bit(key));
+    let start: (Vec<usize>, u32) = ((0..robots.len()).collect(), 0);
+    let search = dijkstra(
+        start,
+        |(at, have)| {
+            let mut moves: Vec<((Vec<usize>, u32), usize)> = Vec::new();
+            for (robot, &from) in at.iter().enumerate() {
+                for (k, &(key, _)) in keys.iter().enumerate() {
+                    if have & key_bit(key) != 0 {
+                        continue;
+                    }
+                    if let Some(&(steps, doors)) = routes.get(&(from, robots.len() + k)) {
+                        if doors & !have == 0 {
+                            let mut moved = at.clone();
+                            moved[robot] = robots.len() + k;
+                            moves.push(((moved, have | key_bit(key)), steps));
+                        }
                     }
                 }
-
-                if !door_in_way {
-                    Some(*k)
-                } else {
-                    None
-                }
-            })
-            .collect();
-
-        if reachable_keys.len() == 0 {
-            continue;
-        }
-
-        let mut visited_k = visited.clone();
-        visited_k.push(c);
-        visited_k.sort();
-
-        for k in reachable_keys {
-            let mut distance = paths[&(c, k)].0;
-
-            if remaining.len() > 1 {
-                let mut remaining_k = remaining.clone();
-                remaining_k.retain(|&r| r != k);
-
-                let current_k: Vec<char> = current
-                    .iter()
-                    .map(|r| if r == &c { k } else { *r })
-                    .collect();
-
-                let remaining_distance = shortest_distance_to_all_keys(
-                    &current_k,
-                    &visited_k,
-                    &remaining_k,
-                    paths,
-                    cache,
-                );
-                if remaining_distance.is_none() {
-                    continue;
-                }
-
-                distance += remaining_distance.unwrap();
             }
+            moves
+        },
+        |(_, have)| *have == all_keys,
+    );
 
-            if min_distance.is_none() || (distance < min_distance.unwrap()) {
-                min_distance = Some(distance);
-            }
-        }
-    }
-
-    if min_distance.is_some() {
-        let d = min_distance.unwrap();
-        cache.insert(hash, d);
-        Some(d)
-    } else {
-        None
-    }
+    let end = search.goal().expect("Some keys can't be reached");
+    search.distance(end).unwrap()
 }
 
-fn solve(map: &Vec<Vec<char>>) -> usize {
-    let (height, width) = (map.len(), map[0].len());
-
-    let mut doors: HashMap<char, (usize, usize)> = HashMap::new();
-    let mut keys: HashMap<char, (usize, usize)> = HashMap::new();
-    let mut robots: HashMap<char, (usize, usize)> = HashMap::new();
+pub struct Day18 {
+    map: Grid<char>,
+}
 
-    for y in 0..height {
-        for x in 0..width {
-            let tile = map[y][x];
-            if tile.is_ascii_uppercase() {
-                doors.insert(tile, (x, y));
-            } else if tile.is_ascii_lowercase() {
-                keys.insert(tile, (x, y));
-            } else if (tile != '#') && (tile != '.') {
-                robots.insert(tile, (x, y));
-            }
-        }
+impl Solution for Day18 {
+    fn parse(input: &str) -> Result<Self, Box<dyn Error>> {
+        Ok(Day18 {
+            map: Grid::parse(input)?,
+        })
     }
 
-    let a_to_b = |a, b| -> Option<(usize, Vec<char>)> {
-        let is_path_a_to_b = shortest_path(a, b, &map);
-        if is_path_a_to_b.is_none() {
-            return None;
-        }
-
-        let path_a_to_b = is_path_a_to_b.unwrap();
-        let doors_between_a_and_b = path_a_to_b
-            .iter()
-            .filter_map(|p| -> Option<char> {
-                let tile = map[p.1][p.0];
-                if tile.is_ascii_uppercase() {
-                    Some(tile)
-                } else {
-                    None
-                }
-            })
-            .collect();
-        Some((path_a_to_b.len() - 1, doors_between_a_and_b))
-    };
+    fn part1(&self) -> Answer {
+        solve(&self.map).into()
+    }
 
-    let mut paths: HashMap<(char, char), (usize, Vec<char>)> = HashMap::new();
-    for a in &keys {
-        for r in &robots {
-            let p = a_to_b(&r.1, &a.1);
-            if p.is_some() {
-                paths.insert((*r.0, *a.0), p.unwrap());
+    fn part2(&self) -> Answer {
+        let mut map = self.map.clone();
+
+        // Split the entrance into four, each with its own robot
+        let entrance = map.find_all(&'@').next().expect("The map has no entrance");
+        let replacement = ["^#>", "###", "@#<"];
+        for (dy, row) in replacement.iter().enumerate() {
+            for (dx, tile) in row.chars().enumerate() {
+                let p = entrance + Point::new(dx as i32 - 1, dy as i32 - 1);
+                map.set(p, tile)
+                    .expect("The entrance is at the edge of the map");
             }
         }
-        for b in &keys {
-            if a.0 == b.0 {
-                continue;
-            }
-            let p = a_to_b(&a.1, &b.1);
-            if p.is_some() {
-                paths.insert((*a.0, *b.0), p.unwrap());
-            }
-        }
-    }
-
-    let mut all_keys: Vec<char> = keys.keys().map(|k| *k).collect();
-    all_keys.sort();
 
-    let mut all_robots: Vec<char> = robots.keys().map(|k| *k).collect();
-    all_robots.sort();
-
-    let mut cache: HashMap<String, usize> = HashMap::new();
-
-    shortest_distance_to_all_keys(&all_robots, &vec![], &all_keys, &paths, &mut cache).unwrap()
+        solve(&map).into()
+    }
 }
 
-pub fn run() {
-    let mut map = load_input("data/day18.txt".to_string());
-
-    // Part 1
-    let part1 = solve(&map);
-    println!("{}", part1);
-
-    // Part 2
-    let (height, width) = (map.len(), map[0].len());
-    let mut found_robot = false;
-    for y in 0..height {
-        for x in 0..width {
-            let tile = map[y][x];
-            if tile == '@' {
-                map[y - 1][x - 1] = '^';
-                map[y - 1][x] = '#';
-                map[y - 1][x + 1] = '>';
-
-                map[y][x - 1] = '#';
-                map[y][x] = '#';
-                map[y][x + 1] = '#';
-
-                map[y + 1][x - 1] = '@';
-                map[y + 1][x] = '#';
-                map[y + 1][x + 1] = '<';
-
-                found_robot = true;
-                break;
-            }
-        }
-        if found_robot {
-            break;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collects_every_key() {
+        let examples = [
+            ("#########\n#b.A.@.a#\n#########", 8),
+            (
+                "########################
+                 #f.D.E.e.C.b.A.@.a.B.c.#
+                 ######################.#
+                 #d.....................#
+                 ########################",
+                86,
+            ),
+            (
+                "########################
+                 #...............b.C.D.f#
+                 #.######################
+                 #.....@.a.B.c.d.A.e.F.g#
+                 ########################",
+                132,
+            ),
+            (
+                "#################
+                 #i.G..c...e..H.p#
+                 ########.########
+                 #j.A..b...f..D.o#
+                 ########@########
+                 #k.E..a...g..B.n#
+                 ########.########
+                 #l.F..d...h..C.m#
+                 #################",
+                136,
+            ),
+            (
+                "########################
+                 #@..............ac.GI.b#
+                 ###d#e#f################
+                 ###A#B#C################
+                 ###g#h#i################
+                 ########################",
+                81,
+            ),
+        ];
+        for (map, steps) in examples.iter() {
+            let day = Day18::parse(map).unwrap();
+            assert_eq!(day.part1(), Answer::Number(*steps));
         }
     }
 
-    let part2 = solve(&map);
-    println!("{}", part2);
+    #[test]
+    fn four_robots() {
+        let day = Day18::parse(
+            "#######
+             #a.#Cd#
+             ##...##
+             ##.@.##
+             ##...##
+             #cB#Ab#
+             #######",
+        )
+        .unwrap();
+        assert_eq!(day.part2(), Answer::Number(8));
+    }
 }
@@ -1,36 +1,18 @@
 use std::cmp;
-use std::fs;
+use std::error::Error;
 
 use std::collections::HashMap;
-use std::collections::HashSet;
 
-fn load_input(filename: String) -> Vec<Vec<char>> {
-    fs::read_to_string(filename)
-        .expect("Can't read file")
-        .split('\n')
-        .filter_map(|s: &str| {
-            if s.len() == 0 {
-                None
-            } else {
-                Some(s.chars().collect())
-            }
-        })
-        .collect()
-}
+use crate::geom::{Direction, Point};
+use crate::grid::Grid;
+use crate::search::bfs;
+use crate::solution::{Answer, Solution};
 
+// Which edge of the maze's doughnut a portal is on
 #[derive(PartialEq, Clone, Copy)]
-enum Direction {
-    In,
-    Out,
-}
-
-impl Direction {
-    fn inverse(&self) -> Direction {
-        match self {
-            Direction::In => Direction::Out,
-            Direction::Out => Direction::In,
-        }
-    }
+enum Side {
+    Inner,
+    Outer,
 }
 
 #[derive(PartialEq)]
@@ -39,24 +21,21 @@ enum Tile {
     End,
     Wall,
     Floor,
-    Portal((String, Direction)),
+    Portal((String, Side)),
 }
 
 fn get_portals(
     label: &String,
-    map: &HashMap<(usize, usize), Tile>,
-    multi_level: bool,
-) -> Vec<((usize, usize), Direction)> {
-    let mut portals: Vec<((usize, usize), Direction)> = Vec::new();
+    map: &HashMap<Point, Tile>,
+    _multi_level: bool,
+) -> Vec<(Point, Side)> {
+    let mut portals: Vec<(Point, Side)> = Vec::new();
 
     for m in map {
-        match m.1 {
-            Tile::Portal(p) => {
-                if p.0.cmp(label) == cmp::Ordering::Equal {
-                    portals.push((*m.0, p.1.inverse()));
-                }
+        if let Tile::Portal(p) = m.1 {
+            if p.0.cmp(label) == cmp::Ordering::Equal {
+                portals.push((*m.0, p.1));
             }
-            _ => {}
         }
     }
 
@@ -64,62 +43,56 @@ fn get_portals(
 }
 
 fn get_adjacent(
-    current: &(usize, usize, usize),
-    map: &HashMap<(usize, usize), Tile>,
+    current: &(Point, usize),
+    map: &HashMap<Point, Tile>,
     multi_level: bool,
-) -> Vec<(usize, usize, usize)> {
-    let mut adjacent: Vec<(usize, usize, usize)> = Vec::new();
+) -> Vec<(Point, usize)> {
+    let mut adjacent: Vec<(Point, usize)> = Vec::new();
 
-    let mut possible_adjacent = vec![
-        (current.0, current.1 - 1, current.2),
-        (current.0, current.1 + 1, current.2),
-        (current.0 - 1, current.1, current.2),
-        (current.0 + 1, current.1, current.2),
-    ];
+    let (position, level) = *current;
+    let mut possible_adjacent: Vec<(Point, usize)> =
+        position.neighbours4().iter().map(|&p| (p, level)).collect();
 
-    match &map[&(current.0, current.1)] {
-        Tile::Portal(p) => {
-            let portals: Vec<(usize, usize, usize)> = get_portals(&p.0, map, multi_level)
-                .iter()
-                .filter_map(|p| {
-                    let pos = p.0;
-                    if pos != (current.0, current.1) {
-                        if multi_level == false {
-                            Some((pos.0, pos.1, current.2))
-                        } else {
-                            match p.1 {
-                                Direction::In => Some((pos.0, pos.1, current.2 + 1)),
-                                Direction::Out => {
-                                    if current.2 != 0 {
-                                        Some((pos.0, pos.1, current.2 - 1))
-                                    } else {
-                                        None
-                                    }
+    if let Tile::Portal(p) = &map[&position] {
+        let portals: Vec<(Point, usize)> = get_portals(&p.0, map, multi_level)
+            .iter()
+            .filter_map(|&(pos, side)| {
+                if pos != position {
+                    if !multi_level {
+                        Some((pos, level))
+                    } else {
+                        // Coming out of an outer portal means going in
+                        // through an inner one, one level deeper
+                        match side {
+                            Side::Outer => Some((pos, level + 1)),
+                            Side::Inner => {
+                                if level != 0 {
+                                    Some((pos, level - 1))
+                                } else {
+                                    None
                                 }
                             }
                         }
-                    } else {
-                        None
                     }
-                })
-                .collect();
-            match portals.len() {
-                0 => {}
-                1 => {
-                    possible_adjacent.push(portals[0]);
-                }
-                _ => {
-                    panic!("Found too many portals!");
+                } else {
+                    None
                 }
+            })
+            .collect();
+        match portals.len() {
+            0 => {}
+            1 => {
+                possible_adjacent.push(portals[0]);
+            }
+            _ => {
+                panic!("Found too many portals!");
             }
         }
-        _ => {}
     }
 
     for p in possible_adjacent {
-        let map_p = (p.0, p.1);
-        if map.contains_key(&map_p) {
-            match &map[&map_p] {
+        if let Some(tile) = map.get(&p.0) {
+            match tile {
                 Tile::Wall => {}
                 _ => {
                     adjacent.push(p);
@@ -131,193 +104,148 @@ fn get_adjacent(
     adjacent
 }
 
-fn bredth_first_search(
-    start: &(usize, usize, usize),
-    map: &HashMap<(usize, usize), Tile>,
-    multi_level: bool,
-    stop: &mut dyn FnMut((usize, usize, usize), u128) -> bool,
-) {
-    let mut frontier: Vec<((usize, usize, usize), u128)> = Vec::new();
-    let mut visited: HashSet<(usize, usize, usize)> = HashSet::new();
-
-    visited.insert(*start);
-    frontier.push((*start, 0));
-
-    if stop(frontier[0].0, 0) {
-        return;
-    }
-
-    while !frontier.is_empty() {
-        let (current, distance) = frontier.remove(0);
-        let v_distance = distance + 1;
-
-        let visit: Vec<(usize, usize, usize)> = get_adjacent(&current, map, multi_level);
-        for v in visit {
-            if !visited.contains(&v) {
-                if map[&(v.0, v.1)] == Tile::Wall {
+fn get_tile(p: Point, input: &Grid<char>) -> Option<Tile> {
+    match input.get(p)? {
+        '.' => {
+            for &d in Direction::ALL.iter() {
+                let (near, far) = (p.step(d), p.step(d).step(d));
+                if !input[near].is_ascii_uppercase() {
                     continue;
                 }
 
-                frontier.push((v, v_distance));
-                visited.insert(v);
-
-                if stop(v, v_distance) {
-                    return;
+                // Labels read left to right or top to bottom
+                let label: String = match d {
+                    Direction::North | Direction::West => [input[far], input[near]],
+                    Direction::South | Direction::East => [input[near], input[far]],
                 }
+                .iter()
+                .collect();
+
+                // The outer portals have their labels against the edge of
+                // the map
+                let outer = far.x == 0
+                    || far.y == 0
+                    || far.x as usize == input.width() - 1
+                    || far.y as usize == input.height() - 1;
+
+                return match label.as_str() {
+                    "AA" => Some(Tile::Start),
+                    "ZZ" => Some(Tile::End),
+                    _ if outer => Some(Tile::Portal((label, Side::Outer))),
+                    _ => Some(Tile::Portal((label, Side::Inner))),
+                };
             }
+
+            Some(Tile::Floor)
         }
+        '#' => Some(Tile::Wall),
+        _ => None,
     }
 }
 
-fn shortest_path(
-    start: &(usize, usize, usize),
-    end: &(usize, usize, usize),
-    map: &HashMap<(usize, usize), Tile>,
-    multi_level: bool,
-) -> Option<Vec<(usize, usize, usize)>> {
-    let mut paths: HashMap<(usize, usize, usize), u128> = HashMap::new();
-    bredth_first_search(start, map, multi_level, &mut |p, d| -> bool {
-        paths.insert(p, d);
-        (p.2 == 0) && (p.0 == end.0) && (p.1 == end.1)
-    });
+pub struct Day20 {
+    map: HashMap<Point, Tile>,
+    start: Point,
+    end: Point,
+}
 
-    if !paths.contains_key(&end) {
-        return None;
+impl Day20 {
+    fn shortest_path(&self, multi_level: bool) -> usize {
+        let end = (self.end, 0);
+        let search = bfs(
+            (self.start, 0),
+            |current| get_adjacent(current, &self.map, multi_level),
+            |&current| current == end,
+        );
+        search.distance(&end).expect("No path through the maze")
     }
+}
 
-    let mut path: Vec<(usize, usize, usize)> = Vec::new();
-
-    let mut current: (usize, usize, usize) = *end;
-    path.push(current);
-
-    while &current != start {
-        let visit: Vec<(usize, usize, usize)> = get_adjacent(&current, map, multi_level);
-
-        let closest = visit
-            .iter()
-            .filter(|p| paths.contains_key(&p))
-            .min_by_key(|p| paths[p])
-            .unwrap();
-
-        if *closest == current {
-            panic!("Stuck in local minima");
+impl Solution for Day20 {
+    fn parse(input: &str) -> Result<Self, Box<dyn Error>> {
+        // The maze is indented with spaces, so only blank lines are dropped.
+        // Lines are padded out in case trailing spaces were trimmed.
+        let input = Grid::parse_padded(input, ' ')?;
+        if input.height() < 5 || input.width() < 5 {
+            return Err("The maze is too small".into());
         }
 
-        current = *closest;
-        path.push(current);
-    }
-
-    path.reverse();
-    Some(path)
-}
+        let mut map: HashMap<Point, Tile> = HashMap::new();
 
-fn get_tile(
-    x: usize,
-    y: usize,
-    width: usize,
-    height: usize,
-    input: &Vec<Vec<char>>,
-) -> Option<Tile> {
-    match input[y][x] {
-        '.' => {
-            let n = input[y - 1][x];
-            if n.is_ascii_uppercase() {
-                let label = format!("{}{}", input[y - 2][x], input[y - 1][x]);
+        let mut start: Option<Point> = None;
+        let mut end: Option<Point> = None;
 
-                if label.eq(&"AA".to_string()) {
-                    return Some(Tile::Start);
-                } else if label.eq(&"ZZ".to_string()) {
-                    return Some(Tile::End);
-                } else if y < height / 2 {
-                    return Some(Tile::Portal((label, Direction::Out)));
-                } else {
-                    return Some(Tile::Portal((label, Direction::In)));
-                }
+        // The labels are outside the maze, in the two rows and columns around
+        // it, so they can always be looked at from inside
+        for p in input.points() {
+            let inside = (2..input.width() - 2).contains(&(p.x as usize))
+                && (2..input.height() - 2).contains(&(p.y as usize));
+            if !inside {
+                continue;
             }
-
-            let s = input[y + 1][x];
-            if s.is_ascii_uppercase() {
-                let label = format!("{}{}", input[y + 1][x], input[y + 2][x]);
-
-                if label.eq(&"AA".to_string()) {
-                    return Some(Tile::Start);
-                } else if label.eq(&"ZZ".to_string()) {
-                    return Some(Tile::End);
-                } else if y > height / 2 {
-                    return Some(Tile::Portal((label, Direction::Out)));
-                } else {
-                    return Some(Tile::Portal((label, Direction::In)));
+            if let Some(tile) = get_tile(p, &input) {
+                if tile == Tile::Start {
+                    start = Some(p);
+                } else if tile == Tile::End {
+                    end = Some(p);
                 }
-            }
-
-            let e = input[y][x + 1];
-            if e.is_ascii_uppercase() {
-                let label = format!("{}{}", input[y][x + 1], input[y][x + 2]);
 
-                if label.eq(&"AA".to_string()) {
-                    return Some(Tile::Start);
-                } else if label.eq(&"ZZ".to_string()) {
-                    return Some(Tile::End);
-                } else if x > width / 2 {
-                    return Some(Tile::Portal((label, Direction::Out)));
-                } else {
-                    return Some(Tile::Portal((label, Direction::In)));
-                }
+                map.insert(p, tile);
             }
+        }
 
-            let w = input[y][x - 1];
-            if w.is_ascii_uppercase() {
-                let label = format!("{}{}", input[y][x - 2], input[y][x - 1]);
+        Ok(Day20 {
+            map,
+            start: start.ok_or("The maze has no entrance")?,
+            end: end.ok_or("The maze has no exit")?,
+        })
+    }
 
-                if label.eq(&"AA".to_string()) {
-                    return Some(Tile::Start);
-                } else if label.eq(&"ZZ".to_string()) {
-                    return Some(Tile::End);
-                } else if x < width / 2 {
-                    return Some(Tile::Portal((label, Direction::Out)));
-                } else {
-                    return Some(Tile::Portal((label, Direction::In)));
-                }
-            }
+    fn part1(&self) -> Answer {
+        self.shortest_path(false).into()
+    }
 
-            Some(Tile::Floor)
-        }
-        '#' => Some(Tile::Wall),
-        _ => None,
+    fn part2(&self) -> Answer {
+        self.shortest_path(true).into()
     }
 }
 
-pub fn run() {
-    let input = load_input("data/day20.txt".to_string());
-
-    let (height, width) = (input.len(), input[0].len());
-    let mut map: HashMap<(usize, usize), Tile> = HashMap::new();
-
-    let mut start: (usize, usize) = (0, 0);
-    let mut end: (usize, usize) = (0, 0);
-
-    for y in 0..height {
-        for x in 0..width {
-            let is_tile = get_tile(x, y, width, height, &input);
-            if is_tile.is_some() {
-                let tile = is_tile.unwrap();
-
-                if tile == Tile::Start {
-                    start = (x, y);
-                } else if tile == Tile::End {
-                    end = (x, y);
-                }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The maze is a list of lines, as its indentation matters
+    const EXAMPLE: [&str; 19] = [
+        "         A           ",
+        "         A           ",
+        "  #######.#########  ",
+        "  #######.........#  ",
+        "  #######.#######.#  ",
+        "  #######.#######.#  ",
+        "  #######.#######.#  ",
+        "  #####  B    ###.#  ",
+        "BC...##  C    ###.#  ",
+        "  ##.##       ###.#  ",
+        "  ##...DE  F  ###.#  ",
+        "  #####    G  ###.#  ",
+        "  #########.#####.#  ",
+        "DE..#######...###.#  ",
+        "  #.#########.###.#  ",
+        "FG..#########.....#  ",
+        "  ###########.#####  ",
+        "             Z       ",
+        "             Z       ",
+    ];
 
-                map.insert((x, y), tile);
-            }
-        }
+    #[test]
+    fn through_the_portals() {
+        let day = Day20::parse(&EXAMPLE.join("\n")).unwrap();
+        assert_eq!(day.part1(), Answer::Number(23));
     }
 
-    // Part 1
-    let path = shortest_path(&(start.0, start.1, 0), &(end.0, end.1, 0), &map, false);
-    println!("Shortest path is {} tiles", path.unwrap().len() - 1);
-
-    // Part 2
-    let path = shortest_path(&(start.0, start.1, 0), &(end.0, end.1, 0), &map, true);
-    println!("Shortest path is {} tiles", path.unwrap().len() - 1);
+    #[test]
+    fn through_the_levels() {
+        let day = Day20::parse(&EXAMPLE.join("\n")).unwrap();
+        assert_eq!(day.part2(), Answer::Number(26));
+    }
 }
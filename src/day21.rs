@@ -1,9 +1,12 @@
-use ascii;
+use std::error::Error;
 
-use crate::intcode::{VirtualMachine, VirtualMachineState, load_program};
+use crate::intcode::{parse_program, VirtualMachine, VirtualMachineState};
+use crate::solution::{Answer, Solution};
 
-fn run_program(program: &Vec<i128>, script: &Vec<&str>) {
-    let mut vm = VirtualMachine::new(&program);
+// Run a springscript program. If the springdroid makes it across the hull the
+// answer is the amount of damage, otherwise it's the droid's last moments.
+fn run_program(program: &[i128], script: &[&str]) -> Answer {
+    let mut vm = VirtualMachine::new(program);
 
     for i in script {
         for ch in i.chars() {
@@ -18,28 +21,32 @@ fn run_program(program: &Vec<i128>, script: &Vec<&str>) {
             VirtualMachineState::WaitForInput => {
                 break;
             }
-            VirtualMachineState::Output(v) => {
-                let ch = ascii::AsciiChar::from_ascii(v as u8);
-                if ch.is_ok() {
-                    let ch = ch.unwrap();
+            VirtualMachineState::Output(v) => match ascii::AsciiChar::from_ascii(v as u8) {
+                Ok(ch) if v < 128 => {
                     s.push(ch.as_char());
-                    if ch == '\n' {
-                        print!("{}", s);
-                        s = String::new();
-                    }
-                } else {
-                    println!("{} damage", v);
                 }
-            }
+                _ => {
+                    return v.into();
+                }
+            },
             VirtualMachineState::Terminated => {
                 break;
             }
         }
     }
+    Answer::Rendered(s)
 }
 
-pub fn run() {
-    let program = load_program("data/day21.txt".to_string());
+pub struct Day21 {
+    program: Vec<i128>,
+}
+
+impl Solution for Day21 {
+    fn parse(input: &str) -> Result<Self, Box<dyn Error>> {
+        Ok(Day21 {
+            program: parse_program(input)?,
+        })
+    }
 
     // There are only three instructions available in springscript:
     //   AND X Y sets Y to true if both X and Y are true
@@ -63,9 +70,10 @@ pub fn run() {
     // #####.#..########
 
     // (!C || !A) && D
-    let script = vec!["NOT A T", "NOT C J", "OR T J", "AND D J", "WALK"];
-
-    run_program(&program, &script);
+    fn part1(&self) -> Answer {
+        let script = vec!["NOT A T", "NOT C J", "OR T J", "AND D J", "WALK"];
+        run_program(&self.program, &script)
+    }
 
     // Part 2
 
@@ -81,9 +89,11 @@ pub fn run() {
     // #####..##.##.####
 
     // ((!C && H) || !B || !A) && D
-    let script = vec![
-        "NOT C T", "AND H T", "AND D T", "NOT B J", "AND D J", "OR J T", "NOT A J", "OR T J", "RUN",
-    ];
-
-    run_program(&program, &script);
+    fn part2(&self) -> Answer {
+        let script = vec![
+            "NOT C T", "AND H T", "AND D T", "NOT B J", "AND D J", "OR J T", "NOT A J", "OR T J",
+            "RUN",
+        ];
+        run_program(&self.program, &script)
+    }
 }
@@ -1,142 +1,159 @@
-
-use std::fs;
+use std::error::Error;
 
 use regex::Regex;
 
-use lazy_static;
+use crate::numtheory::{modinv, modpow, mulmod};
+use crate::parse::{self, Reason};
+use crate::solution::{Answer, Solution};
 
 enum Technique {
-    DealIncrement(i128),
-    Cut(i128),
-    Deal
+    DealIncrement(i64),
+    Cut(i64),
+    Deal,
 }
 
-fn load_input(filename : String) -> Vec<Technique> {
-    fs::read_to_string(filename)
-        .expect("Can't read file")
-        .split('\n')
-        .filter_map(|line: &str| {
-            if line.len() == 0 {
-                None
-            } else {
-                lazy_static! {
-                    // DealIncrement with increment #
-                    static ref DEAL_INC_RE: Regex = Regex::new(r"deal with increment (-?[\d]+)").unwrap();
-
-                    // cut #
-                    static ref CUT_RE: Regex = Regex::new(r"cut (-?[\d]+)").unwrap();
-
-                    // DealIncrement into new stack
-                    static ref NEW_RE: Regex = Regex::new(r"deal into new stack").unwrap();
-                }
-                if DEAL_INC_RE.is_match(&line) {
-                    for line_cap in DEAL_INC_RE.captures_iter(&line) {
-                        let inc: i128 = line_cap[1].parse().unwrap();
-                        return Some(Technique::DealIncrement(inc));
-                    }
-                }
-                if CUT_RE.is_match(&line) {
-                    for line_cap in CUT_RE.captures_iter(&line) {
-                        let cut: i128 = line_cap[1].parse().unwrap();
-                        return Some(Technique::Cut(cut));
-                    }
-                }
-                if NEW_RE.is_match(&line) {
-                    return Some(Technique::Deal);
-                }
-                panic!("Invalid input");
-            }
-        })
-        .collect()
-}
+fn parse_technique(line: &str) -> Result<Technique, Reason> {
+    lazy_static! {
+        // DealIncrement with increment #
+        static ref DEAL_INC_RE: Regex = Regex::new(r"^deal with increment (-?[\d]+)$").unwrap();
 
-// https://algorithmist.com/wiki/Modular_inverse
-fn iterative_egcd(a: i128, b: i128) -> (i128, i128, i128) {
-    let (mut a, mut b) = (a, b);
-    let (mut x, mut y, mut u, mut v) = (0, 1, 1, 0);
-    while a != 0 {
-        let (q, r) = ((b as f64 / a as f64).floor() as i128, b % a);
-        let (m, n) = (x - u * q, y - v * q);
-
-        let (b_p, a_p, x_p, y_p, u_p, v_p) = (a, r, u, v, m, n);
-
-        b = b_p;
-        a = a_p;
-        x = x_p;
-        y = y_p;
-        u = u_p;
-        v = v_p;
-    }
-    (b, x, y)
-}
+        // cut #
+        static ref CUT_RE: Regex = Regex::new(r"^cut (-?[\d]+)$").unwrap();
 
-fn modular_inverse(a: i128, m: i128) -> Option<i128> {
-    let (g, x, _) = iterative_egcd(a, m);
-    if g != 1 {
-        None
-    } else {
-        Some(x % m)
+        // DealIncrement into new stack
+        static ref NEW_RE: Regex = Regex::new(r"^deal into new stack$").unwrap();
     }
-}
-
-fn modular(a: i128, m: i128) -> i128 {
-    if a >= 0 {
-        a % m
-    } else {
-        m + a % m
+    if let Some(line_cap) = DEAL_INC_RE.captures(line) {
+        return Ok(Technique::DealIncrement(parse::number(&line_cap[1])?));
     }
-}
-
-fn poly_pow(a: i128, b: i128, m: i128, n: i128) -> (i128, i128) {
-    if m == 0 {
-        (1, 0)
-    } else if m % 2 == 0 {
-        poly_pow(a * a % n, (a * b + b) % n, m / 2, n)
-    } else {
-        let (c, d) = poly_pow(a, b, m-1, n);
-        (a * c % n, (a * d + b) % n)
+    if let Some(line_cap) = CUT_RE.captures(line) {
+        return Ok(Technique::Cut(parse::number(&line_cap[1])?));
     }
+    if NEW_RE.is_match(line) {
+        return Ok(Technique::Deal);
+    }
+    Err(Reason::Unexpected {
+        expected: "a shuffle technique".to_string(),
+        found: line.to_string(),
+    })
 }
 
-fn get_coefficients(card_count: i128, steps: &Vec<Technique>) -> (i128, i128) {
-    steps.iter().fold((1, 0), |(a, b), s| -> (i128, i128) {
+fn get_coefficients(card_count: i64, steps: &[Technique]) -> (i64, i64) {
+    steps.iter().fold((1, 0), |(a, b), s| -> (i64, i64) {
         match s {
-            Technique::DealIncrement(x) => {
-                (modular(a * x, card_count), modular(b * x, card_count))
-            },
-            Technique::Cut(x) => {
-                (a, modular(b - x, card_count))
-            },
-            Technique::Deal => {
-                (modular(-a, card_count), modular(card_count - 1 - b, card_count))
-            },
+            Technique::DealIncrement(x) => (mulmod(a, *x, card_count), mulmod(b, *x, card_count)),
+            Technique::Cut(x) => (a, (b - x).rem_euclid(card_count)),
+            Technique::Deal => (
+                (-a).rem_euclid(card_count),
+                (card_count - 1 - b).rem_euclid(card_count),
+            ),
         }
     })
 }
 
-pub fn run() {
-    let steps = load_input("data/day22.txt".to_string());
-    
-    // All three dealing operations can be written as a linear operation of the form:
-    //    y = a * x + b
-    // where x is the initial position of the card, and y is the final position of the card
+// Shuffling m times: applying y = a * x + b over and over gives
+//    y = a^m * x + b * (a^m - 1) / (a - 1)
+fn repeat_shuffle(a: i64, b: i64, m: u64, card_count: i64) -> (i64, i64) {
+    let a_m = modpow(a, m, card_count);
+    if a == 1 {
+        return (a_m, mulmod(b, (m % card_count as u64) as i64, card_count));
+    }
+    let series = mulmod(
+        a_m - 1,
+        modinv(a - 1, card_count).expect("The card count isn't prime"),
+        card_count,
+    );
+    (a_m, mulmod(b, series, card_count))
+}
 
-    // Part 1
-    let card_count = 10007;
-    let (a, b) = get_coefficients(card_count, &steps);
+// All three dealing operations can be written as a linear operation of the form:
+//    y = a * x + b
+// where x is the initial position of the card, and y is the final position of the card
+pub struct Day22 {
+    steps: Vec<Technique>,
+}
 
-    let x = 2019;
-    let y = modular(a * x + b, card_count);
-    println!("{}", y);
+impl Solution for Day22 {
+    fn parse(input: &str) -> Result<Self, Box<dyn Error>> {
+        let steps = parse::lines(input, parse_technique)?;
+        Ok(Day22 { steps })
+    }
 
-    // Part 2
-    let card_count = 119315717514047;
-    let (a, b) = get_coefficients(card_count, &steps);
+    fn part1(&self) -> Answer {
+        let card_count = 10007;
+        let (a, b) = get_coefficients(card_count, &self.steps);
 
-    let shuffle_count = 101741582076661;
-    let (a, b) = poly_pow(a, b, shuffle_count, card_count);
+        let x = 2019;
+        ((mulmod(a, x, card_count) + b) % card_count).into()
+    }
 
-    let y = 2020;
-    let x = modular((y - b) * modular_inverse(a, card_count).unwrap(), card_count);
-    println!("{}", x);
-}
\ No newline at end of file
+    fn part2(&self) -> Answer {
+        let card_count = 119315717514047;
+        let (a, b) = get_coefficients(card_count, &self.steps);
+
+        let shuffle_count = 101741582076661;
+        let (a, b) = repeat_shuffle(a, b, shuffle_count, card_count);
+
+        let y = 2020;
+        let inverse = modinv(a, card_count).expect("The shuffle can't be undone");
+        mulmod(y - b, inverse, card_count).into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The order of a deck of 10 cards after shuffling
+    fn shuffle(techniques: &str) -> Vec<i64> {
+        let day = Day22::parse(techniques).unwrap();
+        let (a, b) = get_coefficients(10, &day.steps);
+
+        let mut deck = vec![0; 10];
+        for card in 0..10 {
+            deck[((a * card + b) % 10) as usize] = card;
+        }
+        deck
+    }
+
+    #[test]
+    fn shuffles_small_decks() {
+        assert_eq!(
+            shuffle("deal with increment 7\ndeal into new stack\ndeal into new stack\n"),
+            vec![0, 3, 6, 9, 2, 5, 8, 1, 4, 7]
+        );
+        assert_eq!(
+            shuffle("cut 6\ndeal with increment 7\ndeal into new stack\n"),
+            vec![3, 0, 7, 4, 1, 8, 5, 2, 9, 6]
+        );
+        assert_eq!(
+            shuffle("deal with increment 7\ndeal with increment 9\ncut -2\n"),
+            vec![6, 3, 0, 7, 4, 1, 8, 5, 2, 9]
+        );
+        assert_eq!(
+            shuffle(
+                "deal into new stack\ncut -2\ndeal with increment 7\ncut 8\ncut -4\n\
+                 deal with increment 7\ncut 3\ndeal with increment 9\n\
+                 deal with increment 3\ncut -1\n"
+            ),
+            vec![9, 2, 5, 8, 1, 4, 7, 0, 3, 6]
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_techniques() {
+        let error = Day22::parse("deal with increment 7\nshuffle\n")
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "line 2: expected a shuffle technique, found 'shuffle'"
+        );
+
+        let error = Day22::parse("cut 99999999999999999999\n").err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "line 1: '99999999999999999999' isn't a number"
+        );
+    }
+}
@@ -1,11 +1,13 @@
 use std::collections::HashSet;
+use std::error::Error;
 
-use crate::intcode::{VirtualMachine, VirtualMachineState, load_program};
+use crate::intcode::{parse_program, VirtualMachine, VirtualMachineState};
+use crate::solution::{Answer, Solution};
 
-pub fn run() {
-    let program = load_program("data/day23.txt".to_string());
-
-    let mut template = VirtualMachine::new(&program);
+// Run the network until the NAT first receives a packet, or until it sends the
+// same y value to address 0 twice in a row, and return that y value.
+fn run_network(program: &[i128], first_nat_packet: bool) -> i128 {
+    let mut template = VirtualMachine::new(program);
     match template.run() {
         VirtualMachineState::WaitForInput => {}
         _ => {
@@ -13,7 +15,7 @@ pub fn run() {
         }
     }
 
-    let mut vms: Vec<VirtualMachine> = (0..50).into_iter().map(|_| template.clone()).collect();
+    let mut vms: Vec<VirtualMachine> = (0..50).map(|_| template.clone()).collect();
     let mut packets: Vec<(usize, i128)> = (0..50).map(|addr| (addr, addr as i128)).collect();
     let mut nat: Option<(i128, i128)> = None;
     let mut seen_nat_y: HashSet<i128> = HashSet::new();
@@ -42,8 +44,8 @@ pub fn run() {
                         };
 
                         if addr == 255 {
-                            if nat.is_none() {
-                                println!("{}", y);
+                            if first_nat_packet {
+                                return y;
                             }
                             nat = Some((x, y));
                         } else {
@@ -55,14 +57,9 @@ pub fn run() {
                 }
             }
         }
-        if nat.is_some() {
-            let (x, y) = nat.unwrap();
-
-            if seen_nat_y.contains(&y) {
-                println!("{}", y);
-                break;
-            } else {
-                seen_nat_y.insert(y);
+        if let Some((x, y)) = nat {
+            if !seen_nat_y.insert(y) {
+                return y;
             }
 
             packets.push((0, x));
@@ -72,3 +69,23 @@ pub fn run() {
         }
     }
 }
+
+pub struct Day23 {
+    program: Vec<i128>,
+}
+
+impl Solution for Day23 {
+    fn parse(input: &str) -> Result<Self, Box<dyn Error>> {
+        Ok(Day23 {
+            program: parse_program(input)?,
+        })
+    }
+
+    fn part1(&self) -> Answer {
+        run_network(&self.program, true).into()
+    }
+
+    fn part2(&self) -> Answer {
+        run_network(&self.program, false).into()
+    }
+}
@@ -1,261 +1,222 @@
-use std::fs;
-
 use std::collections::HashMap;
-use std::collections::HashSet;
+use std::error::Error;
 
-fn load_input(filename: String) -> Vec<Vec<char>> {
-    fs::read_to_string(filename)
-        .expect("Can't read file")
-        .split('\n')
-        .filter_map(|s: &str| {
-            if s.len() == 0 {
-                None
-            } else {
-                Some(s.chars().collect())
-            }
-        })
-        .collect()
-}
+use crate::animation::{no_frames, Frames};
+use crate::cycle;
+use crate::geom::{Direction, Point};
+use crate::grid::Grid;
+use crate::image::{Image, Rgb, BLACK};
+use crate::solution::{Answer, Solution};
 
+// A minute passing: a bug dies unless there's exactly one bug next to it, and
+// an empty tile gets infested if one or two bugs are next to it
 pub fn run_iteration(
-    bugs: &Option<&HashSet<(i64, i64)>>,
-    width: usize,
-    height: usize,
-    adjacent_bugs: &mut dyn FnMut((i64, i64)) -> usize,
-) -> HashSet<(i64, i64)> {
-    let mut new_bugs: HashSet<(i64, i64)> = HashSet::new();
-
-    for y in 0..height {
-        for x in 0..width {
-            let p = (x as i64, y as i64);
-            let adjacent = adjacent_bugs(p);
-            if bugs.is_some() && bugs.unwrap().contains(&p) {
-                match adjacent {
-                    1 => {
-                        new_bugs.insert(p);
-                    }
-                    _ => {}
-                }
-            } else {
-                match adjacent {
-                    1 | 2 => {
-                        new_bugs.insert(p);
-                    }
-                    _ => {}
-                }
-            }
+    bugs: &Grid<bool>,
+    adjacent_bugs: &mut dyn FnMut(Point) -> usize,
+) -> Grid<bool> {
+    Grid::from_fn(bugs.width(), bugs.height(), |p| {
+        match (bugs[p], adjacent_bugs(p)) {
+            (true, adjacent) => adjacent == 1,
+            (false, adjacent) => adjacent == 1 || adjacent == 2,
         }
-    }
-
-    new_bugs
+    })
 }
 
-pub fn run() {
-    let input = load_input("data/day24.txt".to_string());
-    let (height, width) = (input.len(), input[0].len());
-
-    let bug_hash = |bugs: &HashSet<(i64, i64)>| -> String {
-        let mut s = String::new();
-        for y in 0..height {
-            for x in 0..width {
-                let p = (x as i64, y as i64);
-                if bugs.contains(&p) {
-                    s.push('#');
-                } else {
-                    s.push('.');
-                }
-            }
-            s.push('\n');
-        }
-        s
-    };
+// Each tile is worth a power of two, in reading order, which also makes it a
+// unique key for the layout
+fn biodiversity(bugs: &Grid<bool>) -> u128 {
+    bugs.iter()
+        .enumerate()
+        .filter(|(_, (_, &bug))| bug)
+        .map(|(i, _)| 1 << i)
+        .sum()
+}
 
-    let mut bugs: HashSet<(i64, i64)> = HashSet::new();
-    for y in 0..height {
-        for x in 0..width {
-            match input[y][x] {
-                '#' => {
-                    let p = (x as i64, y as i64);
-                    bugs.insert(p);
-                }
-                _ => {}
-            }
-        }
+// Bugs are green, and the gaps between levels (and the middle tile, where
+// the next level down is) are grey
+fn tile_colour(bug: Option<bool>) -> Rgb {
+    match bug {
+        Some(true) => [60, 200, 60],
+        Some(false) => BLACK,
+        None => [80, 80, 80],
     }
+}
 
-    // Part 1
-    let mut part1 = bugs.clone();
+fn render(bugs: &Grid<bool>, minute: usize) -> String {
+    let tiles = bugs.render(|&bug| if bug { '#' } else { '.' });
+    format!("After {} minutes:\n{}", minute, tiles)
+}
 
-    let mut visited: HashSet<String> = HashSet::new();
-    visited.insert(bug_hash(&part1));
-    loop {
-        let mut count_bugs = |(x, y): (i64, i64)| -> usize {
-            vec![
-                    (x as i64 - 1, y as i64),
-                    (x as i64 + 1, y as i64),
-                    (x as i64, y as i64 - 1),
-                    (x as i64, y as i64 + 1),
-                ]
-                .into_iter()
-                .map(|p| {
-                    if part1.contains(&p) {
-                        1
-                    } else {
-                        0
-                    }
-                })
-                .sum()
+pub struct Day24 {
+    bugs: Grid<bool>,
+}
+
+impl Day24 {
+    // The first layout to appear twice, sending a frame for every minute up
+    // to when it first appears
+    fn first_repeated(&self, frames: Frames) -> Grid<bool> {
+        let step = |bugs: &Grid<bool>| {
+            let mut count_bugs =
+                |p: Point| -> usize { bugs.neighbours(p).filter(|(_, &bug)| bug).count() };
+            run_iteration(bugs, &mut count_bugs)
         };
-        part1 = run_iteration(&Some(&part1), width, height, &mut count_bugs);
 
-        let hash = bug_hash(&part1);
-        if visited.contains(&hash) {
-            break;
-        }
-        visited.insert(hash);
-    }
+        // The layout the cycle starts with is the first to come round again
+        let cycle = cycle::hashed(self.bugs.clone(), &step, biodiversity);
 
-    let mut biodiversity: u128 = 0;
-    let mut pow2: u128 = 1;
-    for y in 0..height {
-        for x in 0..width {
-            let p = (x as i64, y as i64);
-            if part1.contains(&p) {
-                biodiversity += pow2;
-            }
-            pow2 *= 2;
-        }
+        let mut minute = 0;
+        frames(&|| render(&self.bugs, minute));
+        let show_minute = |bugs: &Grid<bool>| {
+            let next = step(bugs);
+            minute += 1;
+            frames(&|| render(&next, minute));
+            next
+        };
+        cycle.extrapolate(self.bugs.clone(), show_minute, cycle.start)
     }
-    println!("{}", biodiversity);
 
-    // Part 2
-    let mut levels: HashMap<i64, HashSet<(i64, i64)>> = HashMap::new();
-    levels.insert(0, bugs);
+    // The recursive levels that have bugs on them after some minutes
+    fn recursive_levels(&self, minutes: usize) -> HashMap<i64, Grid<bool>> {
+        let (height, width) = (self.bugs.height(), self.bugs.width());
+        let mut levels: HashMap<i64, Grid<bool>> = HashMap::new();
+        levels.insert(0, self.bugs.clone());
 
-    let (mid_x, mid_y) = (
-        (width as f64 / 2.0).floor() as i64,
-        (height as f64 / 2.0).floor() as i64,
-    );
+        let empty = Grid::new(width, height, false);
+        let mid = Point::new((width / 2) as i32, (height / 2) as i32);
 
-    for _ in 0..200 {
-        let min = levels.keys().min().unwrap();
-        let max = levels.keys().max().unwrap();
+        for _ in 0..minutes {
+            let min = levels.keys().min().unwrap();
+            let max = levels.keys().max().unwrap();
 
-        let mut next_levels: HashMap<i64, HashSet<(i64, i64)>> = HashMap::new();
+            let mut next_levels: HashMap<i64, Grid<bool>> = HashMap::new();
 
-        for i in (min-1)..=(max+1) {
-            let previous_bugs = levels.get(&(i-1));
-            let current_bugs = levels.get(&i);
-            let next_bugs = levels.get(&(i+1));
+            for i in (min - 1)..=(max + 1) {
+                let previous_bugs = levels.get(&(i - 1)).unwrap_or(&empty);
+                let current_bugs = levels.get(&i).unwrap_or(&empty);
+                let next_bugs = levels.get(&(i + 1)).unwrap_or(&empty);
 
-            let mut count_bugs = |(x, y): (i64, i64)| -> usize {
-                if (x == mid_x) && (y == mid_y) {
-                    0
-                } else {
-                    let adjacent = vec![
-                        (x as i64 - 1, y as i64),
-                        (x as i64 + 1, y as i64),
-                        (x as i64, y as i64 - 1),
-                        (x as i64, y as i64 + 1),
-                    ];
+                let mut count_bugs = |p: Point| -> usize {
+                    if p == mid {
+                        return 0;
+                    }
 
                     let mut bug_count = 0;
-                    for a in 0..adjacent.len() {
-                        let p = adjacent[a];
-                        if (p.0 == mid_x) && (p.1 == mid_y) {
-                            match next_bugs {    
-                                None => {},
-                                Some(next_bugs) => {
-                                    match a {
-                                        0 | 1 => {
-                                            let x = match a {
-                                                0 => width - 1,  // last column
-                                                1 => 0, // first column
-                                                _ => panic!("Error"),
-                                            };
-                                            for y in 0..height {
-                                                let p_next = (x as i64, y as i64);
-                                                if next_bugs.contains(&p_next) {
-                                                    bug_count += 1;
-                                                }
-                                            }
-                                        },
-                                        2 | 3 => {
-                                            let y = match a {
-                                                2 => height - 1,  // last row
-                                                3 => 0, // first row
-                                                _ => panic!("Error"),
-                                            };
-                                            for x in 0..width {
-                                                let p_next = (x as i64, y as i64);
-                                                if next_bugs.contains(&p_next) {
-                                                    bug_count += 1;
-                                                }
-                                            }
-                                        },
-                                        _ => panic!("Unknown direction")
-                                    }
-                                },
-                            }
-                        } else if (p.0 >= 0) && 
-                                  (p.0 < width as i64) && 
-                                  (p.1 >= 0) && 
-                                  (p.1 < height as i64)
-                        {
-                            match current_bugs {
-                                None => {},
-                                Some(current_bugs) => {
-                                    if  current_bugs.contains(&p) {
-                                        bug_count += 1;
-                                    }
-                                },
+                    for &d in Direction::ALL.iter() {
+                        let n = p.step(d);
+                        let bugs: Vec<&bool> = if n == mid {
+                            // The edge of the level inside that faces this tile
+                            match d {
+                                Direction::North => next_bugs.row(height - 1).iter().collect(),
+                                Direction::South => next_bugs.row(0).iter().collect(),
+                                Direction::West => next_bugs.column(width - 1).collect(),
+                                Direction::East => next_bugs.column(0).collect(),
                             }
+                        } else if let Some(bug) = current_bugs.get(n) {
+                            vec![bug]
                         } else {
-                            match previous_bugs {    
-                                None => {},
-                                Some(previous_bugs) => {
-                                    if p.0 == -1 {
-                                        let p_prev = (mid_x - 1, mid_y);
-                                        if previous_bugs.contains(&p_prev) {
-                                            bug_count += 1;
-                                        }
-                                    } else if p.0 == width as i64 {
-                                        let p_prev = (mid_x + 1, mid_y);
-                                        if previous_bugs.contains(&p_prev) {
-                                            bug_count += 1;
-                                        }
-                                    } else if p.1 == -1 {
-                                        let p_prev = (mid_x, mid_y - 1);
-                                        if previous_bugs.contains(&p_prev) {
-                                            bug_count += 1;
-                                        }
-                                    } else if p.1 == height as i64 {
-                                        let p_prev = (mid_x, mid_y + 1);
-                                        if previous_bugs.contains(&p_prev) {
-                                            bug_count += 1;
-                                        }
-                                    } else {
-                                        panic!("Error");
-                                    }
-                                },
-                            }
-                        }
+                            // Off the edge is the tile next to the middle of
+                            // the level outside
+                            vec![&previous_bugs[mid.step(d)]]
+                        };
+                        bug_count += bugs.into_iter().filter(|&&bug| bug).count();
                     }
                     bug_count
+                };
+                let bugs = run_iteration(current_bugs, &mut count_bugs);
+                if bugs.find_all(&true).next().is_some() {
+                    next_levels.insert(i, bugs);
                 }
-            };
-            let bugs = run_iteration(&current_bugs, width, height, &mut count_bugs);
-            if !bugs.is_empty() {
-                next_levels.insert(i, bugs);
             }
+
+            levels = next_levels;
         }
+        levels
+    }
+
+    // Count the bugs across all the recursive levels after some minutes
+    fn recursive_bug_count(&self, minutes: usize) -> usize {
+        self.recursive_levels(minutes)
+            .values()
+            .map(|bugs| bugs.find_all(&true).count())
+            .sum()
+    }
+}
+
+impl Solution for Day24 {
+    fn parse(input: &str) -> Result<Self, Box<dyn Error>> {
+        let input = Grid::parse(input)?;
+        if let Some((_, tile)) = input.iter().find(|(_, &tile)| !"#.?".contains(tile)) {
+            return Err(format!("Unknown tile '{}'", tile).into());
+        }
+
+        Ok(Day24 {
+            bugs: input.map(|&tile| tile == '#'),
+        })
+    }
+
+    fn part1(&self) -> Answer {
+        biodiversity(&self.first_repeated(&mut no_frames)).into()
+    }
+
+    fn part2(&self) -> Answer {
+        self.recursive_bug_count(200).into()
+    }
+
+    // The first repeated layout, and every recursive level after 200 minutes
+    // side by side, outermost first
+    fn pictures(&self) -> Vec<(String, Image)> {
+        let repeated = Image::from_grid(&self.first_repeated(&mut no_frames), |&bug| {
+            tile_colour(Some(bug))
+        });
+
+        let levels = self.recursive_levels(200);
+        let mut depths: Vec<&i64> = levels.keys().collect();
+        depths.sort();
+        let (width, height) = (self.bugs.width(), self.bugs.height());
+        let mid = Point::new((width / 2) as i32, (height / 2) as i32);
+        let row = Grid::from_fn(
+            ((width + 1) * depths.len()).saturating_sub(1),
+            height,
+            |p| {
+                let (i, x) = (p.x as usize / (width + 1), p.x as usize % (width + 1));
+                let tile = Point::new(x as i32, p.y);
+                if x == width || tile == mid {
+                    None
+                } else {
+                    Some(levels[depths[i]][tile])
+                }
+            },
+        );
+        let levels = Image::from_grid(&row, |&bug| tile_colour(bug));
+
+        vec![
+            ("repeated".to_string(), repeated),
+            ("levels".to_string(), levels),
+        ]
+    }
+
+    // The bugs spreading and dying, a frame a minute, up to the first layout
+    // that comes round again
+    fn animate(&self, frames: Frames) {
+        self.first_repeated(frames);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "....#\n#..#.\n#..##\n..#..\n#....\n";
 
-        levels = next_levels;
+    #[test]
+    fn first_repeated_layout() {
+        let day = Day24::parse(EXAMPLE).unwrap();
+        assert_eq!(day.part1(), Answer::Number(2129920));
     }
 
-    let mut bug_count = 0;
-    for (_, bugs) in levels {
-        bug_count += bugs.len();
+    #[test]
+    fn recursive_bugs() {
+        let day = Day24::parse(EXAMPLE).unwrap();
+        assert_eq!(day.recursive_bug_count(10), 99);
     }
-    println!("{}", bug_count);
 }
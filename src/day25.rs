@@ -1,47 +1,15 @@
 use std::collections::HashMap;
+use std::error::Error;
 use std::io;
-use std::fmt;
 
 use regex::Regex;
 
-use lazy_static;
+use crate::combinatorics::powerset;
+use crate::geom::Direction;
+use crate::intcode::{parse_program, VirtualMachine, VirtualMachineState};
+use crate::solution::{Answer, Solution};
 
-use crate::intcode::{VirtualMachine, VirtualMachineState, load_program};
-
-#[derive(PartialEq, Clone, Copy)]
-enum Direction {
-    North,
-    South,
-    East,
-    West
-}
-
-impl Direction {
-    fn inverse(&self) -> Direction {
-        match self {
-            Direction::North => Direction::South,
-            Direction::South => Direction::North,
-            Direction::East => Direction::West,
-            Direction::West => Direction::East,
-        }
-    }
-}
-
-impl fmt::Display for Direction {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            Direction::North => write!(f, "north"),
-            Direction::South => write!(f, "south"),
-            Direction::East => write!(f, "east"),
-            Direction::West => write!(f, "west"),
-        }
-    }
-}
-
-fn run_interactive(
-    vm: &mut VirtualMachine,
-    output: &mut String
-) {
+fn run_interactive(vm: &mut VirtualMachine, output: &mut String) {
     loop {
         match vm.run() {
             VirtualMachineState::WaitForInput => {
@@ -61,11 +29,11 @@ fn run_interactive(
                     }
                     Err(error) => println!("error: {}", error),
                 }
-            },
+            }
             VirtualMachineState::Output(v) => {
                 let ch = (v as u8) as char;
                 output.push(ch);
-            },
+            }
             VirtualMachineState::Terminated => {
                 break;
             }
@@ -73,16 +41,15 @@ fn run_interactive(
     }
 }
 
-fn parse_output(
-    output: &String
-) -> (Option<String>, Vec<Direction>, Vec<String>) {
+fn parse_output(output: &str) -> (Option<String>, Vec<Direction>, Vec<String>) {
     let mut name: Option<String> = None;
     let mut directions: Vec<Direction> = Vec::new();
     let mut items: Vec<String> = Vec::new();
 
-    let lines: Vec<String> = output.split('\n')
+    let lines: Vec<String> = output
+        .split('\n')
         .filter_map(|s: &str| {
-            if s.len() == 0 {
+            if s.is_empty() {
                 None
             } else {
                 Some(s.chars().collect())
@@ -96,11 +63,10 @@ fn parse_output(
             static ref ROOM_RE: Regex = Regex::new(r"== (.*) ==").unwrap();
         }
 
-        if ROOM_RE.is_match(&line) {
-            for line_cap in ROOM_RE.captures_iter(&line) {
+        if ROOM_RE.is_match(line) {
+            if let Some(line_cap) = ROOM_RE.captures_iter(line).next() {
                 let room = &line_cap[0];
                 name = Some(room.to_string());
-                break;
             }
         } else {
             let ch = line.chars().next().unwrap();
@@ -123,11 +89,10 @@ fn parse_output(
     (name, directions, items)
 }
 
-fn visit_all_the_rooms(
-    vm: &mut VirtualMachine,
-    output: &mut String,
-    visit_room: &mut dyn FnMut(&String, &Vec<Direction>, &Vec<String>, &mut Vec<String>) -> bool
-) {
+type VisitRoom<'a> =
+    dyn FnMut(&String, &Vec<Direction>, &Vec<String>, &mut Vec<String>) -> bool + 'a;
+
+fn visit_all_the_rooms(vm: &mut VirtualMachine, output: &mut String, visit_room: &mut VisitRoom) {
     let mut frontier: Vec<Direction> = Vec::new();
     let mut inputs: Vec<String> = Vec::new();
 
@@ -139,14 +104,14 @@ fn visit_all_the_rooms(
             VirtualMachineState::WaitForInput => {
                 if inputs.is_empty() {
                     if !output.is_empty() {
-                        let (room, directions, items) = parse_output(&output);
+                        let (room, directions, items) = parse_output(output);
 
                         let next = room.unwrap();
                         if next.eq(&current) {
                             frontier.pop();
                         } else {
                             current = next;
-    
+
                             if !rooms_visited.contains_key(&current) {
                                 if visit_room(&current, &directions, &items, &mut inputs) {
                                     break;
@@ -184,12 +149,12 @@ fn visit_all_the_rooms(
                         vm.add_input(ch as i128);
                     }
                 }
-            },
+            }
             VirtualMachineState::Output(v) => {
                 let ch = (v as u8) as char;
 
                 output.push(ch);
-            },
+            }
             VirtualMachineState::Terminated => {
                 break;
             }
@@ -197,11 +162,7 @@ fn visit_all_the_rooms(
     }
 }
 
-fn run_with_input(
-    vm: &mut VirtualMachine,
-    output: &mut String,
-    inputs: &mut Vec<String>
-) {
+fn run_with_input(vm: &mut VirtualMachine, output: &mut String, inputs: &mut Vec<String>) {
     loop {
         match vm.run() {
             VirtualMachineState::WaitForInput => {
@@ -214,12 +175,12 @@ fn run_with_input(
                     }
                     *output = String::new();
                 }
-            },
+            }
             VirtualMachineState::Output(v) => {
                 let ch = (v as u8) as char;
 
                 output.push(ch);
-            },
+            }
             VirtualMachineState::Terminated => {
                 break;
             }
@@ -227,47 +188,53 @@ fn run_with_input(
     }
 }
 
-fn generate_all_combinations<T: Clone>(arr: &Vec<T>) -> Vec<Vec<T>> {
-    let mut output: Vec<Vec<T>> = Vec::new();
+// The airlock prints the password once the droid is carrying the right items
+fn find_password(output: &str) -> Answer {
+    lazy_static! {
+        static ref PASSWORD_RE: Regex = Regex::new(r"typing (\d+) on the keypad").unwrap();
+    }
+    match PASSWORD_RE.captures(output) {
+        Some(cap) => cap[1].to_string().into(),
+        None => output.into(),
+    }
+}
+
+pub struct Day25 {
+    program: Vec<i128>,
+}
 
-    let n = arr.len();
-    let i_max = 2u32.pow(n as u32);
+impl Solution for Day25 {
+    // Explore the ship by typing the droid's commands at the terminal
+    const INTERACTIVE: bool = true;
 
-    for i in 0..i_max {
-        let mut entry: Vec<T> = Vec::new();
-        let mut m = 1;
-        for j in 0..n {
-            if (i & m) > 0 {		
-                entry.push(arr[j].clone());
-            }
-            m = m << 1;
-        }
-        if !entry.is_empty() {
-            output.push(entry);
-        }
+    fn parse(input: &str) -> Result<Self, Box<dyn Error>> {
+        Ok(Day25 {
+            program: parse_program(input)?,
+        })
     }
-    return output;
-}
 
-pub fn run() {
-    let program = load_program("data/day25.txt".to_string());
-    let mut vm = VirtualMachine::new(&program);
+    fn part1(&self) -> Answer {
+        let mut vm = VirtualMachine::new(&self.program);
 
-    let mut output: String = String::new();
+        let mut output: String = String::new();
 
-    let interactive = false;
-    if interactive {
-        run_interactive(&mut vm, &mut output);
-    } else {
         let mut rooms: HashMap<String, (Vec<Direction>, Vec<String>)> = HashMap::new();
 
-        let mut cache_room = |name: &String, directions: &Vec<Direction>, items: &Vec<String>, _: &mut Vec<String>| -> bool {
+        let mut cache_room = |name: &String,
+                              directions: &Vec<Direction>,
+                              items: &Vec<String>,
+                              _: &mut Vec<String>|
+         -> bool {
             rooms.insert(name.clone(), (directions.clone(), items.clone()));
             false
         };
         visit_all_the_rooms(&mut vm, &mut output, &mut cache_room);
 
-        let mut pick_up_all_things = |_: &String, _: &Vec<Direction>, items: &Vec<String>, inputs: &mut Vec<String>| -> bool {
+        let mut pick_up_all_things = |_: &String,
+                                      _: &Vec<Direction>,
+                                      items: &Vec<String>,
+                                      inputs: &mut Vec<String>|
+         -> bool {
             for i in items {
                 let dont_pick_up = vec![
                     "escape pod",
@@ -293,41 +260,41 @@ pub fn run() {
         };
         visit_all_the_rooms(&mut vm, &mut output, &mut pick_up_all_things);
 
-        let mut find_the_security_checkpoint = |name: &String, _: &Vec<Direction>, _: &Vec<String>, _: &mut Vec<String>| -> bool {
-            name.eq(&"== Security Checkpoint ==")
-        };
+        let mut find_the_security_checkpoint =
+            |name: &String, _: &Vec<Direction>, _: &Vec<String>, _: &mut Vec<String>| -> bool {
+                name.eq(&"== Security Checkpoint ==")
+            };
         visit_all_the_rooms(&mut vm, &mut output, &mut find_the_security_checkpoint);
 
         // Get all possible combinations of all items
-        let mut input: Vec<String> = vec![
-            "inv\n".to_string()
-        ];
+        let mut input: Vec<String> = vec!["inv\n".to_string()];
         run_with_input(&mut vm, &mut output, &mut input);
         let (_, _, mut items) = parse_output(&output);
         items.sort();
 
-        let item_combinations = generate_all_combinations(&items);
+        // Carrying nothing is never the right weight
+        let item_combinations = powerset(&items).skip(1);
 
         // Try all possible combinations of items
-        let mut drop_all_items: Vec<String> = items.iter().map(|item| {
-            format!("drop {}\n", item)
-        }).collect();
+        let mut drop_all_items: Vec<String> = items
+            .iter()
+            .map(|item| format!("drop {}\n", item))
+            .collect();
         run_with_input(&mut vm, &mut output, &mut drop_all_items);
 
         // Use a save state to find the correct set of items to cross the pressure plate
         let save_state = vm.clone();
         for items in item_combinations {
-            let mut pick_up_items: Vec<String> = items.iter().map(|item| {
-                format!("take {}\n", item)
-            }).collect();
+            let mut pick_up_items: Vec<String> = items
+                .iter()
+                .map(|item| format!("take {}\n", item))
+                .collect();
             run_with_input(&mut vm, &mut output, &mut pick_up_items);
 
-            let mut input: Vec<String> = vec![
-                "south\n".to_string()
-            ];
+            let mut input: Vec<String> = vec!["south\n".to_string()];
             run_with_input(&mut vm, &mut output, &mut input);
             let (name, _, _) = parse_output(&output);
-            
+
             if !name.unwrap().eq(&"== Security Checkpoint ==") {
                 break;
             }
@@ -335,6 +302,18 @@ pub fn run() {
             vm = save_state.clone();
         }
 
-        println!("{}", output);
+        find_password(&output)
+    }
+
+    // There's only one puzzle on the last day
+    fn part2(&self) -> Answer {
+        Answer::None
+    }
+
+    fn play(&self) -> Answer {
+        let mut vm = VirtualMachine::new(&self.program);
+        let mut output: String = String::new();
+        run_interactive(&mut vm, &mut output);
+        find_password(&output)
     }
 }
@@ -0,0 +1,450 @@
+// Differential fuzzing for Intcode engines.
+//
+// Random, well-formed programs are run on an engine under test and on a
+// deliberately simple reference interpreter, and any disagreement in output,
+// final state or memory is reported after being shrunk to a small program.
+//
+// Programs are generated so that they always halt and never touch memory
+// outside the program image: jumps only go forward to instruction boundaries,
+// writes only land in the data region after the code, and the relative base
+// only ever moves forward by a bounded amount.
+
+use std::fmt;
+
+use crate::intcode::{VirtualMachine, VirtualMachineState};
+
+const DATA_SIZE: usize = 16;
+const MAX_INSTRUCTIONS: usize = 24;
+const MAX_BASE_ADJUSTMENT: i128 = 3;
+
+// Small, fast and deterministic, so a failing seed can be replayed.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        // https://en.wikipedia.org/wiki/Xorshift#xorshift*
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % (n as u64)) as usize
+    }
+    fn between(&mut self, low: i128, high: i128) -> i128 {
+        low + (self.next() % ((high - low + 1) as u64)) as i128
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Parameter {
+    // An offset into the data region, read or written in position mode
+    Position(usize),
+    Immediate(i128),
+    // An offset into the data region, read or written in relative mode
+    Relative(usize),
+    // The index of an instruction to jump to, in immediate mode
+    Target(usize),
+}
+
+#[derive(Clone, PartialEq, Debug)]
+struct Instruction {
+    opcode: i128,
+    parameters: Vec<Parameter>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Case {
+    instructions: Vec<Instruction>,
+    data: Vec<i128>,
+    inputs: Vec<i128>,
+}
+
+impl Case {
+    fn generate(rng: &mut Rng) -> Case {
+        let count = 1 + rng.below(MAX_INSTRUCTIONS);
+
+        let instructions: Vec<Instruction> = (0..count)
+            .map(|i| {
+                let read = |rng: &mut Rng| match rng.below(3) {
+                    0 => Parameter::Position(rng.below(DATA_SIZE)),
+                    1 => Parameter::Immediate(rng.between(-20, 20)),
+                    _ => Parameter::Relative(rng.below(DATA_SIZE)),
+                };
+                let write = |rng: &mut Rng| match rng.below(2) {
+                    0 => Parameter::Position(rng.below(DATA_SIZE)),
+                    _ => Parameter::Relative(rng.below(DATA_SIZE)),
+                };
+                // Anywhere after this instruction, including the final halt
+                let target = |rng: &mut Rng| Parameter::Target(i + 1 + rng.below(count - i));
+
+                let opcode = match rng.below(10) {
+                    0 => 99,
+                    n => n as i128,
+                };
+                let parameters = match opcode {
+                    1 | 2 | 7 | 8 => vec![read(rng), read(rng), write(rng)],
+                    3 => vec![write(rng)],
+                    4 => vec![read(rng)],
+                    5 | 6 => vec![read(rng), target(rng)],
+                    9 => vec![Parameter::Immediate(rng.between(0, MAX_BASE_ADJUSTMENT))],
+                    _ => vec![],
+                };
+                Instruction { opcode, parameters }
+            })
+            .collect();
+
+        let data = (0..DATA_SIZE).map(|_| rng.between(-10, 10)).collect();
+        let inputs = (0..rng.below(4)).map(|_| rng.between(-10, 10)).collect();
+
+        Case {
+            instructions,
+            data,
+            inputs,
+        }
+    }
+
+    // Lay the instructions out in memory, followed by a halt and the data
+    // region. The data region is padded so relative addressing stays inside
+    // it however far the relative base has moved.
+    pub fn program(&self) -> Vec<i128> {
+        let mut addresses: Vec<usize> = Vec::new();
+        let mut address = 0;
+        for i in &self.instructions {
+            addresses.push(address);
+            address += 1 + i.parameters.len();
+        }
+        addresses.push(address);
+
+        let data_start = (address + 1) as i128;
+
+        let mut program: Vec<i128> = Vec::new();
+        for i in &self.instructions {
+            let mut op = i.opcode;
+            let mut mode = 100;
+            for p in &i.parameters {
+                op += mode
+                    * match p {
+                        Parameter::Position(_) => 0,
+                        Parameter::Immediate(_) | Parameter::Target(_) => 1,
+                        Parameter::Relative(_) => 2,
+                    };
+                mode *= 10;
+            }
+            program.push(op);
+
+            for p in &i.parameters {
+                program.push(match p {
+                    Parameter::Position(offset) | Parameter::Relative(offset) => {
+                        data_start + *offset as i128
+                    }
+                    Parameter::Immediate(value) => *value,
+                    Parameter::Target(index) => addresses[*index] as i128,
+                });
+            }
+        }
+        program.push(99);
+
+        let relative_padding = MAX_BASE_ADJUSTMENT as usize * self.instructions.len();
+        program.extend(&self.data);
+        program.extend((0..relative_padding).map(|_| 0));
+        program
+    }
+
+    // Every way of making this case a little simpler, roughly in order of
+    // how much each one helps.
+    fn simplifications(&self) -> Vec<Case> {
+        let mut candidates: Vec<Case> = Vec::new();
+
+        for i in 0..self.instructions.len() {
+            let mut c = self.clone();
+            c.instructions.remove(i);
+            for instruction in c.instructions.iter_mut() {
+                for p in instruction.parameters.iter_mut() {
+                    if let Parameter::Target(t) = p {
+                        if *t > i {
+                            *t -= 1;
+                        }
+                    }
+                }
+            }
+            candidates.push(c);
+        }
+
+        for i in 0..self.inputs.len() {
+            let mut c = self.clone();
+            c.inputs.remove(i);
+            candidates.push(c);
+        }
+
+        for i in 0..self.data.len() {
+            if self.data[i] != 0 {
+                let mut c = self.clone();
+                c.data[i] = 0;
+                candidates.push(c);
+            }
+        }
+
+        for i in 0..self.instructions.len() {
+            for j in 0..self.instructions[i].parameters.len() {
+                let simpler = match self.instructions[i].parameters[j] {
+                    Parameter::Immediate(v) if v != 0 => Parameter::Immediate(v / 2),
+                    Parameter::Relative(offset) => Parameter::Position(offset),
+                    _ => continue,
+                };
+                let mut c = self.clone();
+                c.instructions[i].parameters[j] = simpler;
+                candidates.push(c);
+            }
+        }
+
+        candidates
+    }
+}
+
+#[derive(PartialEq, Debug)]
+pub enum Halt {
+    Terminated,
+    WaitForInput,
+}
+
+#[derive(PartialEq, Debug)]
+pub struct Outcome {
+    pub outputs: Vec<i128>,
+    pub halt: Halt,
+    pub memory: Vec<i128>,
+}
+
+// Anything that can run an Intcode program: the interpreter, or an optimized
+// or pre-decoded engine being checked against it.
+pub trait Engine {
+    // Run the program with all of the inputs available up front, until it
+    // halts or asks for more input than it was given.
+    fn execute(&self, program: &[i128], inputs: &[i128]) -> Outcome;
+}
+
+pub struct Interpreter;
+
+impl Engine for Interpreter {
+    fn execute(&self, program: &[i128], inputs: &[i128]) -> Outcome {
+        let mut vm = VirtualMachine::new(program);
+        for i in inputs {
+            vm.add_input(*i);
+        }
+
+        let mut outputs: Vec<i128> = Vec::new();
+        let halt = loop {
+            match vm.run() {
+                VirtualMachineState::Output(v) => outputs.push(v),
+                VirtualMachineState::WaitForInput => break Halt::WaitForInput,
+                VirtualMachineState::Terminated => break Halt::Terminated,
+            }
+        };
+
+        Outcome {
+            outputs,
+            halt,
+            memory: (0..program.len()).map(|i| vm.get_memory(i)).collect(),
+        }
+    }
+}
+
+// The most direct reading of the spec. Returns None if the arithmetic
+// overflows, since such programs aren't well-formed.
+fn reference(program: &[i128], inputs: &[i128]) -> Option<Outcome> {
+    let mut memory = program.to_vec();
+    let mut inputs = inputs.iter();
+    let mut outputs: Vec<i128> = Vec::new();
+    let mut ip = 0;
+    let mut base = 0;
+
+    let halt = loop {
+        let instruction = memory[ip];
+        let address = |memory: &Vec<i128>, n: usize| -> usize {
+            let mode = instruction / 10i128.pow(n as u32 + 1) % 10;
+            match mode {
+                0 => memory[ip + n] as usize,
+                1 => ip + n,
+                2 => (base + memory[ip + n]) as usize,
+                _ => panic!("Generated an invalid mode"),
+            }
+        };
+        let a = |memory: &Vec<i128>| memory[address(memory, 1)];
+        let b = |memory: &Vec<i128>| memory[address(memory, 2)];
+
+        match instruction % 100 {
+            1 => {
+                let c = address(&memory, 3);
+                memory[c] = a(&memory).checked_add(b(&memory))?;
+                ip += 4;
+            }
+            2 => {
+                let c = address(&memory, 3);
+                memory[c] = a(&memory).checked_mul(b(&memory))?;
+                ip += 4;
+            }
+            3 => match inputs.next() {
+                Some(v) => {
+                    let c = address(&memory, 1);
+                    memory[c] = *v;
+                    ip += 2;
+                }
+                None => break Halt::WaitForInput,
+            },
+            4 => {
+                outputs.push(a(&memory));
+                ip += 2;
+            }
+            5 => {
+                ip = if a(&memory) != 0 {
+                    b(&memory) as usize
+                } else {
+                    ip + 3
+                }
+            }
+            6 => {
+                ip = if a(&memory) == 0 {
+                    b(&memory) as usize
+                } else {
+                    ip + 3
+                }
+            }
+            7 => {
+                let c = address(&memory, 3);
+                memory[c] = (a(&memory) < b(&memory)) as i128;
+                ip += 4;
+            }
+            8 => {
+                let c = address(&memory, 3);
+                memory[c] = (a(&memory) == b(&memory)) as i128;
+                ip += 4;
+            }
+            9 => {
+                base += a(&memory);
+                ip += 2;
+            }
+            99 => break Halt::Terminated,
+            _ => panic!("Generated an invalid opcode"),
+        }
+    };
+
+    Some(Outcome {
+        outputs,
+        halt,
+        memory,
+    })
+}
+
+pub struct Failure {
+    pub case: Case,
+    pub expected: Outcome,
+    pub actual: Outcome,
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let program: Vec<String> = self.case.program().iter().map(|v| v.to_string()).collect();
+        writeln!(f, "program: {}", program.join(","))?;
+        writeln!(f, "inputs: {:?}", self.case.inputs)?;
+        writeln!(f, "expected: {:?}", self.expected)?;
+        write!(f, "actual: {:?}", self.actual)
+    }
+}
+
+fn check(engine: &dyn Engine, case: &Case) -> Option<Failure> {
+    let program = case.program();
+    let expected = reference(&program, &case.inputs)?;
+    let actual = engine.execute(&program, &case.inputs);
+    if actual == expected {
+        None
+    } else {
+        Some(Failure {
+            case: case.clone(),
+            expected,
+            actual,
+        })
+    }
+}
+
+// Greedily apply simplifications for as long as the engine keeps failing.
+fn minimize(engine: &dyn Engine, failure: Failure) -> Failure {
+    let mut smallest = failure;
+    loop {
+        let simpler = smallest
+            .case
+            .simplifications()
+            .iter()
+            .find_map(|c| check(engine, c));
+        match simpler {
+            Some(f) => smallest = f,
+            None => return smallest,
+        }
+    }
+}
+
+// Run a number of random cases against the engine, returning the first
+// disagreement with the reference interpreter after minimizing it.
+pub fn fuzz(engine: &dyn Engine, seed: u64, cases: usize) -> Option<Failure> {
+    let mut rng = Rng(seed | 1);
+    for _ in 0..cases {
+        let case = Case::generate(&mut rng);
+        if let Some(failure) = check(engine, &case) {
+            return Some(minimize(engine, failure));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interpreter_matches_reference() {
+        for seed in 1..=4 {
+            if let Some(failure) = fuzz(&Interpreter, seed, 1000) {
+                panic!("Seed {} disagrees with the reference:\n{}", seed, failure);
+            }
+        }
+    }
+
+    #[test]
+    fn generated_programs_are_well_formed() {
+        let mut rng = Rng(7);
+        for _ in 0..200 {
+            let case = Case::generate(&mut rng);
+            let program = case.program();
+            if let Some(outcome) = reference(&program, &case.inputs) {
+                assert_eq!(outcome.memory.len(), program.len());
+            }
+        }
+    }
+
+    // Loses the last output, like a subtly broken optimization might
+    struct DropsLastOutput;
+
+    impl Engine for DropsLastOutput {
+        fn execute(&self, program: &[i128], inputs: &[i128]) -> Outcome {
+            let mut outcome = Interpreter.execute(program, inputs);
+            outcome.outputs.pop();
+            outcome
+        }
+    }
+
+    #[test]
+    fn failures_are_minimized() {
+        let failure = fuzz(&DropsLastOutput, 1, 1000).expect("The broken engine should be caught");
+
+        // A single output of a constant, followed by the halt
+        assert_eq!(failure.case.instructions.len(), 1);
+        assert_eq!(
+            failure.case.instructions[0],
+            Instruction {
+                opcode: 4,
+                parameters: vec![Parameter::Immediate(0)],
+            }
+        );
+        assert!(failure.case.inputs.is_empty());
+        assert_eq!(failure.case.program()[..3], [104, 0, 99]);
+    }
+}
@@ -0,0 +1,294 @@
+use std::cmp;
+use std::fmt;
+use std::ops;
+
+// The signed integers a point can be made of
+pub trait Coordinate:
+    Copy
+    + Ord
+    + ops::Add<Output = Self>
+    + ops::Sub<Output = Self>
+    + ops::Mul<Output = Self>
+    + ops::Neg<Output = Self>
+{
+    const ZERO: Self;
+    const ONE: Self;
+
+    fn abs(self) -> Self;
+}
+
+macro_rules! coordinate {
+    ($($t:ty),*) => {
+        $(
+            impl Coordinate for $t {
+                const ZERO: Self = 0;
+                const ONE: Self = 1;
+
+                fn abs(self) -> Self {
+                    <$t>::abs(self)
+                }
+            }
+        )*
+    };
+}
+
+coordinate!(i32, i64, i128, isize);
+
+// A point on a grid, or the vector between two points. y grows downwards, as
+// it does on the screen and in the puzzle inputs.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Default)]
+pub struct Point<T = i32> {
+    pub x: T,
+    pub y: T,
+}
+
+impl<T: Coordinate> Point<T> {
+    pub const ORIGIN: Point<T> = Point {
+        x: T::ZERO,
+        y: T::ZERO,
+    };
+
+    pub fn new(x: T, y: T) -> Point<T> {
+        Point { x, y }
+    }
+
+    pub fn manhattan(self, other: Point<T>) -> T {
+        (self.x - other.x).abs() + (self.y - other.y).abs()
+    }
+
+    // One step in the given direction
+    pub fn step(self, direction: Direction) -> Point<T> {
+        self + direction.delta()
+    }
+
+    // The points above, right of, below and left of this one
+    pub fn neighbours4(self) -> [Point<T>; 4] {
+        Direction::ALL.map(|d| self.step(d))
+    }
+
+    // The neighbours including the diagonals, clockwise from above
+    #[allow(dead_code)]
+    pub fn neighbours8(self) -> [Point<T>; 8] {
+        let (o, i) = (T::ZERO, T::ONE);
+        [
+            (o, -i),
+            (i, -i),
+            (i, o),
+            (i, i),
+            (o, i),
+            (-i, i),
+            (-i, o),
+            (-i, -i),
+        ]
+        .map(|(dx, dy)| self + Point::new(dx, dy))
+    }
+}
+
+// Points sort in reading order: top to bottom, then left to right
+impl<T: Ord> Ord for Point<T> {
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        self.y.cmp(&other.y).then_with(|| self.x.cmp(&other.x))
+    }
+}
+
+impl<T: Ord> PartialOrd for Point<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Point<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{},{}", self.x, self.y)
+    }
+}
+
+impl<T: Coordinate> ops::Add for Point<T> {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Point::new(self.x + other.x, self.y + other.y)
+    }
+}
+
+impl<T: Coordinate> ops::Sub for Point<T> {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Point::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+impl<T: Coordinate> ops::Mul<T> for Point<T> {
+    type Output = Self;
+
+    fn mul(self, scale: T) -> Self {
+        Point::new(self.x * scale, self.y * scale)
+    }
+}
+
+impl<T: Coordinate> ops::Neg for Point<T> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Point::new(-self.x, -self.y)
+    }
+}
+
+impl<T: Coordinate> ops::AddAssign for Point<T> {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl<T: Coordinate> ops::SubAssign for Point<T> {
+    fn sub_assign(&mut self, other: Self) {
+        *self = *self - other;
+    }
+}
+
+// Which way to turn, 90 degrees at a time
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum Turn {
+    Left,
+    Right,
+}
+
+impl fmt::Display for Turn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Turn::Left => write!(f, "L"),
+            Turn::Right => write!(f, "R"),
+        }
+    }
+}
+
+// A direction on the grid, with north up the screen
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum Direction {
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Direction {
+    // Clockwise from north
+    pub const ALL: [Direction; 4] = [
+        Direction::North,
+        Direction::East,
+        Direction::South,
+        Direction::West,
+    ];
+
+    pub fn inverse(self) -> Direction {
+        match self {
+            Direction::North => Direction::South,
+            Direction::South => Direction::North,
+            Direction::East => Direction::West,
+            Direction::West => Direction::East,
+        }
+    }
+
+    pub fn turn(self, turn: Turn) -> Direction {
+        match (turn, self) {
+            (Turn::Left, Direction::North) | (Turn::Right, Direction::South) => Direction::West,
+            (Turn::Left, Direction::East) | (Turn::Right, Direction::West) => Direction::North,
+            (Turn::Left, Direction::South) | (Turn::Right, Direction::North) => Direction::East,
+            (Turn::Left, Direction::West) | (Turn::Right, Direction::East) => Direction::South,
+        }
+    }
+
+    // The turn that faces this way towards the given direction, if a single
+    // turn does it
+    pub fn turn_to(self, direction: Direction) -> Option<Turn> {
+        [Turn::Left, Turn::Right]
+            .iter()
+            .copied()
+            .find(|&turn| self.turn(turn) == direction)
+    }
+
+    // The vector for one step this way
+    pub fn delta<T: Coordinate>(self) -> Point<T> {
+        let (o, i) = (T::ZERO, T::ONE);
+        match self {
+            Direction::North => Point::new(o, -i),
+            Direction::East => Point::new(i, o),
+            Direction::South => Point::new(o, i),
+            Direction::West => Point::new(-i, o),
+        }
+    }
+}
+
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Direction::North => write!(f, "north"),
+            Direction::East => write!(f, "east"),
+            Direction::South => write!(f, "south"),
+            Direction::West => write!(f, "west"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn point_arithmetic() {
+        let (a, b) = (Point::new(3, -2), Point::new(-1, 5));
+        assert_eq!(a + b, Point::new(2, 3));
+        assert_eq!(a - b, Point::new(4, -7));
+        assert_eq!(-a * 2, Point::new(-6, 4));
+        assert_eq!(a.manhattan(b), 11);
+        assert_eq!(Point::<i128>::ORIGIN.manhattan(Point::new(3, 4)), 7);
+
+        let mut c = a;
+        c += b;
+        c -= a;
+        assert_eq!(c, b);
+        assert_eq!(a.to_string(), "3,-2");
+    }
+
+    #[test]
+    fn points_sort_in_reading_order() {
+        let mut points = vec![Point::new(0, 1), Point::new(1, 0), Point::new(0, 0)];
+        points.sort();
+        assert_eq!(
+            points,
+            vec![Point::new(0, 0), Point::new(1, 0), Point::new(0, 1)]
+        );
+    }
+
+    #[test]
+    fn neighbourhoods() {
+        let p = Point::new(5, 5);
+        assert_eq!(
+            p.neighbours4(),
+            [
+                Point::new(5, 4),
+                Point::new(6, 5),
+                Point::new(5, 6),
+                Point::new(4, 5)
+            ]
+        );
+        let around = p.neighbours8();
+        assert_eq!(around[1], Point::new(6, 4));
+        assert!(around.iter().all(|&n| n != p && (n - p).x.abs() <= 1));
+        assert!(p.neighbours4().iter().all(|n| around.contains(n)));
+    }
+
+    #[test]
+    fn turns() {
+        for &d in Direction::ALL.iter() {
+            assert_eq!(d.turn(Turn::Left).turn(Turn::Right), d);
+            assert_eq!(d.turn(Turn::Right).turn(Turn::Right), d.inverse());
+            assert_eq!(d.turn_to(d.turn(Turn::Left)), Some(Turn::Left));
+            assert_eq!(d.turn_to(d.inverse()), None);
+            assert_eq!(d.delta::<i32>() + d.inverse().delta(), Point::ORIGIN);
+        }
+        assert_eq!(Direction::North.turn(Turn::Right), Direction::East);
+        assert_eq!(Point::new(0, 0).step(Direction::North), Point::new(0, -1));
+    }
+}
@@ -0,0 +1,339 @@
+use std::ops::Range;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use crate::intcode::{VirtualMachine, VirtualMachineState};
+
+// A memory cell that can be patched before the program runs, along with the
+// values it's allowed to take.
+#[derive(Clone)]
+pub struct Variable {
+    pub address: usize,
+    pub values: Range<i128>,
+}
+
+// Patch the program with a value for each variable, run it to completion and
+// read back the output cell. Programs that ask for input can't be evaluated.
+pub fn evaluate(
+    program: &[i128],
+    variables: &[Variable],
+    values: &[i128],
+    output: usize,
+) -> Option<i128> {
+    let mut vm = VirtualMachine::new(program);
+    for (variable, value) in variables.iter().zip(values) {
+        vm.set_memory(variable.address, *value);
+    }
+
+    loop {
+        match vm.run() {
+            VirtualMachineState::Output(_) => {}
+            VirtualMachineState::WaitForInput => {
+                return None;
+            }
+            VirtualMachineState::Terminated => {
+                return Some(vm.get_memory(output));
+            }
+        }
+    }
+}
+
+// Find values for the variables that make the program leave the target value
+// in the output cell.
+//
+// Most patching puzzles compute something linear in the patched cells, so
+// first probe each variable on its own and, if a linear model predicts the
+// output everywhere we check, solve it directly. If the model has no solution
+// in range there's nothing to find. Only when there's no model, or an answer
+// it gives turns out not to work, fall back to trying every combination on all
+// the available cores.
+pub fn solve(
+    program: &[i128],
+    variables: &[Variable],
+    output: usize,
+    target: i128,
+) -> Option<Vec<i128>> {
+    if variables.iter().any(|v| v.values.start >= v.values.end) {
+        return None;
+    }
+
+    let linear = linear_model(program, variables, output).map(|(base, coefficients)| {
+        solve_linear(program, variables, output, target, base, &coefficients)
+    });
+    match linear {
+        Some(Linear::Solved(values)) => Some(values),
+        Some(Linear::Unreachable) => None,
+        Some(Linear::Wrong) | None => brute_force(program, variables, output, target),
+    }
+}
+
+// What solving the linear model came to: values that reach the target, no
+// values in range that would if the model is right, or an answer the model
+// predicted that the program didn't agree with
+enum Linear {
+    Solved(Vec<i128>),
+    Unreachable,
+    Wrong,
+}
+
+// Probe the output as a function of each variable. Returns the output with
+// every variable at the start of its range, and the change in output per unit
+// change of each variable, if that model holds at the corners we test.
+fn linear_model(
+    program: &[i128],
+    variables: &[Variable],
+    output: usize,
+) -> Option<(i128, Vec<i128>)> {
+    let low: Vec<i128> = variables.iter().map(|v| v.values.start).collect();
+    let high: Vec<i128> = variables.iter().map(|v| v.values.end - 1).collect();
+
+    let base = evaluate(program, variables, &low, output)?;
+
+    let mut coefficients: Vec<i128> = Vec::new();
+    for i in 0..variables.len() {
+        if high[i] == low[i] {
+            coefficients.push(0);
+            continue;
+        }
+        let mut probe = low.clone();
+        probe[i] += 1;
+        coefficients.push(evaluate(program, variables, &probe, output)? - base);
+    }
+
+    let predict = |values: &[i128]| -> i128 {
+        let mut prediction = base;
+        for i in 0..variables.len() {
+            prediction += coefficients[i] * (values[i] - low[i]);
+        }
+        prediction
+    };
+
+    // Check each variable at the far end of its range, every variable at the
+    // far end together, and a point in the middle of the space.
+    let mut checks: Vec<Vec<i128>> = Vec::new();
+    for i in 0..variables.len() {
+        let mut check = low.clone();
+        check[i] = high[i];
+        checks.push(check);
+    }
+    checks.push(high.clone());
+    checks.push(
+        low.iter()
+            .zip(&high)
+            .map(|(l, h)| l + (h - l) / 2)
+            .collect(),
+    );
+
+    for check in checks {
+        if evaluate(program, variables, &check, output)? != predict(&check) {
+            return None;
+        }
+    }
+
+    Some((base, coefficients))
+}
+
+fn solve_linear(
+    program: &[i128],
+    variables: &[Variable],
+    output: usize,
+    target: i128,
+    base: i128,
+    coefficients: &[i128],
+) -> Linear {
+    let low: Vec<i128> = variables.iter().map(|v| v.values.start).collect();
+
+    // Solve for the variable with the widest range and enumerate the rest.
+    let solve_for = (0..variables.len())
+        .filter(|&i| coefficients[i] != 0)
+        .max_by_key(|&i| variables[i].values.end - variables[i].values.start);
+
+    let solve_for = match solve_for {
+        Some(i) => i,
+        None => {
+            // The output doesn't depend on any of the variables
+            return if base == target {
+                Linear::Solved(low)
+            } else {
+                Linear::Unreachable
+            };
+        }
+    };
+
+    let others: Vec<Variable> = variables
+        .iter()
+        .enumerate()
+        .map(|(i, v)| {
+            if i == solve_for {
+                Variable {
+                    address: v.address,
+                    values: v.values.start..v.values.start + 1,
+                }
+            } else {
+                v.clone()
+            }
+        })
+        .collect();
+
+    let mut wrong = false;
+    let mut values = low.clone();
+    loop {
+        let mut remaining = target - base;
+        for i in 0..variables.len() {
+            if i != solve_for {
+                remaining -= coefficients[i] * (values[i] - low[i]);
+            }
+        }
+
+        if remaining % coefficients[solve_for] == 0 {
+            let value = low[solve_for] + remaining / coefficients[solve_for];
+            if variables[solve_for].values.contains(&value) {
+                let mut candidate = values.clone();
+                candidate[solve_for] = value;
+
+                // The model was only checked at a handful of points, so make
+                // sure the answer really works.
+                if evaluate(program, variables, &candidate, output) == Some(target) {
+                    return Linear::Solved(candidate);
+                }
+                wrong = true;
+            }
+        }
+
+        if !next_values(&others, &mut values) {
+            return if wrong {
+                Linear::Wrong
+            } else {
+                Linear::Unreachable
+            };
+        }
+    }
+}
+
+// Step through every combination of values, with the last variable changing
+// fastest. Returns false once every combination has been visited.
+fn next_values(variables: &[Variable], values: &mut [i128]) -> bool {
+    for i in (0..variables.len()).rev() {
+        values[i] += 1;
+        if values[i] < variables[i].values.end {
+            return true;
+        }
+        values[i] = variables[i].values.start;
+    }
+    false
+}
+
+fn brute_force(
+    program: &[i128],
+    variables: &[Variable],
+    output: usize,
+    target: i128,
+) -> Option<Vec<i128>> {
+    if variables.is_empty() {
+        return match evaluate(program, variables, &[], output) {
+            Some(v) if v == target => Some(Vec::new()),
+            _ => None,
+        };
+    }
+
+    // Split the first variable's range into one chunk per thread. Each chunk
+    // is searched in order, so taking the lowest chunk with an answer gives
+    // the same result as a single-threaded search.
+    let first = &variables[0].values;
+    let threads = thread::available_parallelism().map_or(1, |n| n.get()) as i128;
+    let chunk_size = ((first.end - first.start) + threads - 1) / threads;
+
+    let chunks: Vec<Range<i128>> = (0..threads)
+        .map(|t| {
+            let start = first.start + t * chunk_size;
+            start..(start + chunk_size).min(first.end)
+        })
+        .filter(|chunk| chunk.start < chunk.end)
+        .collect();
+
+    let found = AtomicUsize::new(usize::MAX);
+
+    let results: Vec<Option<Vec<i128>>> = thread::scope(|scope| {
+        let handles: Vec<_> = chunks
+            .iter()
+            .enumerate()
+            .map(|(c, chunk)| {
+                let found = &found;
+                scope.spawn(move || {
+                    let mut search: Vec<Variable> = variables.to_vec();
+                    search[0].values = chunk.clone();
+
+                    let mut values: Vec<i128> = search.iter().map(|v| v.values.start).collect();
+                    loop {
+                        if found.load(Ordering::Relaxed) < c {
+                            return None;
+                        }
+                        if evaluate(program, &search, &values, output) == Some(target) {
+                            found.fetch_min(c, Ordering::Relaxed);
+                            return Some(values);
+                        }
+                        if !next_values(&search, &mut values) {
+                            return None;
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .map(|h| h.join().expect("Search thread panicked"))
+            .collect()
+    });
+
+    results.into_iter().flatten().next()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn variable(address: usize, values: Range<i128>) -> Variable {
+        Variable { address, values }
+    }
+
+    #[test]
+    fn solves_a_linear_program() {
+        // mem[0] = mem[5] + mem[6]
+        let program = vec![1, 5, 6, 0, 99, 0, 0];
+        let variables = vec![variable(5, 0..100), variable(6, 0..100)];
+        assert!(linear_model(&program, &variables, 0).is_some());
+
+        let values = solve(&program, &variables, 0, 150).unwrap();
+        assert_eq!(values.iter().sum::<i128>(), 150);
+        assert_eq!(evaluate(&program, &variables, &values, 0), Some(150));
+    }
+
+    #[test]
+    fn falls_back_when_the_linear_model_is_wrong() {
+        // mem[0] = mem[21] + (mem[21] == 5) - (mem[21] == 6), which swaps 5
+        // and 6 but is linear at every point the model checks
+        let mut program = vec![
+            1008, 21, 5, 22, 1008, 21, 6, 23, 1, 21, 22, 24, 1002, 23, -1, 23, 1, 24, 23, 0, 99,
+        ];
+        program.resize(25, 0);
+        let variables = vec![variable(21, 0..10)];
+        let (base, coefficients) = linear_model(&program, &variables, 0).unwrap();
+        assert_eq!((base, coefficients), (0, vec![1]));
+
+        // The model says 5, which the program turns into 6
+        assert_eq!(solve(&program, &variables, 0, 5), Some(vec![6]));
+    }
+
+    #[test]
+    fn gives_up_on_an_unreachable_target() {
+        let program = vec![1, 5, 6, 0, 99, 0, 0];
+        let variables = vec![variable(5, 0..100), variable(6, 0..100)];
+        assert!(matches!(
+            solve_linear(&program, &variables, 0, 1000, 0, &[1, 1]),
+            Linear::Unreachable
+        ));
+        assert_eq!(solve(&program, &variables, 0, 1000), None);
+        assert_eq!(solve(&program, &[variable(5, 3..3)], 0, 0), None);
+    }
+}
@@ -0,0 +1,329 @@
+use std::collections::HashMap;
+use std::ops;
+
+use crate::geom::Point;
+
+// A rectangular grid of cells, indexed by points with (0, 0) at the top left
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    pub fn new(width: usize, height: usize, fill: T) -> Grid<T>
+    where
+        T: Clone,
+    {
+        Grid {
+            width,
+            height,
+            cells: vec![fill; width * height],
+        }
+    }
+
+    // A grid with each cell worked out from where it is
+    pub fn from_fn<F>(width: usize, height: usize, mut f: F) -> Grid<T>
+    where
+        F: FnMut(Point) -> T,
+    {
+        let mut grid = Grid {
+            width,
+            height,
+            cells: Vec::with_capacity(width * height),
+        };
+        grid.cells = grid.points().map(&mut f).collect();
+        grid
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    fn index(&self, p: Point) -> Option<usize> {
+        if self.contains(p) {
+            Some(p.y as usize * self.width + p.x as usize)
+        } else {
+            None
+        }
+    }
+
+    pub fn contains(&self, p: Point) -> bool {
+        p.x >= 0 && p.y >= 0 && (p.x as usize) < self.width && (p.y as usize) < self.height
+    }
+
+    pub fn get(&self, p: Point) -> Option<&T> {
+        self.index(p).map(|i| &self.cells[i])
+    }
+
+    pub fn get_mut(&mut self, p: Point) -> Option<&mut T> {
+        self.index(p).map(move |i| &mut self.cells[i])
+    }
+
+    // Replace the cell at p, returning what was there, or None if p is
+    // outside the grid
+    pub fn set(&mut self, p: Point, value: T) -> Option<T> {
+        self.get_mut(p).map(|cell| std::mem::replace(cell, value))
+    }
+
+    // Every point in the grid, in reading order
+    pub fn points(&self) -> impl Iterator<Item = Point> {
+        let width = self.width;
+        (0..self.width * self.height)
+            .map(move |i| Point::new((i % width) as i32, (i / width) as i32))
+    }
+
+    // Every point in the grid with its cell, in reading order
+    pub fn iter(&self) -> impl Iterator<Item = (Point, &T)> {
+        self.points().zip(self.cells.iter())
+    }
+
+    // The cells above, right of, below and left of p that are inside the grid
+    pub fn neighbours(&self, p: Point) -> impl Iterator<Item = (Point, &T)> {
+        // Called through the trait, as 2018 edition arrays iterate by reference
+        IntoIterator::into_iter(p.neighbours4())
+            .filter_map(move |n| self.get(n).map(|cell| (n, cell)))
+    }
+
+    // Where each cell holding the value is, in reading order
+    pub fn find_all<'a>(&'a self, value: &'a T) -> impl Iterator<Item = Point> + 'a
+    where
+        T: PartialEq,
+    {
+        self.iter()
+            .filter(move |(_, cell)| *cell == value)
+            .map(|(p, _)| p)
+    }
+
+    pub fn row(&self, y: usize) -> &[T] {
+        assert!(y < self.height, "row {} is outside the grid", y);
+        &self.cells[y * self.width..(y + 1) * self.width]
+    }
+
+    pub fn column(&self, x: usize) -> impl Iterator<Item = &T> {
+        assert!(x < self.width, "column {} is outside the grid", x);
+        self.cells[x..].iter().step_by(self.width)
+    }
+
+    pub fn map<U, F>(&self, f: F) -> Grid<U>
+    where
+        F: Fn(&T) -> U,
+    {
+        Grid {
+            width: self.width,
+            height: self.height,
+            cells: self.cells.iter().map(f).collect(),
+        }
+    }
+
+    // Draw the grid as lines of text, one character per cell
+    pub fn render<F>(&self, cell: F) -> String
+    where
+        F: Fn(&T) -> char,
+    {
+        let mut s = String::with_capacity((self.width + 1) * self.height);
+        for y in 0..self.height {
+            s.extend(self.row(y).iter().map(&cell));
+            s.push('\n');
+        }
+        s
+    }
+
+    // The smallest grid holding every point of a sparse map, with the gaps
+    // filled in, along with where the grid's top left corner is on the map
+    pub fn from_sparse(map: &HashMap<Point, T>, fill: T) -> (Grid<T>, Point)
+    where
+        T: Clone,
+    {
+        if map.is_empty() {
+            return (Grid::new(0, 0, fill), Point::ORIGIN);
+        }
+
+        let min = Point::new(
+            map.keys().map(|p| p.x).min().unwrap(),
+            map.keys().map(|p| p.y).min().unwrap(),
+        );
+        let max = Point::new(
+            map.keys().map(|p| p.x).max().unwrap(),
+            map.keys().map(|p| p.y).max().unwrap(),
+        );
+
+        let size = max - min;
+        let mut grid = Grid::new(size.x as usize + 1, size.y as usize + 1, fill);
+        for (&p, value) in map {
+            grid[p - min] = value.clone();
+        }
+        (grid, min)
+    }
+
+    // Back to a sparse map with the top left corner at origin, leaving out
+    // the filler
+    #[allow(dead_code)]
+    pub fn to_sparse(&self, origin: Point, fill: &T) -> HashMap<Point, T>
+    where
+        T: Clone + PartialEq,
+    {
+        self.iter()
+            .filter(|(_, cell)| *cell != fill)
+            .map(|(p, cell)| (p + origin, cell.clone()))
+            .collect()
+    }
+}
+
+impl Grid<char> {
+    fn from_lines(lines: &[&str], fill: Option<char>) -> Result<Grid<char>, String> {
+        let width = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+        if width == 0 {
+            return Err("The map is empty".to_string());
+        }
+
+        let mut cells = Vec::with_capacity(width * lines.len());
+        for line in lines {
+            let len = cells.len();
+            cells.extend(line.chars());
+            match fill {
+                Some(fill) => cells.resize(len + width, fill),
+                None if cells.len() != len + width => {
+                    return Err("The map isn't rectangular".to_string())
+                }
+                None => {}
+            }
+        }
+        Ok(Grid {
+            width,
+            height: lines.len(),
+            cells,
+        })
+    }
+
+    // A grid of characters, one row per line. Blank lines and indentation are
+    // ignored, and every row must be the same length.
+    pub fn parse(text: &str) -> Result<Grid<char>, String> {
+        let lines: Vec<&str> = text
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .collect();
+        Grid::from_lines(&lines, None)
+    }
+
+    // A grid of characters where spaces matter, so lines are kept as they are
+    // and the short ones (trimmed by an editor, say) are padded with fill
+    pub fn parse_padded(text: &str, fill: char) -> Result<Grid<char>, String> {
+        let lines: Vec<&str> = text.lines().filter(|l| !l.trim().is_empty()).collect();
+        Grid::from_lines(&lines, Some(fill))
+    }
+}
+
+impl<T> ops::Index<Point> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, p: Point) -> &T {
+        match self.get(p) {
+            Some(cell) => cell,
+            None => panic!("{} is outside the {}x{} grid", p, self.width, self.height),
+        }
+    }
+}
+
+impl<T> ops::IndexMut<Point> for Grid<T> {
+    fn index_mut(&mut self, p: Point) -> &mut T {
+        let (width, height) = (self.width, self.height);
+        match self.get_mut(p) {
+            Some(cell) => cell,
+            None => panic!("{} is outside the {}x{} grid", p, width, height),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "
+        #.#.
+        ..#.
+        ##..";
+
+    #[test]
+    fn parses_and_renders() {
+        let grid = Grid::parse(EXAMPLE).unwrap();
+        assert_eq!((grid.width(), grid.height()), (4, 3));
+        assert_eq!(grid.render(|&c| c), "#.#.\n..#.\n##..\n");
+        assert_eq!(
+            grid.map(|&c| c == '#')
+                .render(|&b| if b { 'X' } else { ' ' }),
+            "X X \n  X \nXX  \n"
+        );
+
+        assert!(Grid::parse("").is_err());
+        assert!(Grid::parse("##\n#").is_err());
+
+        let padded = Grid::parse_padded("  A\n #\n", '?').unwrap();
+        assert_eq!(padded.render(|&c| c), "  A\n #?\n");
+    }
+
+    #[test]
+    fn bounds_checked() {
+        let mut grid = Grid::parse(EXAMPLE).unwrap();
+        assert_eq!(grid.get(Point::new(2, 1)), Some(&'#'));
+        assert_eq!(grid.get(Point::new(4, 1)), None);
+        assert_eq!(grid.get(Point::new(0, -1)), None);
+
+        assert_eq!(grid.set(Point::new(1, 1), '@'), Some('.'));
+        assert_eq!(grid.set(Point::new(1, 3), '@'), None);
+        assert_eq!(grid[Point::new(1, 1)], '@');
+
+        let around: Vec<Point> = grid.neighbours(Point::new(0, 0)).map(|(p, _)| p).collect();
+        assert_eq!(around, vec![Point::new(1, 0), Point::new(0, 1)]);
+    }
+
+    #[test]
+    fn finds_and_slices() {
+        let grid = Grid::parse(EXAMPLE).unwrap();
+        let walls: Vec<Point> = grid.find_all(&'#').collect();
+        assert_eq!(walls.len(), 5);
+        assert_eq!(walls[0], Point::new(0, 0));
+        assert_eq!(walls[4], Point::new(1, 2));
+
+        assert_eq!(grid.row(1), &['.', '.', '#', '.']);
+        assert_eq!(grid.column(2).collect::<String>(), "##.");
+        assert_eq!(grid.column(3).collect::<String>(), "...");
+    }
+
+    #[test]
+    #[should_panic(expected = "column 4 is outside the grid")]
+    fn column_past_the_edge() {
+        Grid::parse(EXAMPLE).unwrap().column(4).count();
+    }
+
+    #[test]
+    #[should_panic(expected = "column 0 is outside the grid")]
+    fn column_of_an_empty_grid() {
+        Grid::from_fn(0, 2, |_| false).column(0).count();
+    }
+
+    #[test]
+    fn converts_sparse_maps() {
+        let mut map: HashMap<Point, i128> = HashMap::new();
+        map.insert(Point::new(-1, 2), 1);
+        map.insert(Point::new(1, 3), 1);
+        map.insert(Point::new(0, 3), 0);
+
+        let (grid, corner) = Grid::from_sparse(&map, 0);
+        assert_eq!(corner, Point::new(-1, 2));
+        assert_eq!(
+            grid.render(|&c| if c == 1 { '#' } else { '.' }),
+            "#..\n..#\n"
+        );
+
+        let mut painted = map.clone();
+        painted.retain(|_, &mut colour| colour != 0);
+        assert_eq!(grid.to_sparse(corner, &0), painted);
+    }
+}
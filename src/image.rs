@@ -0,0 +1,274 @@
+use std::collections::HashMap;
+
+use crate::geom::Point;
+use crate::grid::Grid;
+
+// A colour, as red, green and blue
+pub type Rgb = [u8; 3];
+
+pub const BLACK: Rgb = [0, 0, 0];
+pub const WHITE: Rgb = [255, 255, 255];
+
+// How an image is saved: PPM is the simplest thing most viewers open, and PNG
+// is what can go in a bug report or a web page
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Format {
+    Ppm,
+    Png,
+}
+
+impl Format {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Ppm => "ppm",
+            Format::Png => "png",
+        }
+    }
+}
+
+// A picture, one colour per pixel in reading order
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Image {
+    width: usize,
+    height: usize,
+    pixels: Vec<Rgb>,
+}
+
+impl Image {
+    // A pixel per cell of the grid, coloured by the palette
+    pub fn from_grid<T, F>(grid: &Grid<T>, palette: F) -> Image
+    where
+        F: Fn(&T) -> Rgb,
+    {
+        Image {
+            width: grid.width(),
+            height: grid.height(),
+            pixels: grid.iter().map(|(_, cell)| palette(cell)).collect(),
+        }
+    }
+
+    // Each pixel blown up into a square of scale by scale pixels, as a pixel
+    // per tile is too small to see
+    pub fn scaled(&self, scale: usize) -> Image {
+        let (width, height) = (self.width * scale, self.height * scale);
+        let grid = Grid::from_fn(width, height, |p| {
+            let (x, y) = (p.x as usize / scale, p.y as usize / scale);
+            self.pixels[y * self.width + x]
+        });
+        Image::from_grid(&grid, |&colour| colour)
+    }
+
+    pub fn encode(&self, format: Format) -> Vec<u8> {
+        match format {
+            Format::Ppm => self.ppm(),
+            Format::Png => self.png(),
+        }
+    }
+
+    // A binary PPM: a short text header, then the pixels as they are
+    fn ppm(&self) -> Vec<u8> {
+        let mut bytes = format!("P6\n{} {}\n255\n", self.width, self.height).into_bytes();
+        bytes.extend(self.pixels.iter().flatten());
+        bytes
+    }
+
+    // A PNG whose pixels are stored without compression. The puzzles' pictures
+    // are small enough that it doesn't matter, and it needs no deflate.
+    fn png(&self) -> Vec<u8> {
+        let mut header = Vec::with_capacity(13);
+        header.extend(&(self.width as u32).to_be_bytes());
+        header.extend(&(self.height as u32).to_be_bytes());
+        // 8 bits per channel, RGB, and the standard compression, filter and
+        // (no) interlacing
+        header.extend(&[8, 2, 0, 0, 0]);
+
+        // Each row starts with the filter it uses, which is always none
+        let mut rows = Vec::with_capacity((self.width * 3 + 1) * self.height);
+        for y in 0..self.height {
+            rows.push(0);
+            rows.extend(
+                self.pixels[y * self.width..(y + 1) * self.width]
+                    .iter()
+                    .flatten(),
+            );
+        }
+
+        let mut bytes = vec![0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+        png_chunk(&mut bytes, b"IHDR", &header);
+        png_chunk(&mut bytes, b"IDAT", &zlib_stored(&rows));
+        png_chunk(&mut bytes, b"IEND", &[]);
+        bytes
+    }
+}
+
+fn png_chunk(bytes: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    bytes.extend(&(data.len() as u32).to_be_bytes());
+    let start = bytes.len();
+    bytes.extend(kind);
+    bytes.extend(data);
+    let crc = crc32(&bytes[start..]);
+    bytes.extend(&crc.to_be_bytes());
+}
+
+// The largest a stored deflate block can be
+const MAX_STORED: usize = 65535;
+
+// A zlib stream of uncompressed deflate blocks
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    // Deflate with a 32K window, and no preset dictionary
+    let mut bytes = vec![0x78, 0x01];
+    let blocks = data.len().div_ceil(MAX_STORED).max(1);
+    for i in 0..blocks {
+        let block = &data[i * MAX_STORED..data.len().min((i + 1) * MAX_STORED)];
+        let last = i + 1 == blocks;
+        bytes.push(last as u8);
+        bytes.extend(&(block.len() as u16).to_le_bytes());
+        bytes.extend(&(!(block.len() as u16)).to_le_bytes());
+        bytes.extend(block);
+    }
+    bytes.extend(&adler32(data).to_be_bytes());
+    bytes
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in data {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+// The CRC of every byte, for the polynomial PNG uses
+const CRC_TABLE: [u32; 256] = crc_table();
+
+const fn crc_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut n = 0;
+    while n < 256 {
+        let mut c = n as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 == 1 {
+                0xedb8_8320 ^ (c >> 1)
+            } else {
+                c >> 1
+            };
+            k += 1;
+        }
+        table[n] = c;
+        n += 1;
+    }
+    table
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let crc = data.iter().fold(!0u32, |c, &byte| {
+        CRC_TABLE[((c ^ byte as u32) & 0xff) as usize] ^ (c >> 8)
+    });
+    !crc
+}
+
+// The picture of a sparse map, like a hull that's been painted, with the
+// tiles that weren't seen drawn in the background colour
+pub fn from_sparse<T, F>(map: &HashMap<Point, T>, background: Rgb, palette: F) -> Image
+where
+    T: Clone,
+    F: Fn(&T) -> Rgb,
+{
+    let sparse = map
+        .iter()
+        .map(|(&p, tile)| (p, Some(tile.clone())))
+        .collect();
+    let (grid, _) = Grid::from_sparse(&sparse, None);
+    Image::from_grid(&grid, |tile| tile.as_ref().map_or(background, &palette))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn checkerboard() -> Image {
+        let grid = Grid::from_fn(3, 2, |p| (p.x + p.y) % 2 == 0);
+        Image::from_grid(&grid, |&on| if on { WHITE } else { BLACK })
+    }
+
+    #[test]
+    fn checksums() {
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+        assert_eq!(crc32(b"IEND"), 0xae42_6082);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+    }
+
+    #[test]
+    fn writes_ppm() {
+        let ppm = checkerboard().encode(Format::Ppm);
+        let header = b"P6\n3 2\n255\n";
+        assert_eq!(&ppm[..header.len()], header);
+        assert_eq!(
+            &ppm[header.len()..],
+            &[255, 255, 255, 0, 0, 0, 255, 255, 255, 0, 0, 0, 255, 255, 255, 0, 0, 0][..]
+        );
+    }
+
+    // Split a PNG into its chunks, checking each one's CRC
+    fn chunks(png: &[u8]) -> Vec<(String, Vec<u8>)> {
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+        let mut chunks = Vec::new();
+        let mut rest = &png[8..];
+        while !rest.is_empty() {
+            let length = u32::from_be_bytes([rest[0], rest[1], rest[2], rest[3]]) as usize;
+            let (body, crc) = rest[4..].split_at(4 + length);
+            assert_eq!(crc32(body).to_be_bytes(), crc[..4]);
+            let kind = String::from_utf8(body[..4].to_vec()).unwrap();
+            chunks.push((kind, body[4..].to_vec()));
+            rest = &crc[4..];
+        }
+        chunks
+    }
+
+    // Read back a zlib stream of stored blocks
+    fn unstore(zlib: &[u8]) -> Vec<u8> {
+        assert_eq!(&zlib[..2], &[0x78, 0x01]);
+        let mut data = Vec::new();
+        let mut rest = &zlib[2..];
+        loop {
+            let length = u16::from_le_bytes([rest[1], rest[2]]) as usize;
+            assert_eq!(!u16::from_le_bytes([rest[3], rest[4]]) as usize, length);
+            data.extend(&rest[5..5 + length]);
+            let last = rest[0] == 1;
+            rest = &rest[5 + length..];
+            if last {
+                break;
+            }
+        }
+        assert_eq!(rest, &adler32(&data).to_be_bytes()[..]);
+        data
+    }
+
+    #[test]
+    fn writes_png() {
+        let png = checkerboard().encode(Format::Png);
+        let chunks = chunks(&png);
+        let kinds: Vec<&str> = chunks.iter().map(|(kind, _)| kind.as_str()).collect();
+        assert_eq!(kinds, vec!["IHDR", "IDAT", "IEND"]);
+        assert_eq!(chunks[0].1, vec![0, 0, 0, 3, 0, 0, 0, 2, 8, 2, 0, 0, 0]);
+        assert_eq!(
+            unstore(&chunks[1].1),
+            vec![
+                0, 255, 255, 255, 0, 0, 0, 255, 255, 255, //
+                0, 0, 0, 0, 255, 255, 255, 0, 0, 0,
+            ]
+        );
+    }
+
+    #[test]
+    fn splits_big_pictures_into_blocks() {
+        let png = checkerboard().scaled(100).encode(Format::Png);
+        let rows = unstore(&chunks(&png)[1].1);
+        assert!(rows.len() > MAX_STORED);
+        assert_eq!(rows.len(), (300 * 3 + 1) * 200);
+        assert_eq!(&rows[1..4], &WHITE);
+        assert_eq!(&rows[1 + 100 * 3..1 + 100 * 3 + 3], &BLACK);
+    }
+}
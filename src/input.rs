@@ -0,0 +1,79 @@
+use std::ffi::OsString;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+// Where the inputs live when no other directory is given
+const DEFAULT_DATA_DIR: &str = "data";
+
+// The directory the inputs live in, from AOC_DATA_DIR or the default
+pub fn data_dir(data_dir: Option<OsString>) -> PathBuf {
+    PathBuf::from(data_dir.unwrap_or_else(|| DEFAULT_DATA_DIR.into()))
+}
+
+// Where a day's puzzle input comes from
+#[derive(PartialEq, Eq, Debug)]
+pub enum Input {
+    Stdin,
+    File(PathBuf),
+}
+
+impl Input {
+    // An explicit input ("-" for stdin) wins, otherwise the day's file is
+    // looked up in the data directory (AOC_DATA_DIR, falling back to data/)
+    pub fn for_day(day: usize, input: Option<&str>, data_dir: Option<OsString>) -> Input {
+        match input {
+            Some("-") => Input::Stdin,
+            Some(path) => Input::File(PathBuf::from(path)),
+            None => {
+                let mut path = self::data_dir(data_dir);
+                path.push(format!("day{:02}.txt", day));
+                Input::File(path)
+            }
+        }
+    }
+
+    pub fn read(&self) -> io::Result<String> {
+        match self {
+            Input::Stdin => io::read_to_string(io::stdin()),
+            Input::File(path) => fs::read_to_string(path),
+        }
+    }
+}
+
+impl fmt::Display for Input {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Input::Stdin => write!(f, "<stdin>"),
+            Input::File(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_to_the_data_directory() {
+        assert_eq!(
+            Input::for_day(7, None, None),
+            Input::File(PathBuf::from("data/day07.txt"))
+        );
+        assert_eq!(
+            Input::for_day(17, None, Some("/tmp/aoc".into())),
+            Input::File(PathBuf::from("/tmp/aoc/day17.txt"))
+        );
+    }
+
+    #[test]
+    fn explicit_input_wins() {
+        assert_eq!(
+            Input::for_day(3, Some("wires.txt"), Some("/tmp/aoc".into())),
+            Input::File(PathBuf::from("wires.txt"))
+        );
+        assert_eq!(Input::for_day(3, Some("-"), None), Input::Stdin);
+        assert_eq!(Input::Stdin.to_string(), "<stdin>");
+    }
+}
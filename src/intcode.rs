@@ -1,27 +1,193 @@
+use std::error;
+use std::fmt;
 use std::fs;
 use std::io;
-use std::io::BufRead;
+use std::io::Read;
+use std::path::Path;
 
-pub fn load_program(filename: String) -> Vec<i128> {
-    let file_in = fs::File::open(filename).expect("Can't read file");
-    let file_reader = io::BufReader::new(file_in);
-    let line: Vec<String> = file_reader.lines().filter_map(io::Result::ok).collect();
+#[derive(Debug)]
+pub enum LoadError {
+    // Reading from files is only needed by tools outside the puzzle runner,
+    // which hands each day its input as a string
+    #[allow(dead_code)]
+    Io {
+        path: Option<String>,
+        error: io::Error,
+    },
+    InvalidToken {
+        path: Option<String>,
+        line: usize,
+        column: usize,
+        token: String,
+    },
+    Empty {
+        path: Option<String>,
+    },
+}
+
+#[allow(dead_code)]
+impl LoadError {
+    fn with_path(self, p: &Path) -> LoadError {
+        let p = Some(p.display().to_string());
+        match self {
+            LoadError::Io { error, .. } => LoadError::Io { path: p, error },
+            LoadError::InvalidToken {
+                line,
+                column,
+                token,
+                ..
+            } => LoadError::InvalidToken {
+                path: p,
+                line,
+                column,
+                token,
+            },
+            LoadError::Empty { .. } => LoadError::Empty { path: p },
+        }
+    }
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let source = |path: &Option<String>| -> String {
+            match path {
+                Some(p) => p.clone(),
+                None => "<input>".to_string(),
+            }
+        };
+        match self {
+            LoadError::Io { path, error } => {
+                write!(f, "{}: can't read program: {}", source(path), error)
+            }
+            LoadError::InvalidToken {
+                path,
+                line,
+                column,
+                token,
+            } => write!(
+                f,
+                "{}:{}:{}: invalid token '{}'",
+                source(path),
+                line,
+                column,
+                token
+            ),
+            LoadError::Empty { path } => write!(f, "{}: program is empty", source(path)),
+        }
+    }
+}
+
+impl error::Error for LoadError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            LoadError::Io { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+pub fn parse_program(source: &str) -> Result<Vec<i128>, LoadError> {
+    let mut program: Vec<i128> = Vec::new();
+
+    for (l, line) in source.lines().enumerate() {
+        // Everything after a '#' is a comment
+        let code = match line.find('#') {
+            Some(i) => &line[..i],
+            None => line,
+        };
+
+        // Values are separated by commas, and a program may be split across
+        // several lines, each optionally ending with a trailing comma.
+        let tokens: Vec<&str> = code.split(',').collect();
+        let mut column = 1;
+        for (i, raw) in tokens.iter().enumerate() {
+            let token = raw.trim();
+            let token_column = column + raw.chars().count() - raw.trim_start().chars().count();
+            column += raw.chars().count() + 1;
+
+            if token.is_empty() && ((i + 1 == tokens.len()) || (code.trim().is_empty())) {
+                continue;
+            }
+
+            match token.parse::<i128>() {
+                Ok(value) => program.push(value),
+                Err(_) => {
+                    return Err(LoadError::InvalidToken {
+                        path: None,
+                        line: l + 1,
+                        column: token_column,
+                        token: token.to_string(),
+                    });
+                }
+            }
+        }
+    }
+
+    if program.is_empty() {
+        return Err(LoadError::Empty { path: None });
+    }
+    Ok(program)
+}
+
+#[allow(dead_code)]
+pub fn read_program<R: Read>(mut reader: R) -> Result<Vec<i128>, LoadError> {
+    let mut source = String::new();
+    reader
+        .read_to_string(&mut source)
+        .map_err(|error| LoadError::Io { path: None, error })?;
+    parse_program(&source)
+}
+
+#[allow(dead_code)]
+pub fn load_program<P: AsRef<Path>>(filename: P) -> Result<Vec<i128>, LoadError> {
+    let path = filename.as_ref();
+    fs::File::open(path)
+        .map_err(|error| LoadError::Io { path: None, error })
+        .and_then(read_program)
+        .map_err(|e| e.with_path(path))
+}
+
+// Programs translated into Rust by build.rs (see translate.rs)
+#[allow(clippy::all, unused)]
+mod native {
+    include!(concat!(env!("OUT_DIR"), "/native.rs"));
+}
+
+// The programs build.rs translated, along with the samples in the tests
+#[cfg(not(test))]
+fn translated() -> impl Iterator<Item = &'static (&'static [i128], NativeProgram)> {
+    native::PROGRAMS.iter()
+}
 
-    line[0]
-        .split(',')
-        .map(|line| line.parse::<i128>().unwrap())
-        .collect()
+#[cfg(test)]
+fn translated() -> impl Iterator<Item = &'static (&'static [i128], NativeProgram)> {
+    native::PROGRAMS.iter().chain(native::samples::SAMPLES)
 }
 
+// What a translated program does when it stops running. Interpret means it
+// has reached something it can't handle, and the interpreter should carry on
+// from the current state. Only translated programs make these, and outside
+// the tests there are none unless the puzzle inputs are there to translate.
+#[allow(dead_code)]
+pub enum NativeStep {
+    Output(i128),
+    WaitForInput,
+    Terminated,
+    Interpret,
+}
+
+pub type NativeProgram = fn(&mut Vec<i128>, &mut usize, &mut i128, &mut Vec<i128>) -> NativeStep;
+
 #[derive(Clone)]
 pub struct VirtualMachine {
     ip: usize,
     memory: Vec<i128>,
     input: Vec<i128>,
     relative_base: i128,
+    native: Option<NativeProgram>,
 }
 
-#[derive(PartialEq)]
+#[derive(PartialEq, Debug)]
 pub enum VirtualMachineState {
     Output(i128),
     WaitForInput,
@@ -29,18 +195,22 @@ pub enum VirtualMachineState {
 }
 
 impl VirtualMachine {
-    pub fn new(program: &Vec<i128>) -> VirtualMachine {
+    pub fn new(program: &[i128]) -> VirtualMachine {
         VirtualMachine {
             ip: 0,
-            memory: program.clone(),
+            memory: program.to_vec(),
             input: Vec::new(),
             relative_base: 0,
+            native: translated().find(|(p, _)| *p == program).map(|(_, n)| *n),
         }
     }
     pub fn add_input(&mut self, input: i128) {
         self.input.push(input);
     }
     pub fn set_memory(&mut self, location: usize, value: i128) {
+        // The translated program can't know about patches to its code
+        self.native = None;
+
         if location >= self.memory.len() {
             self.memory.resize(location + 1, 0);
         }
@@ -58,21 +228,14 @@ impl VirtualMachine {
         let p = self.get_memory(self.ip + i);
 
         // Any missing modes are 0
-        let m;
-        match i {
-            1 => {
-                m = (op / 100) % 10;
-            }
-            2 => {
-                m = (op / 1000) % 10;
-            }
-            3 => {
-                m = (op / 10000) % 10;
-            }
+        let m = match i {
+            1 => (op / 100) % 10,
+            2 => (op / 1000) % 10,
+            3 => (op / 10000) % 10,
             _ => {
                 panic!("Invalid parameter");
             }
-        }
+        };
 
         let ret;
         match m {
@@ -128,6 +291,20 @@ impl VirtualMachine {
         ret
     }
     pub fn run(&mut self) -> VirtualMachineState {
+        if let Some(native) = self.native {
+            match native(
+                &mut self.memory,
+                &mut self.ip,
+                &mut self.relative_base,
+                &mut self.input,
+            ) {
+                NativeStep::Output(v) => return VirtualMachineState::Output(v),
+                NativeStep::WaitForInput => return VirtualMachineState::WaitForInput,
+                NativeStep::Terminated => return VirtualMachineState::Terminated,
+                NativeStep::Interpret => self.native = None,
+            }
+        }
+
         let mut ret: Option<VirtualMachineState> = None;
         while ret.is_none() {
             let op = (self.get_memory(self.ip) as u32) % 100;
@@ -260,3 +437,354 @@ impl VirtualMachine {
         ret.unwrap()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Run a program to completion, feeding it the given inputs up front.
+    fn run_to_end(program: &[i128], inputs: &[i128]) -> (Vec<i128>, VirtualMachine) {
+        let mut vm = VirtualMachine::new(program);
+        for i in inputs {
+            vm.add_input(*i);
+        }
+
+        let mut outputs: Vec<i128> = Vec::new();
+        loop {
+            match vm.run() {
+                VirtualMachineState::Output(v) => outputs.push(v),
+                VirtualMachineState::WaitForInput => panic!("Unexpected request for input"),
+                VirtualMachineState::Terminated => break,
+            }
+        }
+        (outputs, vm)
+    }
+
+    fn memory(vm: &VirtualMachine, len: usize) -> Vec<i128> {
+        (0..len).map(|i| vm.get_memory(i)).collect()
+    }
+
+    #[test]
+    fn day02_add_and_multiply() {
+        let cases: Vec<(Vec<i128>, Vec<i128>)> = vec![
+            (
+                vec![1, 9, 10, 3, 2, 3, 11, 0, 99, 30, 40, 50],
+                vec![3500, 9, 10, 70, 2, 3, 11, 0, 99, 30, 40, 50],
+            ),
+            (vec![1, 0, 0, 0, 99], vec![2, 0, 0, 0, 99]),
+            (vec![2, 3, 0, 3, 99], vec![2, 3, 0, 6, 99]),
+            (vec![2, 4, 4, 5, 99, 0], vec![2, 4, 4, 5, 99, 9801]),
+            (
+                vec![1, 1, 1, 4, 99, 5, 6, 0, 99],
+                vec![30, 1, 1, 4, 2, 5, 6, 0, 99],
+            ),
+        ];
+        for (program, expected) in cases {
+            let (outputs, vm) = run_to_end(&program, &[]);
+            assert!(outputs.is_empty());
+            assert_eq!(memory(&vm, expected.len()), expected);
+        }
+    }
+
+    #[test]
+    fn day05_input_and_output() {
+        let (outputs, _) = run_to_end(&[3, 0, 4, 0, 99], &[1234]);
+        assert_eq!(outputs, vec![1234]);
+    }
+
+    #[test]
+    fn day05_parameter_modes() {
+        let (_, vm) = run_to_end(&[1002, 4, 3, 4, 33], &[]);
+        assert_eq!(vm.get_memory(4), 99);
+
+        // Integers can be negative
+        let (_, vm) = run_to_end(&[1101, 100, -1, 4, 0], &[]);
+        assert_eq!(vm.get_memory(4), 99);
+    }
+
+    #[test]
+    fn day05_compare_to_8() {
+        // (program, input) -> output
+        let equal_position = vec![3, 9, 8, 9, 10, 9, 4, 9, 99, -1, 8];
+        let less_position = vec![3, 9, 7, 9, 10, 9, 4, 9, 99, -1, 8];
+        let equal_immediate = vec![3, 3, 1108, -1, 8, 3, 4, 3, 99];
+        let less_immediate = vec![3, 3, 1107, -1, 8, 3, 4, 3, 99];
+
+        for input in 6..=10 {
+            let equal = if input == 8 { 1 } else { 0 };
+            let less = if input < 8 { 1 } else { 0 };
+
+            assert_eq!(run_to_end(&equal_position, &[input]).0, vec![equal]);
+            assert_eq!(run_to_end(&less_position, &[input]).0, vec![less]);
+            assert_eq!(run_to_end(&equal_immediate, &[input]).0, vec![equal]);
+            assert_eq!(run_to_end(&less_immediate, &[input]).0, vec![less]);
+        }
+    }
+
+    #[test]
+    fn day05_jumps() {
+        // Output 0 if the input was zero or 1 if the input was non-zero
+        let jump_position = vec![3, 12, 6, 12, 15, 1, 13, 14, 13, 4, 13, 99, -1, 0, 1, 9];
+        let jump_immediate = vec![3, 3, 1105, -1, 9, 1101, 0, 0, 12, 4, 12, 99, 1];
+
+        for (input, expected) in [(0, 0), (1, 1), (-5, 1)] {
+            assert_eq!(run_to_end(&jump_position, &[input]).0, vec![expected]);
+            assert_eq!(run_to_end(&jump_immediate, &[input]).0, vec![expected]);
+        }
+    }
+
+    #[test]
+    fn day05_larger_compare_to_8() {
+        // Output 999 if the input is below 8, 1000 if it's equal to 8, or
+        // 1001 if it's greater than 8.
+        let program = vec![
+            3, 21, 1008, 21, 8, 20, 1005, 20, 22, 107, 8, 21, 20, 1006, 20, 31, 1106, 0, 36, 98, 0,
+            0, 1002, 21, 125, 20, 4, 20, 1105, 1, 46, 104, 999, 1105, 1, 46, 1101, 1000, 1, 20, 4,
+            20, 1105, 1, 46, 98, 99,
+        ];
+        for (input, expected) in [(-3, 999), (7, 999), (8, 1000), (9, 1001), (100, 1001)] {
+            assert_eq!(run_to_end(&program, &[input]).0, vec![expected]);
+        }
+    }
+
+    #[test]
+    fn day09_quine() {
+        let program = vec![
+            109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99,
+        ];
+        assert_eq!(run_to_end(&program, &[]).0, program);
+    }
+
+    #[test]
+    fn day09_large_numbers() {
+        let (outputs, _) = run_to_end(&[1102, 34915192, 34915192, 7, 4, 7, 99, 0], &[]);
+        assert_eq!(outputs.len(), 1);
+        assert_eq!(outputs[0].to_string().len(), 16);
+
+        let (outputs, _) = run_to_end(&[104, 1125899906842624, 99], &[]);
+        assert_eq!(outputs, vec![1125899906842624]);
+    }
+
+    #[test]
+    fn relative_mode_reads_and_writes() {
+        // Move the relative base to 100, add 3 and 4 into it, and output it
+        let (outputs, vm) = run_to_end(&[109, 100, 21101, 3, 4, 0, 204, 0, 99], &[]);
+        assert_eq!(outputs, vec![7]);
+        assert_eq!(vm.get_memory(100), 7);
+
+        // Relative base adjustments accumulate and can be negative
+        let (outputs, vm) = run_to_end(&[109, 60, 109, -10, 203, -3, 204, -3, 99], &[42]);
+        assert_eq!(outputs, vec![42]);
+        assert_eq!(vm.get_memory(47), 42);
+
+        // Every instruction that writes supports relative mode
+        let program = vec![
+            109, 50, // rb = 50
+            21102, 6, 7, 0, // [50] = 6 * 7
+            21107, 1, 2, 1, // [51] = 1 < 2
+            21108, 5, 5, 2, // [52] = 5 == 5
+            22201, 0, 1, 3, // [53] = [50] + [51]
+            99,
+        ];
+        let (_, vm) = run_to_end(&program, &[]);
+        assert_eq!(memory(&vm, 54)[50..], [42, 1, 1, 43]);
+    }
+
+    #[test]
+    fn memory_beyond_the_program() {
+        // Reads past the end of the program are 0, and writes grow memory
+        let (outputs, vm) = run_to_end(&[4, 1000, 1101, 2, 3, 2000, 4, 2000, 99], &[]);
+        assert_eq!(outputs, vec![0, 5]);
+        assert_eq!(vm.get_memory(2000), 5);
+        assert_eq!(vm.get_memory(1999), 0);
+    }
+
+    #[test]
+    fn jumps_with_every_mode() {
+        // jump-if-true via a relative target, jump-if-false via a position target
+        let program = vec![
+            109, 20, // rb = 20
+            2105, 1, 1, // if 1 != 0 jump to [rb + 1] (= 7)
+            104, -1, // skipped
+            6, 22, 23, // if [22] == 0 jump to [23] (= 12)
+            104, -2, // skipped
+            104, 1, 99, 0, 0, 0, 0, 0, 0, 7, 0, 12,
+        ];
+        assert_eq!(run_to_end(&program, &[]).0, vec![1]);
+    }
+
+    #[test]
+    fn blocks_until_input_arrives() {
+        let mut vm = VirtualMachine::new(&[3, 11, 3, 12, 1, 11, 12, 13, 4, 13, 99, 0, 0, 0]);
+
+        // Waiting doesn't consume the instruction, so asking again is harmless
+        assert_eq!(vm.run(), VirtualMachineState::WaitForInput);
+        assert_eq!(vm.run(), VirtualMachineState::WaitForInput);
+
+        vm.add_input(30);
+        assert_eq!(vm.run(), VirtualMachineState::WaitForInput);
+
+        vm.add_input(12);
+        assert_eq!(vm.run(), VirtualMachineState::Output(42));
+        assert_eq!(vm.run(), VirtualMachineState::Terminated);
+    }
+
+    #[test]
+    fn inputs_are_consumed_in_order() {
+        let program = vec![3, 0, 4, 0, 3, 0, 4, 0, 3, 0, 4, 0, 99];
+        assert_eq!(run_to_end(&program, &[3, 1, 2]).0, vec![3, 1, 2]);
+    }
+
+    #[test]
+    fn resumes_after_each_output() {
+        // Echo inputs forever, like the network cards and droids do
+        let mut vm = VirtualMachine::new(&[3, 100, 4, 100, 1105, 1, 0]);
+        for i in 0..5 {
+            assert_eq!(vm.run(), VirtualMachineState::WaitForInput);
+            vm.add_input(i);
+            assert_eq!(vm.run(), VirtualMachineState::Output(i));
+        }
+
+        // A clone carries on from the same point independently
+        let mut clone = vm.clone();
+        clone.add_input(7);
+        vm.add_input(8);
+        assert_eq!(clone.run(), VirtualMachineState::Output(7));
+        assert_eq!(vm.run(), VirtualMachineState::Output(8));
+    }
+
+    #[test]
+    fn terminates_on_halt() {
+        let mut vm = VirtualMachine::new(&[99]);
+        assert_eq!(vm.run(), VirtualMachineState::Terminated);
+    }
+
+    #[test]
+    #[should_panic(expected = "Unknown opcode")]
+    fn unknown_opcode() {
+        VirtualMachine::new(&[42]).run();
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid mode")]
+    fn unknown_mode() {
+        VirtualMachine::new(&[301, 0, 0, 0, 99]).run();
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid rw mode")]
+    fn immediate_mode_write() {
+        VirtualMachine::new(&[11101, 1, 1, 0, 99]).run();
+    }
+
+    // Run the program on the native and interpreted engines side by side,
+    // feeding both the same inputs, and check they agree at every step.
+    fn compare_native(program: &[i128]) {
+        let mut native = VirtualMachine::new(program);
+        assert!(native.native.is_some());
+
+        let mut interpreted = VirtualMachine::new(program);
+        interpreted.native = None;
+
+        let mut inputs = (0..20).map(|i| (i * 7) % 11 - 2);
+        for _ in 0..200 {
+            let state = interpreted.run();
+            assert_eq!(native.run(), state);
+            match state {
+                VirtualMachineState::WaitForInput => match inputs.next() {
+                    Some(i) => {
+                        native.add_input(i);
+                        interpreted.add_input(i);
+                    }
+                    None => break,
+                },
+                VirtualMachineState::Terminated => break,
+                VirtualMachineState::Output(_) => {}
+            }
+        }
+
+        let len = native.memory.len().max(interpreted.memory.len());
+        assert_eq!(memory(&native, len), memory(&interpreted, len));
+        assert_eq!(native.relative_base, interpreted.relative_base);
+    }
+
+    #[test]
+    fn native_programs_match_the_interpreter() {
+        assert!(!native::samples::SAMPLES.is_empty());
+        for (program, _) in translated() {
+            compare_native(program);
+        }
+    }
+
+    #[test]
+    fn native_programs_hand_back_to_the_interpreter() {
+        // A relative write into code turns the second output into a halt
+        let program = vec![109, 0, 21101, 0, 99, 8, 104, 7, 104, 5, 99];
+        let (outputs, vm) = run_to_end(&program, &[]);
+        assert_eq!(outputs, vec![7]);
+        assert!(vm.native.is_none());
+
+        // Patching the program switches to the interpreter up front
+        let mut vm = VirtualMachine::new(&[3, 100, 4, 100, 1105, 1, 0]);
+        assert!(vm.native.is_some());
+        vm.set_memory(0, 104);
+        assert!(vm.native.is_none());
+        assert_eq!(vm.run(), VirtualMachineState::Output(100));
+    }
+
+    #[test]
+    fn parse_program_accepts_comments_and_lines() {
+        let source = "# add two numbers\n1,5,6,0,\n99, 2, 3 # data\n\n";
+        assert_eq!(parse_program(source).unwrap(), vec![1, 5, 6, 0, 99, 2, 3]);
+    }
+
+    #[test]
+    fn read_program_loads_from_a_reader() {
+        assert_eq!(
+            read_program("104,-1,99\n".as_bytes()).unwrap(),
+            vec![104, -1, 99]
+        );
+        assert!(matches!(
+            read_program(&[0xff, 0xfe][..]),
+            Err(LoadError::Io { path: None, .. })
+        ));
+    }
+
+    #[test]
+    fn load_program_reports_the_path() {
+        let missing = std::env::temp_dir().join("no-such-intcode-program.txt");
+        let error = load_program(&missing).unwrap_err();
+        assert!(matches!(error, LoadError::Io { path: Some(_), .. }));
+        assert!(error
+            .to_string()
+            .starts_with(&format!("{}: can't read program", missing.display())));
+
+        let bad = std::env::temp_dir().join(format!("intcode-{}.txt", std::process::id()));
+        fs::write(&bad, "1,2,3\n4, five,6\n").unwrap();
+        let error = load_program(&bad).unwrap_err();
+        fs::remove_file(&bad).unwrap();
+        assert_eq!(
+            error.to_string(),
+            format!("{}:2:4: invalid token 'five'", bad.display())
+        );
+    }
+
+    #[test]
+    fn parse_program_reports_bad_tokens() {
+        match parse_program("1,2,3\n4, five,6") {
+            Err(LoadError::InvalidToken {
+                line,
+                column,
+                token,
+                ..
+            }) => {
+                assert_eq!((line, column, token.as_str()), (2, 4, "five"));
+            }
+            _ => panic!("Expected an invalid token"),
+        }
+        assert!(matches!(
+            parse_program("# nothing\n"),
+            Err(LoadError::Empty { .. })
+        ));
+    }
+}
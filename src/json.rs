@@ -0,0 +1,99 @@
+use std::fmt::Write;
+use std::time::Duration;
+
+use crate::solution::{Answer, Solved};
+
+// Quote a string for JSON
+fn string(s: &str) -> String {
+    let mut quoted = String::from("\"");
+    for ch in s.chars() {
+        match ch {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            ch if (ch as u32) < 0x20 => write!(quoted, "\\u{:04x}", ch as u32).unwrap(),
+            ch => quoted.push(ch),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn milliseconds(d: Duration) -> String {
+    format!("{:.3}", d.as_secs_f64() * 1000.0)
+}
+
+// The answer as a JSON value, and what kind of answer it is
+fn answer(answer: &Answer) -> (String, &'static str) {
+    match answer {
+        Answer::Number(n) => (n.to_string(), "number"),
+        Answer::Text(s) => (string(s), "string"),
+        Answer::Rendered(s) => (string(s), "rendered"),
+        Answer::None => ("null".to_string(), "none"),
+    }
+}
+
+// One line of JSON for each part that was solved
+pub fn solved(day: usize, solved: &Solved) -> Vec<String> {
+    solved
+        .parts
+        .iter()
+        .map(|(part, a, elapsed)| {
+            let (value, kind) = answer(a);
+            format!(
+                "{{\"day\":{},\"part\":{},\"answer\":{},\"type\":\"{}\",\"parse_ms\":{},\"elapsed_ms\":{},\"error\":null}}",
+                day,
+                part,
+                value,
+                kind,
+                milliseconds(solved.parse),
+                milliseconds(*elapsed)
+            )
+        })
+        .collect()
+}
+
+// A line of JSON for a day that couldn't be solved
+pub fn error(day: usize, error: &str) -> String {
+    format!(
+        "{{\"day\":{},\"part\":null,\"answer\":null,\"type\":null,\"parse_ms\":null,\"elapsed_ms\":null,\"error\":{}}}",
+        day,
+        string(error)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quotes_strings() {
+        assert_eq!(string("CJZHR"), "\"CJZHR\"");
+        assert_eq!(string("a \"b\"\\"), "\"a \\\"b\\\"\\\\\"");
+        assert_eq!(string("x \n x\u{1}"), "\"x \\n x\\u0001\"");
+    }
+
+    #[test]
+    fn one_object_per_part() {
+        let day = Solved {
+            parse: Duration::from_micros(1500),
+            parts: vec![
+                (1, Answer::Number(42), Duration::from_millis(2)),
+                (2, Answer::Rendered("#.\n.#\n".to_string()), Duration::ZERO),
+            ],
+        };
+        assert_eq!(
+            solved(8, &day),
+            vec![
+                "{\"day\":8,\"part\":1,\"answer\":42,\"type\":\"number\",\"parse_ms\":1.500,\"elapsed_ms\":2.000,\"error\":null}",
+                "{\"day\":8,\"part\":2,\"answer\":\"#.\\n.#\\n\",\"type\":\"rendered\",\"parse_ms\":1.500,\"elapsed_ms\":0.000,\"error\":null}",
+            ]
+        );
+        assert_eq!(
+            error(2, "can't read data/day02.txt"),
+            "{\"day\":2,\"part\":null,\"answer\":null,\"type\":null,\"parse_ms\":null,\"elapsed_ms\":null,\"error\":\"can't read data/day02.txt\"}"
+        );
+    }
+}
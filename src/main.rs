@@ -1,11 +1,828 @@
 #[macro_use]
 extern crate lazy_static;
 
+mod animation;
+mod answers;
+mod bench;
 mod intcode;
+mod json;
+mod manifest;
 
+#[cfg(test)]
+mod fuzz;
+
+// Used for real by build.rs
+#[cfg(test)]
+mod translate;
+
+mod combinatorics;
+mod cycle;
+mod geom;
+mod goalseek;
+mod grid;
+mod image;
+mod input;
+mod numtheory;
+mod ocr;
+mod parse;
+mod pool;
+mod search;
+mod solution;
+mod timing;
+
+mod day01;
+mod day02;
+mod day03;
+mod day04;
+mod day05;
+mod day06;
+mod day07;
+mod day08;
+mod day09;
+mod day10;
+mod day11;
+mod day12;
+mod day13;
+mod day14;
+mod day15;
+mod day16;
+mod day17;
+mod day18;
+mod day19;
+mod day20;
+mod day21;
+mod day22;
+mod day23;
+mod day24;
 mod day25;
-use day25::run;
+
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::process;
+use std::thread;
+
+use animation::{Animation, Frames};
+use answers::{Answers, Verdict};
+use image::Image;
+use input::Input;
+use manifest::{Entry, Manifest};
+use solution::{animate, draw, solve, Mode, Solved};
+
+type Solver = fn(&str, Option<u8>, Mode) -> Result<Solved, Box<dyn Error>>;
+type Drawer = fn(&str) -> Result<Vec<(String, Image)>, Box<dyn Error>>;
+type Animator = fn(&str, Frames) -> Result<(), Box<dyn Error>>;
+
+// How to solve a day's puzzle, draw it, and animate it
+struct Day {
+    solve: Solver,
+    draw: Drawer,
+    animate: Animator,
+}
+
+macro_rules! day {
+    ($solution:ty) => {
+        Day {
+            solve: solve::<$solution>,
+            draw: draw::<$solution>,
+            animate: animate::<$solution>,
+        }
+    };
+}
+
+// Every day's solution, in order
+const DAYS: [Day; 25] = [
+    day!(day01::Day01),
+    day!(day02::Day02),
+    day!(day03::Day03),
+    day!(day04::Day04),
+    day!(day05::Day05),
+    day!(day06::Day06),
+    day!(day07::Day07),
+    day!(day08::Day08),
+    day!(day09::Day09),
+    day!(day10::Day10),
+    day!(day11::Day11),
+    day!(day12::Day12),
+    day!(day13::Day13),
+    day!(day14::Day14),
+    day!(day15::Day15),
+    day!(day16::Day16),
+    day!(day17::Day17),
+    day!(day18::Day18),
+    day!(day19::Day19),
+    day!(day20::Day20),
+    day!(day21::Day21),
+    day!(day22::Day22),
+    day!(day23::Day23),
+    day!(day24::Day24),
+    day!(day25::Day25),
+];
+
+const USAGE: &str = "Usage: aoc <day|all> [--part <1|2>] [--input <file|->] [--jobs <n>]
+                       [--format <text|json>]
+       aoc <day> --interactive
+       aoc verify [day] [--answers <file>] [--jobs <n>]
+       aoc bench <day> [--part <1|2>] [--runs <n>] [--warmup <n>] [--baseline <file>]
+                       [--threshold <percent>] [--save]
+       aoc manifest [day]
+       aoc draw <day|all> [--out <dir>] [--scale <n>] [--ppm]
+       aoc watch <day> [--fps <n>] [--record <file>] [--headless]";
+
+// The manifest of the inputs, in the data directory
+const MANIFEST: &str = "manifest.txt";
+
+// Where the expected answers are recorded when no other file is given
+const DEFAULT_ANSWERS: &str = "answers.toml";
+
+// Where pictures are saved when no other directory is given
+const DEFAULT_PICTURES: &str = "pictures";
+
+// How answers are printed: for people, or one JSON object per line for scripts
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Format {
+    Text,
+    Json,
+}
+
+// Print the answers, check them against the recorded ones, time how long
+// they take to find, record what the inputs look like, or draw pictures of
+// the puzzles
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Command {
+    Run,
+    Verify,
+    Bench,
+    Manifest,
+    Draw,
+    Watch,
+}
+
+// Where pictures are saved, how big, and in what format
+struct Pictures {
+    out: String,
+    scale: usize,
+    format: image::Format,
+}
+
+// How fast to play an animation, where to record it, and whether to show it
+// at all
+struct Watching {
+    fps: u32,
+    record: Option<String>,
+    headless: bool,
+}
+
+// Which days to run, and optionally which part. Running all the days also
+// reports how long each took. Days run in parallel on up to jobs threads,
+// unless one is being played interactively or they're being timed.
+struct Options {
+    command: Command,
+    days: Vec<usize>,
+    all: bool,
+    part: Option<u8>,
+    input: Option<String>,
+    answers: Option<String>,
+    bench: bench::Settings,
+    pictures: Pictures,
+    watching: Watching,
+    jobs: usize,
+    mode: Mode,
+    format: Format,
+}
+
+// A count given on the command line, like the number of runs
+fn parse_count(flag: &str, value: Option<String>, min: usize) -> Result<usize, String> {
+    let value = value.ok_or(format!("{} needs a value", flag))?;
+    value
+        .parse::<usize>()
+        .ok()
+        .filter(|&n| n >= min)
+        .ok_or(format!(
+            "Invalid {} '{}', expected at least {}",
+            flag, value, min
+        ))
+}
+
+fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Options, String> {
+    let mut command = Command::Run;
+    let mut days: Option<Vec<usize>> = None;
+    let mut all = false;
+    let mut part: Option<u8> = None;
+    let mut input: Option<String> = None;
+    let mut answers: Option<String> = None;
+    let mut bench = bench::Settings::default();
+    let mut bench_flags = false;
+    let mut pictures = Pictures {
+        out: DEFAULT_PICTURES.to_string(),
+        scale: 8,
+        format: image::Format::Png,
+    };
+    let mut picture_flags = false;
+    let mut watching = Watching {
+        fps: 30,
+        record: None,
+        headless: false,
+    };
+    let mut watch_flags = false;
+    let mut jobs: Option<usize> = None;
+    let mut mode = Mode::Batch;
+    let mut format = Format::Text;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--part" | "-p" => {
+                let value = args.next().ok_or("--part needs a value")?;
+                part = match value.as_str() {
+                    "1" => Some(1),
+                    "2" => Some(2),
+                    _ => return Err(format!("Invalid part '{}', expected 1 or 2", value)),
+                };
+            }
+            "--input" | "-i" => {
+                input = Some(args.next().ok_or("--input needs a file, or - for stdin")?);
+            }
+            "--answers" | "-a" => {
+                answers = Some(args.next().ok_or("--answers needs a file")?);
+            }
+            "--jobs" | "-j" => {
+                jobs = Some(parse_count("--jobs", args.next(), 1)?);
+            }
+            "--runs" => {
+                bench.runs = parse_count("--runs", args.next(), 1)?;
+                bench_flags = true;
+            }
+            "--warmup" => {
+                bench.warmup = parse_count("--warmup", args.next(), 0)?;
+                bench_flags = true;
+            }
+            "--baseline" => {
+                bench.baseline = args.next().ok_or("--baseline needs a file")?;
+                bench_flags = true;
+            }
+            "--threshold" => {
+                let value = args.next().ok_or("--threshold needs a percentage")?;
+                bench.threshold =
+                    value
+                        .parse::<f64>()
+                        .ok()
+                        .filter(|t| *t >= 0.0)
+                        .ok_or(format!(
+                            "Invalid threshold '{}', expected a percentage",
+                            value
+                        ))?;
+                bench_flags = true;
+            }
+            "--save" => {
+                bench.save = true;
+                bench_flags = true;
+            }
+            "--out" | "-o" => {
+                pictures.out = args.next().ok_or("--out needs a directory")?;
+                picture_flags = true;
+            }
+            "--scale" => {
+                pictures.scale = parse_count("--scale", args.next(), 1)?;
+                picture_flags = true;
+            }
+            "--ppm" => {
+                pictures.format = image::Format::Ppm;
+                picture_flags = true;
+            }
+            "--fps" => {
+                watching.fps = parse_count("--fps", args.next(), 1)? as u32;
+                watch_flags = true;
+            }
+            "--record" => {
+                watching.record = Some(args.next().ok_or("--record needs a file")?);
+                watch_flags = true;
+            }
+            "--headless" => {
+                watching.headless = true;
+                watch_flags = true;
+            }
+            "--interactive" => {
+                mode = Mode::Interactive;
+            }
+            "--format" | "-f" => {
+                let value = args.next().ok_or("--format needs a value")?;
+                format = match value.as_str() {
+                    "text" => Format::Text,
+                    "json" => Format::Json,
+                    _ => return Err(format!("Invalid format '{}', expected text or json", value)),
+                };
+            }
+            "verify" if days.is_none() && command == Command::Run => {
+                command = Command::Verify;
+            }
+            "bench" if days.is_none() && command == Command::Run => {
+                command = Command::Bench;
+            }
+            "manifest" if days.is_none() && command == Command::Run => {
+                command = Command::Manifest;
+            }
+            "draw" if days.is_none() && command == Command::Run => {
+                command = Command::Draw;
+            }
+            "watch" if days.is_none() && command == Command::Run => {
+                command = Command::Watch;
+            }
+            "all" if days.is_none() => {
+                days = Some((1..=DAYS.len()).collect());
+                all = true;
+            }
+            _ if days.is_none() => {
+                let day = arg
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|day| (1..=DAYS.len()).contains(day))
+                    .ok_or(format!("Invalid day '{}', expected 1-25 or all", arg))?;
+                days = Some(vec![day]);
+            }
+            _ => return Err(format!("Unexpected argument '{}'", arg)),
+        }
+    }
+
+    let days = match days {
+        Some(days) => days,
+        None if command == Command::Verify || command == Command::Manifest => {
+            (1..=DAYS.len()).collect()
+        }
+        None => return Err("No day given".to_string()),
+    };
+    if input.is_some() && days.len() > 1 {
+        return Err("--input can only be used with a single day".to_string());
+    }
+    if input.is_some() && command == Command::Manifest {
+        return Err("manifest only records the inputs in the data directory".to_string());
+    }
+    if answers.is_some() && command != Command::Verify {
+        return Err("--answers can only be used with verify".to_string());
+    }
+    if bench_flags && command != Command::Bench {
+        return Err(
+            "--runs, --warmup, --baseline, --threshold and --save can only be used with bench"
+                .to_string(),
+        );
+    }
+    if picture_flags && command != Command::Draw {
+        return Err("--out, --scale and --ppm can only be used with draw".to_string());
+    }
+    if watch_flags && command != Command::Watch {
+        return Err("--fps, --record and --headless can only be used with watch".to_string());
+    }
+    if command == Command::Bench && days.len() > 1 {
+        return Err("bench needs a single day".to_string());
+    }
+    if command == Command::Watch && days.len() > 1 {
+        return Err("watch needs a single day".to_string());
+    }
+    if mode == Mode::Interactive && (days.len() > 1 || command != Command::Run) {
+        return Err("--interactive can only be used to run a single day".to_string());
+    }
+    if format == Format::Json && command != Command::Run {
+        return Err("--format can only be used when running days".to_string());
+    }
+    // The timings printed after running all the days would be skewed by the
+    // days competing for cores, so they're run one at a time unless asked
+    let timed = command == Command::Run && all && format == Format::Text;
+    let jobs = jobs.unwrap_or_else(|| {
+        if timed {
+            1
+        } else {
+            thread::available_parallelism().map_or(1, |n| n.get())
+        }
+    });
+
+    Ok(Options {
+        command,
+        days,
+        all,
+        part,
+        input,
+        answers,
+        bench,
+        pictures,
+        watching,
+        jobs,
+        mode,
+        format,
+    })
+}
+
+// Read the manifest of the inputs, if there is one
+fn load_manifest() -> Result<Manifest, String> {
+    let path = input::data_dir(env::var_os("AOC_DATA_DIR")).join(MANIFEST);
+    match fs::read_to_string(&path) {
+        Ok(source) => Manifest::parse(&source).map_err(|e| format!("{}: {}", path.display(), e)),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(Manifest::default()),
+        Err(error) => Err(format!("can't read {}: {}", path.display(), error)),
+    }
+}
+
+// Record what the inputs in the data directory look like now
+fn record_manifest(options: &Options) -> Result<bool, String> {
+    let mut manifest = load_manifest()?;
+    for &day in &options.days {
+        let input = Input::for_day(day, None, env::var_os("AOC_DATA_DIR"));
+        match input.read() {
+            Ok(source) => {
+                manifest.set(day, Entry::of(&source));
+                println!("Recorded {}", input);
+            }
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {}
+            Err(error) => return Err(format!("can't read {}: {}", input, error)),
+        }
+    }
+
+    let path = input::data_dir(env::var_os("AOC_DATA_DIR")).join(MANIFEST);
+    fs::write(&path, manifest.to_string())
+        .map_err(|error| format!("can't write {}: {}", path.display(), error))?;
+    println!("Saved the manifest to {}", path.display());
+    Ok(true)
+}
+
+// Read a day's input, checking it against the manifest
+fn read_input(
+    day: usize,
+    options: &Options,
+    manifest: &Manifest,
+) -> Result<(Input, String), String> {
+    let input = Input::for_day(day, options.input.as_deref(), env::var_os("AOC_DATA_DIR"));
+    let source = input
+        .read()
+        .map_err(|error| format!("can't read {}: {}", input, error))?;
+
+    // Only the inputs in the data directory are in the manifest
+    if let (None, Some(entry)) = (&options.input, manifest.get(day)) {
+        entry
+            .check(&source)
+            .map_err(|error| format!("{} looks wrong: it {}", input, error))?;
+    }
+    Ok((input, source))
+}
+
+fn run_day(day: usize, options: &Options, manifest: &Manifest) -> Result<Solved, String> {
+    let (input, source) = read_input(day, options, manifest)?;
+    (DAYS[day - 1].solve)(&source, options.part, options.mode)
+        .map_err(|error| format!("{}: {}", input, error))
+}
+
+fn run(options: &Options, manifest: &Manifest) {
+    let mut timings: Vec<(usize, Solved)> = Vec::new();
+    let solve = |&day: &usize| run_day(day, options, manifest);
+    pool::run_in_order(&options.days, options.jobs, solve, |&day, result| {
+        match (options.format, result) {
+            (Format::Text, Ok(solved)) => {
+                println!("Day {}", day);
+                for (part, answer, _) in &solved.parts {
+                    println!("Part {}: {}", part, answer);
+                }
+                timings.push((day, solved));
+            }
+            (Format::Text, Err(error)) => {
+                println!("Day {}", day);
+                eprintln!("Day {}: {}", day, error);
+            }
+            (Format::Json, Ok(solved)) => {
+                for line in json::solved(day, &solved) {
+                    println!("{}", line);
+                }
+            }
+            (Format::Json, Err(error)) => println!("{}", json::error(day, &error)),
+        }
+    });
+
+    if options.all && options.format == Format::Text {
+        print!("\n{}", timing::table(&timings));
+        if options.jobs > 1 {
+            println!(
+                "Timed with up to {} days running at once, so they were sharing the cores",
+                options.jobs
+            );
+        }
+    }
+}
+
+// Check every answer against the recorded ones, returning whether they all
+// passed
+fn verify(options: &Options, manifest: &Manifest) -> Result<bool, String> {
+    let filename = options.answers.as_deref().unwrap_or(DEFAULT_ANSWERS);
+    let recorded = fs::read_to_string(filename)
+        .map_err(|error| format!("can't read {}: {}", filename, error))
+        .and_then(|source| Answers::parse(&source).map_err(|e| format!("{}: {}", filename, e)))?;
+
+    let (mut passed, mut failed, mut missing) = (0, 0, 0);
+    let solve = |&day: &usize| run_day(day, options, manifest);
+    pool::run_in_order(
+        &options.days,
+        options.jobs,
+        solve,
+        |&day, result| match result {
+            Ok(solved) => {
+                for (part, answer, _) in solved.parts {
+                    let verdict = recorded.check(day, part, &answer);
+                    match verdict {
+                        Verdict::Pass => passed += 1,
+                        Verdict::Fail { .. } => failed += 1,
+                        Verdict::Missing { .. } => missing += 1,
+                    }
+                    println!("Day {} part {}: {}", day, part, verdict);
+                }
+            }
+            Err(error) => {
+                failed += 1;
+                println!("Day {}: ERROR {}", day, error);
+            }
+        },
+    );
+
+    println!("{} passed, {} failed, {} missing", passed, failed, missing);
+    Ok(failed == 0)
+}
+
+// Time a day's parsing and parts over many runs, and compare them to the
+// baseline, returning whether anything got slower
+fn bench(options: &Options, manifest: &Manifest) -> Result<bool, String> {
+    let settings = &options.bench;
+    let day = options.days[0];
+
+    let mut baseline = match fs::read_to_string(&settings.baseline) {
+        Ok(source) => bench::Baseline::parse(&source)
+            .map_err(|error| format!("{}: {}", settings.baseline, error))?,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => bench::Baseline::default(),
+        Err(error) => return Err(format!("can't read {}: {}", settings.baseline, error)),
+    };
+
+    println!(
+        "Day {}, {} runs after {} warm-up",
+        day, settings.runs, settings.warmup
+    );
+    let results = bench::measure(settings, || run_day(day, options, manifest))
+        .map_err(|error| format!("Day {}: {}", day, error))?;
+    let (table, slower) = bench::report(day, &results, &baseline, settings.threshold);
+    print!("{}", table);
+
+    // The first results for a day become its baseline
+    if settings.save || !baseline.contains_day(day) {
+        for (step, stats) in &results {
+            baseline.set(day, step, stats.median);
+        }
+        fs::write(&settings.baseline, baseline.to_string())
+            .map_err(|error| format!("can't write {}: {}", settings.baseline, error))?;
+        println!("Saved the baseline to {}", settings.baseline);
+    }
+
+    Ok(!slower)
+}
+
+// Save the pictures of each day as dayNN-<name>.png (or .ppm), returning
+// whether every day could be drawn
+fn draw_pictures(options: &Options, manifest: &Manifest) -> Result<bool, String> {
+    let settings = &options.pictures;
+    fs::create_dir_all(&settings.out)
+        .map_err(|error| format!("can't create {}: {}", settings.out, error))?;
+
+    let mut drawn = true;
+    let draw = |&day: &usize| {
+        let (input, source) = read_input(day, options, manifest)?;
+        (DAYS[day - 1].draw)(&source).map_err(|error| format!("{}: {}", input, error))
+    };
+    pool::run_in_order(&options.days, options.jobs, draw, |&day, result| {
+        let pictures = match result {
+            Ok(pictures) => pictures,
+            Err(error) => {
+                eprintln!("Day {}: {}", day, error);
+                drawn = false;
+                return;
+            }
+        };
+        if pictures.is_empty() && !options.all {
+            println!("Day {} has nothing to draw", day);
+        }
+        for (name, picture) in pictures {
+            let filename = format!("day{:02}-{}.{}", day, name, settings.format.extension());
+            let path = Path::new(&settings.out).join(filename);
+            let bytes = picture.scaled(settings.scale).encode(settings.format);
+            match fs::write(&path, bytes) {
+                Ok(()) => println!("Saved {}", path.display()),
+                Err(error) => {
+                    eprintln!("can't write {}: {}", path.display(), error);
+                    drawn = false;
+                }
+            }
+        }
+    });
+    Ok(drawn)
+}
+
+// Play a day's simulation on the terminal, and record it if asked to
+fn watch(options: &Options, manifest: &Manifest) -> Result<bool, String> {
+    let settings = &options.watching;
+    let day = options.days[0];
+    let (input, source) = read_input(day, options, manifest)?;
+
+    let screen: Option<Box<dyn io::Write>> = if settings.headless {
+        None
+    } else {
+        Some(Box::new(io::stdout()))
+    };
+    let mut animation = Animation::new(screen, settings.fps);
+    if let Some(filename) = &settings.record {
+        let file = fs::File::create(filename)
+            .map_err(|error| format!("can't create {}: {}", filename, error))?;
+        animation = animation.record(Box::new(io::BufWriter::new(file)));
+    }
+
+    (DAYS[day - 1].animate)(&source, &mut |draw| animation.frame(draw))
+        .map_err(|error| format!("{}: {}", input, error))?;
+    let frames = animation
+        .finish()
+        .map_err(|error| format!("can't show the animation: {}", error))?;
+
+    if frames == 0 {
+        println!("Day {} has nothing to watch", day);
+        return Ok(true);
+    }
+    println!("Day {}: {} frames", day, frames);
+    if let Some(filename) = &settings.record {
+        println!("Recorded {}", filename);
+    }
+    Ok(true)
+}
 
 fn main() {
-    run();
+    let options = parse_args(env::args().skip(1)).unwrap_or_else(|error| {
+        eprintln!("{}\n{}", error, USAGE);
+        process::exit(2);
+    });
+
+    let result = match options.command {
+        Command::Manifest => record_manifest(&options),
+        Command::Run => load_manifest().map(|manifest| {
+            run(&options, &manifest);
+            true
+        }),
+        Command::Verify => load_manifest().and_then(|manifest| verify(&options, &manifest)),
+        Command::Bench => load_manifest().and_then(|manifest| bench(&options, &manifest)),
+        Command::Draw => load_manifest().and_then(|manifest| draw_pictures(&options, &manifest)),
+        Command::Watch => load_manifest().and_then(|manifest| watch(&options, &manifest)),
+    };
+    match result {
+        Ok(true) => {}
+        Ok(false) => process::exit(1),
+        Err(error) => {
+            eprintln!("{}", error);
+            process::exit(2);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options, String> {
+        parse_args(args.iter().map(|s| s.to_string()))
+    }
+
+    #[test]
+    fn selects_a_single_day() {
+        let options = parse(&["17"]).unwrap();
+        assert_eq!(options.days, vec![17]);
+        assert!(!options.all);
+        assert_eq!(options.part, None);
+    }
+
+    #[test]
+    fn selects_a_part() {
+        let options = parse(&["3", "--part", "2"]).unwrap();
+        assert_eq!(options.days, vec![3]);
+        assert_eq!(options.part, Some(2));
+
+        let options = parse(&["-p", "1", "all"]).unwrap();
+        assert_eq!(options.days.len(), 25);
+        assert!(options.all);
+        assert_eq!(options.part, Some(1));
+    }
+
+    #[test]
+    fn selects_an_input() {
+        let options = parse(&["7", "--input", "amplifiers.txt"]).unwrap();
+        assert_eq!(options.input.as_deref(), Some("amplifiers.txt"));
+
+        let options = parse(&["-i", "-", "7"]).unwrap();
+        assert_eq!(options.input.as_deref(), Some("-"));
+    }
+
+    #[test]
+    fn verifies_every_day_by_default() {
+        let options = parse(&["verify"]).unwrap();
+        assert_eq!(options.command, Command::Verify);
+        assert_eq!(options.days.len(), 25);
+        assert_eq!(options.answers, None);
+
+        let options = parse(&["verify", "8", "--answers", "mine.toml"]).unwrap();
+        assert_eq!(options.days, vec![8]);
+        assert_eq!(options.answers.as_deref(), Some("mine.toml"));
+    }
+
+    #[test]
+    fn selects_jobs_and_mode() {
+        let options = parse(&["all", "--jobs", "3"]).unwrap();
+        assert_eq!(options.jobs, 3);
+        assert_eq!(options.mode, Mode::Batch);
+
+        let options = parse(&["all", "--format", "json"]).unwrap();
+        assert_eq!(options.format, Format::Json);
+        assert!(options.jobs >= 1);
+
+        // Timing all the days runs them one at a time
+        assert_eq!(parse(&["all"]).unwrap().jobs, 1);
+
+        let options = parse(&["25", "--interactive"]).unwrap();
+        assert_eq!(options.mode, Mode::Interactive);
+        assert!(options.jobs >= 1);
+    }
+
+    #[test]
+    fn benchmarks_a_single_day() {
+        let options = parse(&["bench", "18", "--runs", "5", "--warmup", "0", "--save"]).unwrap();
+        assert_eq!(options.command, Command::Bench);
+        assert_eq!(options.days, vec![18]);
+        assert_eq!((options.bench.runs, options.bench.warmup), (5, 0));
+        assert!(options.bench.save);
+
+        let options = parse(&["bench", "16", "--threshold", "25"]).unwrap();
+        assert_eq!(options.bench.threshold, 25.0);
+        assert!(!options.bench.save);
+    }
+
+    #[test]
+    fn records_every_input_by_default() {
+        let options = parse(&["manifest"]).unwrap();
+        assert_eq!(options.command, Command::Manifest);
+        assert_eq!(options.days.len(), 25);
+    }
+
+    #[test]
+    fn draws_pictures() {
+        let options = parse(&["draw", "11"]).unwrap();
+        assert_eq!(options.command, Command::Draw);
+        assert_eq!(options.days, vec![11]);
+        assert_eq!(options.pictures.out, DEFAULT_PICTURES);
+        assert_eq!(options.pictures.format, image::Format::Png);
+
+        let options = parse(&["draw", "all", "--out", "docs", "--scale", "4", "--ppm"]).unwrap();
+        assert_eq!(options.days.len(), 25);
+        assert_eq!(options.pictures.out, "docs");
+        assert_eq!(options.pictures.scale, 4);
+        assert_eq!(options.pictures.format, image::Format::Ppm);
+    }
+
+    #[test]
+    fn watches_a_single_day() {
+        let options = parse(&["watch", "13"]).unwrap();
+        assert_eq!(options.command, Command::Watch);
+        assert_eq!(options.watching.fps, 30);
+        assert!(!options.watching.headless);
+
+        let options = parse(&["watch", "24", "--headless", "--record", "bugs.txt"]).unwrap();
+        assert!(options.watching.headless);
+        assert_eq!(options.watching.record.as_deref(), Some("bugs.txt"));
+    }
+
+    #[test]
+    fn rejects_bad_arguments() {
+        assert!(parse(&[]).is_err());
+        assert!(parse(&["0"]).is_err());
+        assert!(parse(&["26"]).is_err());
+        assert!(parse(&["three"]).is_err());
+        assert!(parse(&["3", "4"]).is_err());
+        assert!(parse(&["3", "--part"]).is_err());
+        assert!(parse(&["3", "--part", "3"]).is_err());
+        assert!(parse(&["3", "--input"]).is_err());
+        assert!(parse(&["all", "--input", "-"]).is_err());
+        assert!(parse(&["3", "--answers", "answers.toml"]).is_err());
+        assert!(parse(&["verify", "verify"]).is_err());
+        assert!(parse(&["all", "--jobs", "0"]).is_err());
+        assert!(parse(&["all", "--interactive"]).is_err());
+        assert!(parse(&["all", "--format", "xml"]).is_err());
+        assert!(parse(&["verify", "--format", "json"]).is_err());
+        assert!(parse(&["verify", "25", "--interactive"]).is_err());
+        assert!(parse(&["bench"]).is_err());
+        assert!(parse(&["bench", "all"]).is_err());
+        assert!(parse(&["bench", "18", "--runs", "0"]).is_err());
+        assert!(parse(&["18", "--save"]).is_err());
+        assert!(parse(&["verify", "bench"]).is_err());
+        assert!(parse(&["manifest", "--input", "-"]).is_err());
+        assert!(parse(&["draw"]).is_err());
+        assert!(parse(&["draw", "13", "--scale", "0"]).is_err());
+        assert!(parse(&["13", "--ppm"]).is_err());
+        assert!(parse(&["watch", "all"]).is_err());
+        assert!(parse(&["watch", "15", "--fps", "0"]).is_err());
+        assert!(parse(&["draw", "15", "--headless"]).is_err());
+    }
 }
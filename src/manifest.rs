@@ -0,0 +1,232 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+// What an input file looks like: a single line of comma separated values (an
+// Intcode program), a rectangular grid, or lines of differing lengths
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Shape {
+    Values(usize),
+    Grid { width: usize, height: usize },
+    Lines,
+}
+
+impl Shape {
+    fn of(lines: &[&str]) -> Shape {
+        match lines {
+            [line] if line.contains(',') => Shape::Values(line.split(',').count()),
+            [first, _, ..] if lines.iter().all(|l| l.len() == first.len()) => Shape::Grid {
+                width: first.len(),
+                height: lines.len(),
+            },
+            _ => Shape::Lines,
+        }
+    }
+
+    fn parse(s: &str) -> Option<Shape> {
+        if s == "lines" {
+            return Some(Shape::Lines);
+        }
+        if let Some(count) = s.strip_prefix("values:") {
+            return count.parse().ok().map(Shape::Values);
+        }
+        let (width, height) = s.strip_prefix("grid:")?.split_once('x')?;
+        Some(Shape::Grid {
+            width: width.parse().ok()?,
+            height: height.parse().ok()?,
+        })
+    }
+}
+
+impl fmt::Display for Shape {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Shape::Values(count) => write!(f, "values:{}", count),
+            Shape::Grid { width, height } => write!(f, "grid:{}x{}", width, height),
+            Shape::Lines => write!(f, "lines"),
+        }
+    }
+}
+
+// 64 bit FNV-1a, which is plenty to notice a changed file
+fn checksum(lines: &[&str]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for (i, line) in lines.iter().enumerate() {
+        let separator: &[u8] = if i == 0 { b"" } else { b"\n" };
+        for byte in separator.iter().chain(line.as_bytes()) {
+            hash ^= *byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    }
+    hash
+}
+
+// What an input file looked like when it was recorded. Line endings and the
+// final newline don't count, so copying between machines doesn't matter.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Entry {
+    checksum: u64,
+    lines: usize,
+    shape: Shape,
+}
+
+impl Entry {
+    pub fn of(source: &str) -> Entry {
+        let lines: Vec<&str> = source.lines().collect();
+        Entry {
+            checksum: checksum(&lines),
+            lines: lines.len(),
+            shape: Shape::of(&lines),
+        }
+    }
+
+    // Explain how an input differs from this one, most obvious problem first
+    pub fn check(&self, source: &str) -> Result<(), String> {
+        let actual = Entry::of(source);
+        if actual.lines != self.lines {
+            return Err(format!(
+                "has {} lines, expected {}",
+                actual.lines, self.lines
+            ));
+        }
+        if actual.shape != self.shape {
+            return Err(format!(
+                "has shape {}, expected {}",
+                actual.shape, self.shape
+            ));
+        }
+        if actual.checksum != self.checksum {
+            return Err(format!(
+                "has checksum {:016x}, expected {:016x}",
+                actual.checksum, self.checksum
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn parse_entry(fields: &[&str]) -> Option<(usize, Entry)> {
+    let (day, checksum, lines, shape) = match fields {
+        [day, checksum, lines, shape] => (day, checksum, lines, shape),
+        _ => return None,
+    };
+    let day = day.strip_prefix("day")?.parse::<usize>().ok()?;
+    let checksum = u64::from_str_radix(checksum.strip_prefix("checksum=")?, 16).ok()?;
+    let lines = lines.strip_prefix("lines=")?.parse::<usize>().ok()?;
+    let shape = Shape::parse(shape.strip_prefix("shape=")?)?;
+    Some((
+        day,
+        Entry {
+            checksum,
+            lines,
+            shape,
+        },
+    ))
+}
+
+// The recorded inputs, one day per line:
+//
+//   day07 checksum=3c9f1e0b5a2d4e61 lines=1 shape=values:519
+#[derive(Default, Debug)]
+pub struct Manifest {
+    entries: BTreeMap<usize, Entry>,
+}
+
+impl Manifest {
+    pub fn parse(source: &str) -> Result<Manifest, String> {
+        let mut manifest = Manifest::default();
+        for (l, line) in source.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let entry = parse_entry(&fields);
+            let (day, entry) = entry.ok_or(format!(
+                "line {}: expected 'dayNN checksum=<hex> lines=<n> shape=<shape>'",
+                l + 1
+            ))?;
+            manifest.entries.insert(day, entry);
+        }
+        Ok(manifest)
+    }
+
+    pub fn get(&self, day: usize) -> Option<&Entry> {
+        self.entries.get(&day)
+    }
+
+    pub fn set(&mut self, day: usize, entry: Entry) {
+        self.entries.insert(day, entry);
+    }
+}
+
+impl fmt::Display for Manifest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (day, entry) in &self.entries {
+            writeln!(
+                f,
+                "day{:02} checksum={:016x} lines={} shape={}",
+                day, entry.checksum, entry.lines, entry.shape
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn works_out_shapes() {
+        assert_eq!(Entry::of("1,0,0,3,99\n").shape, Shape::Values(5));
+        assert_eq!(
+            Entry::of("#..\n.#.\n..#\n#..\n").shape,
+            Shape::Grid {
+                width: 3,
+                height: 4
+            }
+        );
+        assert_eq!(Entry::of("COM)B\nB)C\nC)DE\n").shape, Shape::Lines);
+    }
+
+    #[test]
+    fn line_endings_dont_matter() {
+        assert_eq!(Entry::of("12\n14\n"), Entry::of("12\r\n14"));
+        assert_ne!(Entry::of("12\n14\n"), Entry::of("12\n15\n"));
+    }
+
+    #[test]
+    fn explains_what_changed() {
+        let entry = Entry::of("#..\n.#.\n..#\n");
+        assert_eq!(entry.check("#..\n.#.\n..#\n"), Ok(()));
+        assert_eq!(
+            entry.check("#..\n.#.\n"),
+            Err("has 2 lines, expected 3".to_string())
+        );
+        assert_eq!(
+            entry.check("#..\n.#.\n..\n"),
+            Err("has shape lines, expected grid:3x3".to_string())
+        );
+        assert!(entry
+            .check("#..\n.#.\n.#.\n")
+            .unwrap_err()
+            .contains("checksum"));
+    }
+
+    #[test]
+    fn reads_what_it_writes() {
+        let mut manifest = Manifest::default();
+        manifest.set(2, Entry::of("1,0,0,3,99"));
+        manifest.set(24, Entry::of("....#\n#..#.\n"));
+
+        let written = manifest.to_string();
+        assert!(written.starts_with("day02 checksum="));
+
+        let read = Manifest::parse(&written).unwrap();
+        assert_eq!(read.get(2), manifest.get(2));
+        assert_eq!(read.get(24), manifest.get(24));
+        assert_eq!(read.get(3), None);
+        assert!(Manifest::parse("day02 checksum=xyz lines=1 shape=lines").is_err());
+    }
+}
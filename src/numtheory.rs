@@ -0,0 +1,144 @@
+// Greatest common divisor, by Euclid's algorithm. Never negative.
+pub fn gcd(a: i64, b: i64) -> i64 {
+    let (mut a, mut b) = (a.abs(), b.abs());
+    while b != 0 {
+        let r = a % b;
+        a = b;
+        b = r;
+    }
+    a
+}
+
+pub fn lcm(a: i64, b: i64) -> i64 {
+    if a == 0 || b == 0 {
+        0
+    } else {
+        (a / gcd(a, b) * b).abs()
+    }
+}
+
+// The extended Euclidean algorithm: (g, x, y) where g is the gcd of a and b,
+// and a * x + b * y = g
+pub fn egcd(a: i64, b: i64) -> (i64, i64, i64) {
+    let (mut r0, mut r1) = (a, b);
+    let (mut x0, mut x1) = (1, 0);
+    let (mut y0, mut y1) = (0, 1);
+    while r1 != 0 {
+        let q = r0.div_euclid(r1);
+        (r0, r1) = (r1, r0 - q * r1);
+        (x0, x1) = (x1, x0 - q * x1);
+        (y0, y1) = (y1, y0 - q * y1);
+    }
+    if r0 < 0 {
+        (-r0, -x0, -y0)
+    } else {
+        (r0, x0, y0)
+    }
+}
+
+// a * b mod m, without overflowing even when m is close to 2^63. The result is
+// in 0..m.
+pub fn mulmod(a: i64, b: i64, m: i64) -> i64 {
+    (a as i128 * b as i128).rem_euclid(m as i128) as i64
+}
+
+// base^exp mod m, by repeated squaring
+pub fn modpow(base: i64, exp: u64, m: i64) -> i64 {
+    let (mut base, mut exp) = (base.rem_euclid(m), exp);
+    let mut result = 1 % m;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = mulmod(result, base, m);
+        }
+        base = mulmod(base, base, m);
+        exp >>= 1;
+    }
+    result
+}
+
+// The x in 0..m with a * x = 1 mod m, if a and m are coprime
+pub fn modinv(a: i64, m: i64) -> Option<i64> {
+    let (g, x, _) = egcd(a.rem_euclid(m), m);
+    if g == 1 {
+        Some(x.rem_euclid(m))
+    } else {
+        None
+    }
+}
+
+// The Chinese Remainder Theorem: the x that is each residue modulo its
+// modulus, as (x, lcm of the moduli). The moduli don't need to be coprime,
+// but None is returned if the congruences contradict each other.
+#[allow(dead_code)]
+pub fn crt(congruences: &[(i64, i64)]) -> Option<(i64, i64)> {
+    let mut solution = (0, 1);
+    for &(residue, modulus) in congruences {
+        let (x, m) = solution;
+        let (g, p, _) = egcd(m, modulus);
+        let difference = residue - x;
+        if difference % g != 0 {
+            return None;
+        }
+
+        // x + m * k is the answer, where m * k = difference mod modulus
+        let step = modulus / g;
+        let k = mulmod(difference / g, p, step);
+        let combined = lcm(m, modulus);
+        solution = (
+            (x as i128 + m as i128 * k as i128).rem_euclid(combined as i128) as i64,
+            combined,
+        );
+    }
+    Some(solution)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn divisors_and_multiples() {
+        assert_eq!(gcd(48, 18), 6);
+        assert_eq!(gcd(-48, 18), 6);
+        assert_eq!(gcd(0, 7), 7);
+        assert_eq!(lcm(4, 6), 12);
+        assert_eq!(lcm(18, 28), 252);
+        assert_eq!([18, 28, 44].iter().fold(1, |l, &n| lcm(l, n)), 2772);
+
+        for &(a, b) in [(240, 46), (46, 240), (-7, 3), (17, 0)].iter() {
+            let (g, x, y) = egcd(a, b);
+            assert_eq!(g, gcd(a, b));
+            assert_eq!(a * x + b * y, g);
+        }
+    }
+
+    #[test]
+    fn modular_arithmetic() {
+        let big = i64::MAX - 24; // the largest prime below 2^63
+        assert_eq!(mulmod(big - 1, big - 1, big), 1);
+        assert_eq!(mulmod(-3, 4, 10), 8);
+
+        assert_eq!(modpow(4, 13, 497), 445);
+        assert_eq!(modpow(2, 0, 1), 0);
+        assert_eq!(modpow(big - 1, 3, big), big - 1);
+
+        assert_eq!(modinv(3, 11), Some(4));
+        assert_eq!(modinv(-3, 11), Some(7));
+        assert_eq!(modinv(6, 9), None);
+        let deck = 119315717514047;
+        let inverse = modinv(2020, deck).unwrap();
+        assert_eq!(mulmod(2020, inverse, deck), 1);
+    }
+
+    #[test]
+    fn chinese_remainders() {
+        assert_eq!(crt(&[(2, 3), (3, 5), (2, 7)]), Some((23, 105)));
+        assert_eq!(
+            crt(&[(0, 7), (12, 13), (55, 59), (25, 31), (12, 19)]),
+            Some((1068781, 3162341))
+        );
+        assert_eq!(crt(&[(1, 4), (3, 6)]), Some((9, 12)));
+        assert_eq!(crt(&[(1, 4), (2, 6)]), None);
+        assert_eq!(crt(&[]), Some((0, 1)));
+    }
+}
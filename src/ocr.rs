@@ -0,0 +1,160 @@
+use crate::geom::Point;
+use crate::grid::Grid;
+use crate::solution::Answer;
+
+// The capital letters the puzzles draw: 6 pixels tall, a letter every 5
+// columns, and all but Y 4 pixels wide so there's a gap between them
+const HEIGHT: usize = 6;
+const PITCH: usize = 5;
+
+const GLYPHS: [(char, [&str; HEIGHT]); 18] = [
+    ('A', [".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+    ('B', ["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+    ('C', [".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+    ('E', ["####", "#...", "###.", "#...", "#...", "####"]),
+    ('F', ["####", "#...", "###.", "#...", "#...", "#..."]),
+    ('G', [".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+    ('H', ["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+    ('I', [".###", "..#.", "..#.", "..#.", "..#.", ".###"]),
+    ('J', ["..##", "...#", "...#", "...#", "#..#", ".##."]),
+    ('K', ["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+    ('L', ["#...", "#...", "#...", "#...", "#...", "####"]),
+    ('O', [".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('P', ["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+    ('R', ["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+    ('S', [".###", "#...", "#...", ".##.", "...#", "###."]),
+    ('U', ["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('Y', ["#...#", "#...#", ".#.#.", "..#..", "..#..", "..#.."]),
+    ('Z', ["####", "...#", "..#.", ".#..", "#...", "####"]),
+];
+
+fn is_lit(pixels: &Grid<bool>, x: usize, y: usize) -> bool {
+    pixels.get(Point::new(x as i32, y as i32)) == Some(&true)
+}
+
+fn is_blank_column(pixels: &Grid<bool>, x: usize) -> bool {
+    pixels.column(x).all(|&lit| !lit)
+}
+
+// The letter whose glyph exactly fills the cell starting at column x, with
+// the columns past a narrow glyph blank
+fn read_letter(pixels: &Grid<bool>, x: usize, top: usize) -> Option<char> {
+    GLYPHS.iter().find_map(|(letter, glyph)| {
+        let matches = glyph.iter().enumerate().all(|(dy, row)| {
+            (0..PITCH).all(|dx| {
+                let expected = row.as_bytes().get(dx) == Some(&b'#');
+                is_lit(pixels, x + dx, top + dy) == expected
+            })
+        });
+        if matches {
+            Some(*letter)
+        } else {
+            None
+        }
+    })
+}
+
+// Read the letters, the first starting at column start, and the rest every
+// PITCH columns until the picture is blank
+fn read_from(pixels: &Grid<bool>, start: usize, top: usize) -> Option<String> {
+    if !(0..start).all(|x| is_blank_column(pixels, x)) {
+        return None;
+    }
+
+    let mut letters = String::new();
+    let mut x = start;
+    while x < pixels.width() {
+        if (x..pixels.width()).all(|x| is_blank_column(pixels, x)) {
+            break;
+        }
+        letters.push(read_letter(pixels, x, top)?);
+        x += PITCH;
+    }
+    if letters.is_empty() {
+        None
+    } else {
+        Some(letters)
+    }
+}
+
+// The letters drawn by the lit pixels, if every one of them can be read
+pub fn read(pixels: &Grid<bool>) -> Option<String> {
+    let lit_rows: Vec<usize> = (0..pixels.height())
+        .filter(|&y| pixels.row(y).contains(&true))
+        .collect();
+    let (top, bottom) = (*lit_rows.first()?, *lit_rows.last()?);
+    if bottom - top + 1 != HEIGHT {
+        return None;
+    }
+
+    // There may be a margin on the left, so try the first letter in each
+    // column it could start in
+    (0..PITCH).find_map(|start| read_from(pixels, start, top))
+}
+
+// The letters as text when they can be read, or the picture drawn with lit
+// as the answer for a person to read
+pub fn read_or_render(pixels: &Grid<bool>, lit: char) -> Answer {
+    match read(pixels) {
+        Some(letters) => Answer::Text(letters),
+        None => Answer::Rendered(pixels.render(|&on| if on { lit } else { ' ' })),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pixels(picture: &str) -> Grid<bool> {
+        Grid::parse(picture).unwrap().map(|&c| c == '#')
+    }
+
+    #[test]
+    fn reads_every_letter() {
+        for (letter, glyph) in GLYPHS.iter() {
+            let picture = glyph.join("\n");
+            assert_eq!(read(&pixels(&picture)), Some(letter.to_string()));
+        }
+    }
+
+    #[test]
+    fn reads_words_with_margins() {
+        let picture = "
+            .......................
+            .###..#..#.#...#####...
+            .#..#.#..#.#...#...#...
+            .#..#.####..#.#...#....
+            .###..#..#...#...#.....
+            .#.#..#..#...#..#......
+            .#..#.#..#...#..####...
+            .......................";
+        assert_eq!(read(&pixels(picture)), Some("RHYZ".to_string()));
+
+        // The I's first column is blank
+        let picture = "
+            .###.#..#
+            ..#..#..#
+            ..#..####
+            ..#..#..#
+            ..#..#..#
+            .###.#..#";
+        assert_eq!(read(&pixels(picture)), Some("IH".to_string()));
+    }
+
+    #[test]
+    fn gives_up_on_anything_else() {
+        assert_eq!(read(&pixels("#.\n.#")), None);
+        let smudged = "
+            .##..
+            #..#.
+            #..#.
+            ####.
+            #..##
+            #..#.";
+        assert_eq!(read(&pixels(smudged)), None);
+        assert_eq!(
+            read_or_render(&pixels("#.\n.#"), 'x'),
+            Answer::Rendered("x \n x\n".to_string())
+        );
+    }
+}
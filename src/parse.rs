@@ -0,0 +1,159 @@
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use regex::{Captures, Regex};
+
+// Why part of an input couldn't be parsed
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Reason {
+    // A field that should have been a number
+    NotANumber(String),
+    // Something that isn't any of the forms it could take
+    Unexpected { expected: String, found: String },
+    // A part that isn't there at all, like a separator
+    Missing(String),
+}
+
+impl fmt::Display for Reason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Reason::NotANumber(field) => write!(f, "'{}' isn't a number", field),
+            Reason::Unexpected { expected, found } => {
+                write!(f, "expected {}, found '{}'", expected, found)
+            }
+            Reason::Missing(what) => write!(f, "missing {}", what),
+        }
+    }
+}
+
+// Why an input couldn't be parsed, and the line (and column, where it helps)
+// it went wrong on, both counting from 1
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: Option<usize>,
+    pub reason: Reason,
+}
+
+impl ParseError {
+    pub fn at(line: usize, column: usize, reason: Reason) -> ParseError {
+        ParseError {
+            line,
+            column: Some(column),
+            reason,
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.column {
+            Some(column) => write!(f, "line {}, column {}: {}", self.line, column, self.reason),
+            None => write!(f, "line {}: {}", self.line, self.reason),
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+// Parse every line that isn't blank, trimmed, with parse_line. The first
+// line that fails is reported with its line number.
+pub fn lines<'a, T, F>(input: &'a str, parse_line: F) -> Result<Vec<T>, ParseError>
+where
+    F: FnMut(&'a str) -> Result<T, Reason>,
+{
+    let numbered = numbered_lines(input, parse_line)?;
+    Ok(numbered.into_iter().map(|(_, parsed)| parsed).collect())
+}
+
+// The same, keeping each line's number with what was parsed from it, for
+// checks that can only be made once every line has been read
+pub fn numbered_lines<'a, T, F>(
+    input: &'a str,
+    mut parse_line: F,
+) -> Result<Vec<(usize, T)>, ParseError>
+where
+    F: FnMut(&'a str) -> Result<T, Reason>,
+{
+    input
+        .lines()
+        .enumerate()
+        .map(|(l, line)| (l + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty())
+        .map(|(l, line)| match parse_line(line) {
+            Ok(parsed) => Ok((l, parsed)),
+            Err(reason) => Err(ParseError {
+                line: l,
+                column: None,
+                reason,
+            }),
+        })
+        .collect()
+}
+
+// A number, such as an i32 or a u64, with spaces around it ignored
+pub fn number<T: FromStr>(field: &str) -> Result<T, Reason> {
+    field
+        .trim()
+        .parse()
+        .map_err(|_| Reason::NotANumber(field.trim().to_string()))
+}
+
+// The parts of s either side of the first separator
+pub fn split_once<'a>(s: &'a str, separator: &str) -> Result<(&'a str, &'a str), Reason> {
+    s.split_once(separator)
+        .ok_or_else(|| Reason::Missing(format!("'{}' in '{}'", separator, s)))
+}
+
+// The groups of a regex that has to match s, where expected describes what
+// it matches for the error if it doesn't
+pub fn captures<'t>(re: &Regex, s: &'t str, expected: &str) -> Result<Captures<'t>, Reason> {
+    re.captures(s).ok_or_else(|| Reason::Unexpected {
+        expected: expected.to_string(),
+        found: s.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbers_every_line() {
+        let input = "12\n\n  -7 \n14\n";
+        assert_eq!(lines(input, number::<i32>), Ok(vec![12, -7, 14]));
+
+        let error = lines("12\n\n1x4\n", number::<i32>).unwrap_err();
+        assert_eq!(error.line, 3);
+        assert_eq!(error.reason, Reason::NotANumber("1x4".to_string()));
+        assert_eq!(error.to_string(), "line 3: '1x4' isn't a number");
+
+        assert_eq!(
+            numbered_lines(input, number::<i32>),
+            Ok(vec![(1, 12), (3, -7), (4, 14)])
+        );
+    }
+
+    #[test]
+    fn splits_and_matches() {
+        assert_eq!(split_once("COM)B", ")"), Ok(("COM", "B")));
+        assert_eq!(
+            split_once("COM-B", ")").unwrap_err().to_string(),
+            "missing ')' in 'COM-B'"
+        );
+
+        let re = Regex::new(r"^cut (-?\d+)$").unwrap();
+        assert_eq!(&captures(&re, "cut -4", "a cut").unwrap()[1], "-4");
+        assert_eq!(
+            captures(&re, "cut it", "a cut").unwrap_err().to_string(),
+            "expected a cut, found 'cut it'"
+        );
+    }
+
+    #[test]
+    fn points_at_a_column() {
+        let error = ParseError::at(2, 5, Reason::Missing("a step".to_string()));
+        assert_eq!(error.to_string(), "line 2, column 5: missing a step");
+    }
+}
@@ -0,0 +1,130 @@
+use std::any::Any;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+
+// Run f over every item on a pool of threads. The results are handed to done
+// in the items' order, each as soon as it and everything before it is ready,
+// so the output doesn't depend on which thread finishes first. If f panics,
+// that item's result is an error saying so, and the rest still run.
+pub fn run_in_order<T, R, F, D>(items: &[T], threads: usize, f: F, mut done: D)
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> Result<R, String> + Sync,
+    D: FnMut(&T, Result<R, String>),
+{
+    let next = AtomicUsize::new(0);
+    let (sender, receiver) = mpsc::channel::<(usize, Result<R, String>)>();
+
+    thread::scope(|scope| {
+        for _ in 0..threads.clamp(1, items.len().max(1)) {
+            let sender = sender.clone();
+            let (next, f) = (&next, &f);
+            scope.spawn(move || loop {
+                let i = next.fetch_add(1, Ordering::SeqCst);
+                if i >= items.len() {
+                    break;
+                }
+                let result = panic::catch_unwind(AssertUnwindSafe(|| f(&items[i])))
+                    .unwrap_or_else(|payload| Err(panicked(payload)));
+                if sender.send((i, result)).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(sender);
+
+        let mut results: Vec<Option<Result<R, String>>> = items.iter().map(|_| None).collect();
+        let mut next_done = 0;
+        for (i, result) in receiver {
+            results[i] = Some(result);
+            while next_done < items.len() {
+                match results[next_done].take() {
+                    Some(result) => {
+                        done(&items[next_done], result);
+                        next_done += 1;
+                    }
+                    None => break,
+                }
+            }
+        }
+    });
+}
+
+// What a panic said, which is a &str or a String unless it was started with
+// panic_any
+fn panicked(payload: Box<dyn Any + Send>) -> String {
+    let message = match payload.downcast::<String>() {
+        Ok(message) => *message,
+        Err(payload) => match payload.downcast::<&str>() {
+            Ok(message) => message.to_string(),
+            Err(_) => "an unknown error".to_string(),
+        },
+    };
+    format!("panicked: {}", message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn results_come_back_in_order() {
+        let items: Vec<u64> = (0..20).collect();
+        let mut results: Vec<(u64, u64)> = Vec::new();
+
+        // The early items are the slowest, so they finish last
+        run_in_order(
+            &items,
+            4,
+            |&i| {
+                thread::sleep(Duration::from_millis(20 - i));
+                Ok(i * i)
+            },
+            |&i, square| results.push((i, square.unwrap())),
+        );
+
+        let expected: Vec<(u64, u64)> = items.iter().map(|&i| (i, i * i)).collect();
+        assert_eq!(results, expected);
+    }
+
+    #[test]
+    fn handles_more_threads_than_items() {
+        let mut results: Vec<u32> = Vec::new();
+        run_in_order(&[1, 2], 8, |&i| Ok(i + 1), |_, r| results.push(r.unwrap()));
+        assert_eq!(results, vec![2, 3]);
+
+        run_in_order(&[], 8, |&i: &u32| Ok(i), |_, r| results.push(r.unwrap()));
+        assert_eq!(results, vec![2, 3]);
+    }
+
+    #[test]
+    fn a_panic_only_fails_its_own_item() {
+        let items: Vec<u32> = (1..=6).collect();
+        let mut results: Vec<Result<u32, String>> = Vec::new();
+        run_in_order(
+            &items,
+            3,
+            |&i| match i {
+                2 => panic!("day {} fell over", i),
+                4 => Err("bad input".to_string()),
+                _ => Ok(i * 10),
+            },
+            |_, result| results.push(result),
+        );
+        assert_eq!(
+            results,
+            vec![
+                Ok(10),
+                Err("panicked: day 2 fell over".to_string()),
+                Ok(30),
+                Err("bad input".to_string()),
+                Ok(50),
+                Ok(60),
+            ]
+        );
+    }
+}
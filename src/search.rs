@@ -0,0 +1,233 @@
+use std::cmp;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::hash::Hash;
+use std::ops;
+
+// What a search found: how far it is to every node it reached, and the node
+// each was reached from, so the path to any of them can be rebuilt. For
+// Dijkstra and A*, nodes that were reached but not yet settled when the goal
+// was found only have an upper bound on their distance.
+pub struct Search<N, C> {
+    start: N,
+    distances: HashMap<N, C>,
+    previous: HashMap<N, N>,
+    goal: Option<N>,
+}
+
+impl<N, C> Search<N, C>
+where
+    N: Eq + Hash + Clone,
+    C: Copy,
+{
+    fn new(start: N, zero: C) -> Search<N, C> {
+        let mut distances = HashMap::new();
+        distances.insert(start.clone(), zero);
+        Search {
+            start,
+            distances,
+            previous: HashMap::new(),
+            goal: None,
+        }
+    }
+
+    // The node that satisfied the goal, if the search found one
+    pub fn goal(&self) -> Option<&N> {
+        self.goal.as_ref()
+    }
+
+    pub fn distance(&self, node: &N) -> Option<C> {
+        self.distances.get(node).copied()
+    }
+
+    // Every node that was reached, with its distance from the start
+    pub fn visited(&self) -> impl Iterator<Item = (&N, C)> {
+        self.distances.iter().map(|(node, &d)| (node, d))
+    }
+
+    // The path from the start to a node, including both ends
+    pub fn path_to(&self, node: &N) -> Option<Vec<N>> {
+        if !self.distances.contains_key(node) {
+            return None;
+        }
+
+        let mut path = vec![node.clone()];
+        let mut current = node;
+        while *current != self.start {
+            current = &self.previous[current];
+            path.push(current.clone());
+        }
+        path.reverse();
+        Some(path)
+    }
+}
+
+// Breadth first search, for when every step costs the same. Stops at the
+// first node that satisfies goal, or once everything reachable has been seen.
+pub fn bfs<N, F, I, G>(start: N, mut neighbours: F, mut goal: G) -> Search<N, usize>
+where
+    N: Eq + Hash + Clone,
+    F: FnMut(&N) -> I,
+    I: IntoIterator<Item = N>,
+    G: FnMut(&N) -> bool,
+{
+    let mut search = Search::new(start.clone(), 0);
+    let mut queue: VecDeque<N> = VecDeque::new();
+    queue.push_back(start);
+
+    while let Some(node) = queue.pop_front() {
+        if goal(&node) {
+            search.goal = Some(node);
+            break;
+        }
+
+        let distance = search.distances[&node] + 1;
+        for next in neighbours(&node) {
+            if !search.distances.contains_key(&next) {
+                search.distances.insert(next.clone(), distance);
+                search.previous.insert(next.clone(), node.clone());
+                queue.push_back(next);
+            }
+        }
+    }
+    search
+}
+
+// A node waiting to be expanded, ordered so the BinaryHeap (a max-heap) pops
+// the lowest priority first
+struct Queued<N, C> {
+    priority: C,
+    cost: C,
+    node: N,
+}
+
+impl<N, C: Ord> PartialEq for Queued<N, C> {
+    fn eq(&self, other: &Self) -> bool {
+        self.priority == other.priority
+    }
+}
+
+impl<N, C: Ord> Eq for Queued<N, C> {}
+
+impl<N, C: Ord> Ord for Queued<N, C> {
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        other.priority.cmp(&self.priority)
+    }
+}
+
+impl<N, C: Ord> PartialOrd for Queued<N, C> {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// A* search: neighbours gives each next node with the cost of the step to it,
+// and heuristic estimates the cost from a node to the goal. The estimate must
+// never be too high, or the path found might not be the shortest.
+pub fn astar<N, C, F, I, H, G>(
+    start: N,
+    mut neighbours: F,
+    mut heuristic: H,
+    mut goal: G,
+) -> Search<N, C>
+where
+    N: Eq + Hash + Clone,
+    C: Copy + Ord + ops::Add<Output = C> + Default,
+    F: FnMut(&N) -> I,
+    I: IntoIterator<Item = (N, C)>,
+    H: FnMut(&N) -> C,
+    G: FnMut(&N) -> bool,
+{
+    let mut search = Search::new(start.clone(), C::default());
+    let mut queue: BinaryHeap<Queued<N, C>> = BinaryHeap::new();
+    queue.push(Queued {
+        priority: heuristic(&start),
+        cost: C::default(),
+        node: start,
+    });
+
+    while let Some(Queued { cost, node, .. }) = queue.pop() {
+        // A cheaper way here was found after this one was queued
+        if cost > search.distances[&node] {
+            continue;
+        }
+        if goal(&node) {
+            search.goal = Some(node);
+            break;
+        }
+
+        for (next, step) in neighbours(&node) {
+            let next_cost = cost + step;
+            if search.distance(&next).is_none_or(|d| next_cost < d) {
+                search.distances.insert(next.clone(), next_cost);
+                search.previous.insert(next.clone(), node.clone());
+                queue.push(Queued {
+                    priority: next_cost + heuristic(&next),
+                    cost: next_cost,
+                    node: next,
+                });
+            }
+        }
+    }
+    search
+}
+
+// Dijkstra's algorithm, which is A* without a heuristic
+pub fn dijkstra<N, C, F, I, G>(start: N, neighbours: F, goal: G) -> Search<N, C>
+where
+    N: Eq + Hash + Clone,
+    C: Copy + Ord + ops::Add<Output = C> + Default,
+    F: FnMut(&N) -> I,
+    I: IntoIterator<Item = (N, C)>,
+    G: FnMut(&N) -> bool,
+{
+    astar(start, neighbours, |_| C::default(), goal)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A line of nodes 0..10, each linked to the next, with a slow shortcut
+    // from 0 to 9
+    fn line(n: &u32) -> Vec<(u32, u32)> {
+        let mut next = Vec::new();
+        if *n < 9 {
+            next.push((n + 1, 1));
+        }
+        if *n == 0 {
+            next.push((9, 20));
+        }
+        next
+    }
+
+    #[test]
+    fn breadth_first() {
+        let unweighted = |n: &u32| line(n).into_iter().map(|(n, _)| n);
+        let search = bfs(0, unweighted, |&n| n == 9);
+        assert_eq!(search.goal(), Some(&9));
+        assert_eq!(search.distance(&9), Some(1));
+        assert_eq!(search.path_to(&9), Some(vec![0, 9]));
+
+        let search = bfs(3, unweighted, |_| false);
+        assert_eq!(search.goal(), None);
+        assert_eq!(search.visited().count(), 7);
+        assert_eq!(search.visited().map(|(_, d)| d).max(), Some(6));
+        assert_eq!(search.path_to(&5), Some(vec![3, 4, 5]));
+        assert_eq!(search.path_to(&0), None);
+    }
+
+    #[test]
+    fn cheapest_first() {
+        let search = dijkstra(0, line, |&n| n == 9);
+        assert_eq!(search.distance(&9), Some(9));
+        assert_eq!(search.path_to(&9), Some((0..10).collect()));
+
+        let search = astar(0, line, |&n| 9 - n, |&n| n == 9);
+        assert_eq!(search.goal(), Some(&9));
+        assert_eq!(search.distance(&9), Some(9));
+
+        let search = dijkstra(0, line, |&n| n == 42);
+        assert_eq!(search.goal(), None);
+        assert_eq!(search.distance(&9), Some(9));
+    }
+}
@@ -0,0 +1,215 @@
+use std::error::Error;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::animation::Frames;
+use crate::image::Image;
+
+// The answer to one part of a puzzle
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Answer {
+    Number(i128),
+    Text(String),
+    // Several lines of output, like letters drawn on a screen
+    Rendered(String),
+    // The part doesn't have a puzzle (Day 25 only has one)
+    None,
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Answer::Number(n) => write!(f, "{}", n),
+            Answer::Text(s) => write!(f, "{}", s),
+            Answer::Rendered(s) => write!(f, "\n{}", s.trim_end()),
+            Answer::None => write!(f, "-"),
+        }
+    }
+}
+
+macro_rules! answer_from_number {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for Answer {
+                fn from(n: $t) -> Answer {
+                    Answer::Number(n as i128)
+                }
+            }
+        )*
+    };
+}
+
+answer_from_number!(i32, i64, i128, u32, u64, u128, usize);
+
+impl From<String> for Answer {
+    fn from(s: String) -> Answer {
+        if s.trim_end().contains('\n') {
+            Answer::Rendered(s)
+        } else {
+            Answer::Text(s)
+        }
+    }
+}
+
+impl From<&str> for Answer {
+    fn from(s: &str) -> Answer {
+        Answer::from(s.to_string())
+    }
+}
+
+// A day's puzzle. The input is parsed once, and both parts are solved from
+// the parsed form. Interactive puzzles can also have part 1 played by hand,
+// and puzzles with a map or a screen can draw pictures of it, or send a frame
+// for each step of their simulation to be watched.
+pub trait Solution: Sized {
+    const INTERACTIVE: bool = false;
+
+    fn parse(input: &str) -> Result<Self, Box<dyn Error>>;
+    fn part1(&self) -> Answer;
+    fn part2(&self) -> Answer;
+
+    fn play(&self) -> Answer {
+        self.part1()
+    }
+
+    // Pictures of the puzzle, each with a short name to save it under
+    fn pictures(&self) -> Vec<(String, Image)> {
+        Vec::new()
+    }
+
+    fn animate(&self, _frames: Frames) {}
+}
+
+// Whether a puzzle solves itself, or is played at the terminal
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Mode {
+    Batch,
+    Interactive,
+}
+
+// A puzzle's answers, with how long parsing and each part took
+#[derive(Debug)]
+pub struct Solved {
+    pub parse: Duration,
+    pub parts: Vec<(u8, Answer, Duration)>,
+}
+
+impl Solved {
+    pub fn total(&self) -> Duration {
+        self.parse + self.parts.iter().map(|(_, _, elapsed)| *elapsed).sum()
+    }
+}
+
+// Solve the requested parts of a puzzle, or both if no part is given
+pub fn solve<S: Solution>(
+    input: &str,
+    part: Option<u8>,
+    mode: Mode,
+) -> Result<Solved, Box<dyn Error>> {
+    if mode == Mode::Interactive && !S::INTERACTIVE {
+        return Err("this puzzle can't be played interactively".into());
+    }
+
+    let start = Instant::now();
+    let solution = S::parse(input)?;
+    let parse = start.elapsed();
+
+    let mut parts: Vec<(u8, Answer, Duration)> = Vec::new();
+    if part != Some(2) {
+        let start = Instant::now();
+        let answer = match mode {
+            Mode::Batch => solution.part1(),
+            Mode::Interactive => solution.play(),
+        };
+        parts.push((1, answer, start.elapsed()));
+    }
+    if part != Some(1) {
+        let start = Instant::now();
+        let answer = solution.part2();
+        parts.push((2, answer, start.elapsed()));
+    }
+    Ok(Solved { parse, parts })
+}
+
+// Draw a puzzle's pictures
+pub fn draw<S: Solution>(input: &str) -> Result<Vec<(String, Image)>, Box<dyn Error>> {
+    Ok(S::parse(input)?.pictures())
+}
+
+// Run a puzzle's simulation, sending it frames
+pub fn animate<S: Solution>(input: &str, frames: Frames) -> Result<(), Box<dyn Error>> {
+    S::parse(input)?.animate(frames);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Sum {
+        values: Vec<i32>,
+    }
+
+    impl Solution for Sum {
+        fn parse(input: &str) -> Result<Self, Box<dyn Error>> {
+            let values = input
+                .lines()
+                .map(|line| line.parse::<i32>())
+                .collect::<Result<_, _>>()?;
+            Ok(Sum { values })
+        }
+
+        fn part1(&self) -> Answer {
+            self.values.iter().sum::<i32>().into()
+        }
+
+        fn part2(&self) -> Answer {
+            format!("{} values", self.values.len()).into()
+        }
+    }
+
+    fn answers_of(solved: Solved) -> Vec<(u8, Answer)> {
+        solved
+            .parts
+            .into_iter()
+            .map(|(part, answer, _)| (part, answer))
+            .collect()
+    }
+
+    #[test]
+    fn solves_requested_parts() {
+        let solved = solve::<Sum>("1\n2\n3\n", None, Mode::Batch).unwrap();
+        assert!(solved.total() >= solved.parse);
+        let answers = answers_of(solved);
+        assert_eq!(
+            answers,
+            vec![
+                (1, Answer::Number(6)),
+                (2, Answer::Text("3 values".to_string()))
+            ]
+        );
+
+        let answers = answers_of(solve::<Sum>("1\n2\n3\n", Some(2), Mode::Batch).unwrap());
+        assert_eq!(answers, vec![(2, Answer::Text("3 values".to_string()))]);
+    }
+
+    #[test]
+    fn reports_parse_errors() {
+        assert!(solve::<Sum>("1\ntwo\n", None, Mode::Batch).is_err());
+    }
+
+    #[test]
+    fn only_interactive_puzzles_can_be_played() {
+        assert!(solve::<Sum>("1\n", None, Mode::Interactive).is_err());
+    }
+
+    #[test]
+    fn multi_line_text_is_rendered() {
+        assert_eq!(
+            Answer::from("#..#\n#..#\n"),
+            Answer::Rendered("#..#\n#..#\n".to_string())
+        );
+        assert_eq!(Answer::from("CJZHR").to_string(), "CJZHR");
+        assert_eq!(Answer::from(42usize).to_string(), "42");
+    }
+}
@@ -0,0 +1,108 @@
+use std::fmt::Write;
+use std::time::Duration;
+
+use crate::solution::Solved;
+
+fn format_duration(d: Duration) -> String {
+    format!("{:.3}ms", d.as_secs_f64() * 1000.0)
+}
+
+// A table of how long each day took to parse and solve, most expensive first,
+// with a total at the bottom
+pub fn table(days: &[(usize, Solved)]) -> String {
+    let mut sorted: Vec<&(usize, Solved)> = days.iter().collect();
+    sorted.sort_by(|a, b| b.1.total().cmp(&a.1.total()).then(a.0.cmp(&b.0)));
+
+    let part = |solved: &Solved, part: u8| -> Option<Duration> {
+        solved
+            .parts
+            .iter()
+            .find(|(p, _, _)| *p == part)
+            .map(|(_, _, elapsed)| *elapsed)
+    };
+    let column = |d: Option<Duration>| -> String {
+        match d {
+            Some(d) => format_duration(d),
+            None => "-".to_string(),
+        }
+    };
+
+    let mut s = String::new();
+    writeln!(
+        s,
+        "{:<5} {:>12} {:>12} {:>12} {:>12}",
+        "Day", "Parse", "Part 1", "Part 2", "Total"
+    )
+    .unwrap();
+
+    let mut totals = [Duration::ZERO; 4];
+    for (day, solved) in sorted {
+        let row = [
+            Some(solved.parse),
+            part(solved, 1),
+            part(solved, 2),
+            Some(solved.total()),
+        ];
+        for (total, d) in totals.iter_mut().zip(row.iter()) {
+            *total += d.unwrap_or_default();
+        }
+        writeln!(
+            s,
+            "{:<5} {:>12} {:>12} {:>12} {:>12}",
+            day,
+            column(row[0]),
+            column(row[1]),
+            column(row[2]),
+            column(row[3])
+        )
+        .unwrap();
+    }
+
+    writeln!(
+        s,
+        "{:<5} {:>12} {:>12} {:>12} {:>12}",
+        "Total",
+        format_duration(totals[0]),
+        format_duration(totals[1]),
+        format_duration(totals[2]),
+        format_duration(totals[3])
+    )
+    .unwrap();
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solution::Answer;
+
+    fn solved(parse: u64, part1: u64, part2: Option<u64>) -> Solved {
+        let mut parts = vec![(1, Answer::None, Duration::from_millis(part1))];
+        if let Some(part2) = part2 {
+            parts.push((2, Answer::None, Duration::from_millis(part2)));
+        }
+        Solved {
+            parse: Duration::from_millis(parse),
+            parts,
+        }
+    }
+
+    #[test]
+    fn sorts_by_cost_with_a_total() {
+        let days = vec![
+            (1, solved(1, 2, Some(3))),
+            (18, solved(5, 400, Some(900))),
+            (25, solved(1, 50, None)),
+        ];
+        let table = table(&days);
+        let lines: Vec<&str> = table.lines().collect();
+
+        assert_eq!(lines.len(), 5);
+        assert!(lines[1].starts_with("18 "));
+        assert!(lines[2].starts_with("25 "));
+        assert!(lines[3].starts_with("1 "));
+        assert!(lines[2].contains(" -"));
+        assert!(lines[4].starts_with("Total"));
+        assert!(lines[4].ends_with("1362.000ms"));
+    }
+}
@@ -0,0 +1,525 @@
+// Ahead-of-time translation of Intcode programs into Rust.
+//
+// The program is explored from address 0 to find its instructions and the
+// addresses execution can enter at (leaders). Each leader becomes an arm of a
+// `match` on the instruction pointer, holding straight-line code up to the next
+// jump, output, halt or leader. Jumps just set the instruction pointer and go
+// round the dispatcher again, so computed jumps (return addresses and the like)
+// work as long as they land on a leader; anything else is handed back to the
+// interpreter.
+//
+// Programs that write into their own code with a fixed address are rejected.
+// Writes through the relative base can't be checked ahead of time, so those
+// are checked as they happen and also hand back to the interpreter.
+//
+// This file is shared with build.rs, so it can't depend on the rest of the
+// crate.
+
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::fmt;
+
+#[derive(Debug, PartialEq)]
+pub enum TranslateError {
+    Empty,
+    // The instruction at `at` writes to `address`, which holds code
+    SelfModifying { at: usize, address: usize },
+}
+
+impl fmt::Display for TranslateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TranslateError::Empty => write!(f, "program is empty"),
+            TranslateError::SelfModifying { at, address } => {
+                write!(f, "instruction at {} writes into code at {}", at, address)
+            }
+        }
+    }
+}
+
+struct Instruction {
+    opcode: i128,
+    modes: Vec<i128>,
+    operands: Vec<i128>,
+}
+
+impl Instruction {
+    fn size(&self) -> usize {
+        1 + self.operands.len()
+    }
+    // Which of the parameters are written to
+    fn writes(&self) -> Option<usize> {
+        match self.opcode {
+            1 | 2 | 7 | 8 => Some(2),
+            3 => Some(0),
+            _ => None,
+        }
+    }
+}
+
+fn decode(program: &[i128], address: usize) -> Option<Instruction> {
+    let op = program[address];
+    if op < 0 {
+        return None;
+    }
+
+    let opcode = op % 100;
+    let parameters = match opcode {
+        1 | 2 | 7 | 8 => 3,
+        3 | 4 | 9 => 1,
+        5 | 6 => 2,
+        99 => 0,
+        _ => return None,
+    };
+    if address + parameters >= program.len() {
+        return None;
+    }
+
+    let modes: Vec<i128> = (0..parameters)
+        .map(|i| (op / 10i128.pow(i as u32 + 2)) % 10)
+        .collect();
+    let instruction = Instruction {
+        opcode,
+        modes,
+        operands: program[address + 1..=address + parameters].to_vec(),
+    };
+
+    // Leave anything the interpreter would reject to the interpreter
+    for (i, mode) in instruction.modes.iter().enumerate() {
+        let valid = match instruction.writes() {
+            Some(w) if w == i => *mode == 0 || *mode == 2,
+            _ => *mode <= 2,
+        };
+        if !valid {
+            return None;
+        }
+    }
+    Some(instruction)
+}
+
+struct Analysis {
+    // Every reachable address, and the instruction there if it's valid
+    instructions: BTreeMap<usize, Option<Instruction>>,
+    leaders: BTreeSet<usize>,
+    // Every address holding part of a reachable instruction
+    code: BTreeSet<usize>,
+}
+
+fn analyse(program: &[i128]) -> Analysis {
+    let mut instructions: BTreeMap<usize, Option<Instruction>> = BTreeMap::new();
+    let mut leaders: BTreeSet<usize> = BTreeSet::new();
+    let mut computed_jumps = false;
+
+    let mut frontier: Vec<usize> = vec![0];
+    leaders.insert(0);
+
+    while let Some(start) = frontier.pop() {
+        let mut address = start;
+        while address < program.len() && !instructions.contains_key(&address) {
+            let instruction = decode(program, address);
+            let next = address + instruction.as_ref().map_or(1, |i| i.size());
+
+            let follow = match &instruction {
+                None => false,
+                Some(i) => match i.opcode {
+                    // Execution resumes here when input arrives
+                    3 => {
+                        leaders.insert(address);
+                        true
+                    }
+                    // ...and after each output
+                    4 => {
+                        if next < program.len() {
+                            leaders.insert(next);
+                        }
+                        true
+                    }
+                    5 | 6 => {
+                        if i.modes[1] == 1 {
+                            let target = i.operands[1];
+                            if target >= 0 && (target as usize) < program.len() {
+                                leaders.insert(target as usize);
+                                frontier.push(target as usize);
+                            }
+                        } else {
+                            computed_jumps = true;
+                        }
+                        if next < program.len() {
+                            leaders.insert(next);
+                        }
+                        true
+                    }
+                    99 => false,
+                    _ => true,
+                },
+            };
+
+            instructions.insert(address, instruction);
+            if !follow {
+                break;
+            }
+            address = next;
+        }
+    }
+
+    // Computed jumps usually go to addresses the program stored as immediate
+    // values (return addresses), so any of those that start an instruction
+    // become leaders too.
+    if computed_jumps {
+        let mut targets: Vec<usize> = Vec::new();
+        for i in instructions.values().flatten() {
+            for (mode, operand) in i.modes.iter().zip(&i.operands) {
+                if *mode == 1 && *operand >= 0 {
+                    targets.push(*operand as usize);
+                }
+            }
+        }
+        for t in targets {
+            if let Some(Some(_)) = instructions.get(&t) {
+                leaders.insert(t);
+            }
+        }
+    }
+
+    let mut code: BTreeSet<usize> = BTreeSet::new();
+    for (address, i) in &instructions {
+        if let Some(i) = i {
+            code.extend(*address..*address + i.size());
+        }
+    }
+
+    Analysis {
+        instructions,
+        leaders,
+        code,
+    }
+}
+
+fn read(mode: i128, operand: i128) -> String {
+    match mode {
+        0 => format!("read(memory, {})", operand as usize),
+        1 => format!("{}", operand),
+        _ => format!("read(memory, (*relative_base + {}) as usize)", operand),
+    }
+}
+
+// Emit the write of `value` to a parameter, falling back to the interpreter
+// if a relative write would land in code.
+fn write(code: &mut String, at: usize, mode: i128, operand: i128, value: &str, name: &str) {
+    match mode {
+        0 => {
+            code.push_str(&format!(
+                "                let value = {};\n                write(memory, {}, value);\n",
+                value, operand as usize
+            ));
+        }
+        _ => {
+            code.push_str(&format!(
+                "                let address = (*relative_base + {}) as usize;\n",
+                operand
+            ));
+            code.push_str(&format!(
+                "                if {}_is_code(address) {{\n                    *ip = {};\n                    return NativeStep::Interpret;\n                }}\n",
+                name, at
+            ));
+            code.push_str(&format!(
+                "                let value = {};\n                write(memory, address, value);\n",
+                value
+            ));
+        }
+    }
+}
+
+// Translate a program into a function with the given name, along with a
+// helper that tells it which addresses hold code.
+pub fn translate(program: &[i128], name: &str) -> Result<String, TranslateError> {
+    if program.is_empty() {
+        return Err(TranslateError::Empty);
+    }
+
+    let analysis = analyse(program);
+
+    for (address, i) in &analysis.instructions {
+        if let Some(i) = i {
+            if let Some(w) = i.writes() {
+                let target = i.operands[w];
+                if i.modes[w] == 0 && target >= 0 && analysis.code.contains(&(target as usize)) {
+                    return Err(TranslateError::SelfModifying {
+                        at: *address,
+                        address: target as usize,
+                    });
+                }
+            }
+        }
+    }
+
+    let mut code = String::new();
+    code.push_str(&format!(
+        "pub fn {}(\n    memory: &mut Vec<i128>,\n    ip: &mut usize,\n    relative_base: &mut i128,\n    input: &mut Vec<i128>,\n) -> NativeStep {{\n    loop {{\n        match *ip {{\n",
+        name
+    ));
+
+    for leader in &analysis.leaders {
+        if let Some(None) | None = analysis.instructions.get(leader) {
+            continue;
+        }
+
+        code.push_str(&format!("            {} => {{\n", leader));
+
+        let mut address = *leader;
+        loop {
+            if address != *leader && analysis.leaders.contains(&address) {
+                code.push_str(&format!(
+                    "                *ip = {};\n                continue;\n",
+                    address
+                ));
+                break;
+            }
+
+            let i = match analysis.instructions.get(&address) {
+                Some(Some(i)) => i,
+                _ => {
+                    code.push_str(&format!(
+                        "                *ip = {};\n                return NativeStep::Interpret;\n",
+                        address
+                    ));
+                    break;
+                }
+            };
+            let next = address + i.size();
+            let a = || read(i.modes[0], i.operands[0]);
+            let b = || read(i.modes[1], i.operands[1]);
+
+            match i.opcode {
+                1 => {
+                    let value = format!("{} + {}", a(), b());
+                    write(&mut code, address, i.modes[2], i.operands[2], &value, name);
+                }
+                2 => {
+                    let value = format!("{} * {}", a(), b());
+                    write(&mut code, address, i.modes[2], i.operands[2], &value, name);
+                }
+                3 => {
+                    code.push_str(&format!(
+                        "                if input.is_empty() {{\n                    *ip = {};\n                    return NativeStep::WaitForInput;\n                }}\n",
+                        address
+                    ));
+                    write(
+                        &mut code,
+                        address,
+                        i.modes[0],
+                        i.operands[0],
+                        "input.remove(0)",
+                        name,
+                    );
+                }
+                4 => {
+                    code.push_str(&format!(
+                        "                let value = {};\n                *ip = {};\n                return NativeStep::Output(value);\n",
+                        a(),
+                        next
+                    ));
+                    break;
+                }
+                5 | 6 => {
+                    let condition = if i.opcode == 5 { "!=" } else { "==" };
+                    let target = if i.modes[1] == 1 {
+                        format!("{}", i.operands[1] as usize)
+                    } else {
+                        format!("{} as usize", b())
+                    };
+                    code.push_str(&format!(
+                        "                if {} {} 0 {{\n                    *ip = {};\n                    continue;\n                }}\n",
+                        a(),
+                        condition,
+                        target
+                    ));
+                }
+                7 => {
+                    let value = format!("({} < {}) as i128", a(), b());
+                    write(&mut code, address, i.modes[2], i.operands[2], &value, name);
+                }
+                8 => {
+                    let value = format!("({} == {}) as i128", a(), b());
+                    write(&mut code, address, i.modes[2], i.operands[2], &value, name);
+                }
+                9 => {
+                    code.push_str(&format!("                *relative_base += {};\n", a()));
+                }
+                _ => {
+                    code.push_str(&format!(
+                        "                *ip = {};\n                return NativeStep::Terminated;\n",
+                        address
+                    ));
+                    break;
+                }
+            }
+            address = next;
+        }
+
+        code.push_str("            }\n");
+    }
+
+    code.push_str("            _ => return NativeStep::Interpret,\n        }\n    }\n}\n\n");
+
+    // Contiguous ranges of code, for checking relative writes
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for address in &analysis.code {
+        match ranges.last_mut() {
+            Some(r) if r.1 + 1 == *address => r.1 = *address,
+            _ => ranges.push((*address, *address)),
+        }
+    }
+    let ranges: Vec<String> = ranges
+        .iter()
+        .map(|(start, end)| format!("{}..={}", start, end))
+        .collect();
+    code.push_str(&format!(
+        "fn {}_is_code(address: usize) -> bool {{\n    matches!(address, {})\n}}\n",
+        name,
+        ranges.join(" | ")
+    ));
+
+    Ok(code)
+}
+
+// Translate several programs into one module, exposing them through a
+// `PROGRAMS` table of each program and its native version. The samples are
+// only there to test the translator, so they go in a `SAMPLES` table of their
+// own that's only compiled into the tests. Programs that can't be translated
+// are left out and returned alongside, by name.
+pub fn module(
+    programs: &[Vec<i128>],
+    samples: &[Vec<i128>],
+) -> (String, Vec<(String, TranslateError)>) {
+    let mut code = String::new();
+    let mut errors: Vec<(String, TranslateError)> = Vec::new();
+
+    code.push_str(
+        "// Generated by build.rs from src/translate.rs\n\nuse super::{NativeProgram, NativeStep};\n\n",
+    );
+    code.push_str("fn read(memory: &[i128], address: usize) -> i128 {\n    if address < memory.len() {\n        memory[address]\n    } else {\n        0\n    }\n}\n\n");
+    code.push_str("fn write(memory: &mut Vec<i128>, address: usize, value: i128) {\n    if address >= memory.len() {\n        memory.resize(address + 1, 0);\n    }\n    memory[address] = value;\n}\n\n");
+
+    table(&mut code, &mut errors, programs, "program", "PROGRAMS");
+
+    code.push_str("#[cfg(test)]\npub mod samples {\nuse super::*;\n\n");
+    table(&mut code, &mut errors, samples, "sample", "SAMPLES");
+    code.push_str("}\n");
+
+    (code, errors)
+}
+
+// Translate each program, naming them prefix_0, prefix_1 and so on, followed
+// by a table of the ones that could be translated
+fn table(
+    code: &mut String,
+    errors: &mut Vec<(String, TranslateError)>,
+    programs: &[Vec<i128>],
+    prefix: &str,
+    table_name: &str,
+) {
+    let mut table: Vec<String> = Vec::new();
+
+    for (n, program) in programs.iter().enumerate() {
+        let name = format!("{}_{}", prefix, n);
+        match translate(program, &name) {
+            Ok(translated) => {
+                let values: Vec<String> = program.iter().map(|v| v.to_string()).collect();
+                code.push_str(&translated);
+                code.push_str(&format!(
+                    "\nconst {}: [i128; {}] = [{}];\n\n",
+                    name.to_uppercase(),
+                    program.len(),
+                    values.join(", ")
+                ));
+                table.push(format!("    (&{}, {}),\n", name.to_uppercase(), name));
+            }
+            Err(e) => errors.push((name, e)),
+        }
+    }
+
+    code.push_str(&format!(
+        "pub const {}: &[(&[i128], NativeProgram)] = &[\n",
+        table_name
+    ));
+    for t in table {
+        code.push_str(&t);
+    }
+    code.push_str("];\n");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_self_modifying_programs() {
+        // Input is written over the jump's condition
+        let program = vec![3, 3, 1105, -1, 9, 1101, 0, 0, 12, 4, 12, 99, 1];
+        assert_eq!(
+            translate(&program, "p"),
+            Err(TranslateError::SelfModifying { at: 0, address: 3 })
+        );
+
+        // Writes to data are fine
+        let program = vec![1101, 1, 2, 5, 99, 0];
+        assert!(translate(&program, "p").is_ok());
+
+        assert_eq!(translate(&[], "p"), Err(TranslateError::Empty));
+    }
+
+    #[test]
+    fn finds_leaders() {
+        let program = vec![
+            109, 100, // rb = 100
+            21101, 9, 0, 0, // push the return address
+            1105, 1, 20, // call
+            4, 50, // 9: output [50]
+            99, 0, 0, 0, 0, 0, 0, 0, 0, //
+            1101, 40, 2, 50, // 20: [50] = 42
+            2106, 0, 0, // return
+        ];
+        let analysis = analyse(&program);
+        let leaders: Vec<usize> = analysis.leaders.iter().cloned().collect();
+
+        // The start, the call's target and its fall through (which is also
+        // the return address), and the instruction after the output. The
+        // return is a computed jump, so the immediate 2 at 22 might be a
+        // target too.
+        assert_eq!(leaders, vec![0, 2, 9, 11, 20]);
+        assert!(analysis.code.contains(&26));
+        assert!(!analysis.code.contains(&15));
+    }
+
+    #[test]
+    fn leaves_invalid_instructions_to_the_interpreter() {
+        // Jumps to an unknown opcode, which only the interpreter can report
+        let program = vec![1105, 1, 4, 99, 42];
+        let translated = translate(&program, "p").unwrap();
+        assert!(translated.contains("            3 => {"));
+        assert!(!translated.contains("            4 => {"));
+        assert!(translated.contains("_ => return NativeStep::Interpret,"));
+    }
+
+    #[test]
+    fn module_lists_translated_programs() {
+        let programs = vec![vec![104, 1, 99], vec![1101, 1, 1, 0, 99]];
+        let samples = vec![vec![104, 2, 99]];
+        let (code, errors) = module(&programs, &samples);
+        assert!(code.contains("(&PROGRAM_0, program_0)"));
+        assert!(!code.contains("(&PROGRAM_1, program_1)"));
+        assert_eq!(
+            errors,
+            vec![(
+                "program_1".to_string(),
+                TranslateError::SelfModifying { at: 0, address: 0 }
+            )]
+        );
+
+        // The samples are kept apart, for the tests only
+        let (programs, samples) = code.split_at(code.find("#[cfg(test)]").unwrap());
+        assert!(!programs.contains("sample_0"));
+        assert!(samples.contains("(&SAMPLE_0, sample_0)"));
+    }
+}